@@ -0,0 +1,464 @@
+use std::fmt;
+
+use crate::computer::chip::cpu::instructions::{CpuInstructions, ParseInstructionError};
+use crate::computer::chip::memory::Rom;
+
+use self::symbol_table::SymbolTable;
+
+pub mod symbol_table;
+
+const MAX_ADDRESS: u16 = 0x7FFF;
+
+#[derive(Debug, PartialEq)]
+pub enum AssemblerError {
+    InvalidSymbol {
+        line: usize,
+        symbol: String,
+    },
+    InvalidConstant {
+        line: usize,
+        constant: String,
+    },
+    DuplicateLabel {
+        line: usize,
+        label: String,
+    },
+    /// A label named like `R0` or `SCREEN`, which already have addresses.
+    PredefinedLabel {
+        line: usize,
+        label: String,
+    },
+    InvalidComputation {
+        line: usize,
+        comp: String,
+    },
+    InvalidDestination {
+        line: usize,
+        dest: String,
+    },
+    InvalidJump {
+        line: usize,
+        jump: String,
+    },
+    ProgramTooLarge {
+        lines: usize,
+    },
+    /// A new variable when RAM 16 up to `SCREEN` is already taken.
+    TooManyVariables {
+        line: usize,
+        symbol: String,
+    },
+}
+
+impl fmt::Display for AssemblerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssemblerError::InvalidSymbol { line, symbol } => {
+                write!(f, "line {line}: invalid symbol {symbol}")
+            }
+            AssemblerError::InvalidConstant { line, constant } => {
+                write!(f, "line {line}: invalid constant {constant}")
+            }
+            AssemblerError::DuplicateLabel { line, label } => {
+                write!(f, "line {line}: label {label} is already defined")
+            }
+            AssemblerError::PredefinedLabel { line, label } => {
+                write!(f, "line {line}: label {label} is a predefined symbol")
+            }
+            AssemblerError::InvalidComputation { line, comp } => {
+                write!(f, "line {line}: invalid computation {comp}")
+            }
+            AssemblerError::InvalidDestination { line, dest } => {
+                write!(f, "line {line}: invalid destination {dest}")
+            }
+            AssemblerError::InvalidJump { line, jump } => {
+                write!(f, "line {line}: invalid jump {jump}")
+            }
+            AssemblerError::ProgramTooLarge { lines } => write!(
+                f,
+                "program has {lines} instructions, the ROM holds {}",
+                Rom::SIZE
+            ),
+            AssemblerError::TooManyVariables { line, symbol } => {
+                write!(f, "line {line}: no RAM left for variable {symbol}")
+            }
+        }
+    }
+}
+
+enum Statement<'a> {
+    Address(&'a str),
+    Compute(&'a str),
+}
+
+impl AssemblerError {
+    fn from_parse(line: usize, err: ParseInstructionError) -> Self {
+        match err {
+            ParseInstructionError::InvalidAddress(constant) => {
+                AssemblerError::InvalidConstant { line, constant }
+            }
+            ParseInstructionError::InvalidComputation(comp) => {
+                AssemblerError::InvalidComputation { line, comp }
+            }
+            ParseInstructionError::InvalidDestination(dest) => {
+                AssemblerError::InvalidDestination { line, dest }
+            }
+            ParseInstructionError::InvalidJump(jump) => AssemblerError::InvalidJump { line, jump },
+        }
+    }
+}
+
+/// Assembles Hack assembly source into the words expected by
+/// `Computer::load_program`.
+pub fn assemble(source: &str) -> Result<Vec<u16>, AssemblerError> {
+    Ok(parse(source)?.into_iter().map(u16::from).collect())
+}
+
+/// Runs both assembler passes and returns the decoded instructions, with every
+/// label and variable already resolved to an address.
+pub fn parse(source: &str) -> Result<Vec<CpuInstructions>, AssemblerError> {
+    let mut symbols = SymbolTable::new();
+    let mut statements = Vec::new();
+
+    // First pass: bind every `(LABEL)` to the ROM address of the next instruction.
+    for (idx, raw) in source.lines().enumerate() {
+        let line = idx + 1;
+        let text = clean(raw);
+
+        if text.is_empty() {
+            continue;
+        }
+
+        if let Some(label) = text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+            if !is_symbol(label) {
+                return Err(AssemblerError::InvalidSymbol {
+                    line,
+                    symbol: label.to_string(),
+                });
+            }
+            if SymbolTable::is_predefined(label) {
+                return Err(AssemblerError::PredefinedLabel {
+                    line,
+                    label: label.to_string(),
+                });
+            }
+            if symbols.contains(label) {
+                return Err(AssemblerError::DuplicateLabel {
+                    line,
+                    label: label.to_string(),
+                });
+            }
+            symbols.add_label(label, statements.len() as u16);
+            continue;
+        }
+
+        statements.push((line, split(text)));
+
+        if statements.len() > Rom::SIZE {
+            return Err(AssemblerError::ProgramTooLarge {
+                lines: statements.len(),
+            });
+        }
+    }
+
+    // Second pass: resolve symbols, allocating variables from RAM 16 upwards.
+    let mut program = Vec::with_capacity(statements.len());
+
+    for (line, statement) in statements {
+        let instruction = match statement {
+            Statement::Address(value) => {
+                CpuInstructions::Ainstruction(address(value, line, &mut symbols)?)
+            }
+            Statement::Compute(text) => text
+                .parse::<CpuInstructions>()
+                .map_err(|err| AssemblerError::from_parse(line, err))?,
+        };
+        program.push(instruction);
+    }
+
+    Ok(program)
+}
+
+fn clean(line: &str) -> &str {
+    let code = match line.find("//") {
+        Some(idx) => &line[..idx],
+        None => line,
+    };
+    code.trim()
+}
+
+fn split(text: &str) -> Statement<'_> {
+    if let Some(value) = text.strip_prefix('@') {
+        return Statement::Address(value.trim());
+    }
+
+    Statement::Compute(text)
+}
+
+fn is_symbol(symbol: &str) -> bool {
+    let mut chars = symbol.chars();
+    match chars.next() {
+        Some(first) if !first.is_ascii_digit() => symbol_char(first) && chars.all(symbol_char),
+        _ => false,
+    }
+}
+
+fn symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '$' | ':')
+}
+
+fn address(value: &str, line: usize, symbols: &mut SymbolTable) -> Result<u16, AssemblerError> {
+    if value.starts_with(|c: char| c.is_ascii_digit()) {
+        return match value.parse::<u16>() {
+            Ok(constant) if constant <= MAX_ADDRESS => Ok(constant),
+            _ => Err(AssemblerError::InvalidConstant {
+                line,
+                constant: value.to_string(),
+            }),
+        };
+    }
+
+    if !is_symbol(value) {
+        return Err(AssemblerError::InvalidSymbol {
+            line,
+            symbol: value.to_string(),
+        });
+    }
+
+    symbols
+        .resolve(value)
+        .ok_or_else(|| AssemblerError::TooManyVariables {
+            line,
+            symbol: value.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::chip::cpu::computation::Computation;
+    use crate::computer::chip::cpu::instructions::{Destination, Jump};
+
+    const ADD: &str = "
+        // Computes R0 = 2 + 3  (R0 refers to RAM[0])
+        @2
+        D=A
+        @3
+        D=D+A
+        @0
+        M=D
+    ";
+
+    const MAX: &str = "
+        // Computes R2 = max(R0, R1)
+        @R0
+        D=M              // D = first number
+        @R1
+        D=D-M            // D = first number - second number
+        @OUTPUT_FIRST
+        D;JGT            // if D>0 (first is greater) goto output_first
+        @R1
+        D=M              // D = second number
+        @OUTPUT_D
+        0;JMP            // goto output_d
+    (OUTPUT_FIRST)
+        @R0
+        D=M              // D = first number
+    (OUTPUT_D)
+        @R2
+        M=D              // M[2] = D (greatest number)
+    (INFINITE_LOOP)
+        @INFINITE_LOOP
+        0;JMP            // infinite loop
+    ";
+
+    #[test]
+    fn test_assemble_add() -> Result<(), AssemblerError> {
+        assert_eq!(
+            assemble(ADD)?,
+            vec![
+                0b0000000000000010,
+                0b1110110000010000,
+                0b0000000000000011,
+                0b1110000010010000,
+                0b0000000000000000,
+                0b1110001100001000,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_assemble_max() -> Result<(), AssemblerError> {
+        assert_eq!(
+            assemble(MAX)?,
+            vec![
+                0b0000000000000000,
+                0b1111110000010000,
+                0b0000000000000001,
+                0b1111010011010000,
+                0b0000000000001010,
+                0b1110001100000001,
+                0b0000000000000001,
+                0b1111110000010000,
+                0b0000000000001100,
+                0b1110101010000111,
+                0b0000000000000000,
+                0b1111110000010000,
+                0b0000000000000010,
+                0b1110001100001000,
+                0b0000000000001110,
+                0b1110101010000111,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_variables_and_symbols() -> Result<(), AssemblerError> {
+        let source = "
+            @i
+            M=1
+            @sum
+            M=0
+            @i
+            D=M
+            @SCREEN
+            AMD=D+1;JNE
+            @KBD
+        ";
+
+        assert_eq!(
+            parse(source)?,
+            vec![
+                CpuInstructions::Ainstruction(16),
+                CpuInstructions::CInstruction {
+                    comp: Computation::One,
+                    dest: Destination::from(0b001),
+                    jump: Jump::Null,
+                },
+                CpuInstructions::Ainstruction(17),
+                CpuInstructions::CInstruction {
+                    comp: Computation::Zero,
+                    dest: Destination::from(0b001),
+                    jump: Jump::Null,
+                },
+                CpuInstructions::Ainstruction(16),
+                CpuInstructions::CInstruction {
+                    comp: Computation::M,
+                    dest: Destination::from(0b010),
+                    jump: Jump::Null,
+                },
+                CpuInstructions::Ainstruction(16384),
+                CpuInstructions::CInstruction {
+                    comp: Computation::DPlusOne,
+                    dest: Destination::from(0b111),
+                    jump: Jump::Jne,
+                },
+                CpuInstructions::Ainstruction(24576),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_assemble_d_and_m() -> Result<(), AssemblerError> {
+        assert_eq!(
+            assemble("D=D&M\nM=M&D")?,
+            vec![0b1111000000010000, 0b1111000000001000]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        struct Test<'a> {
+            source: &'a str,
+            expected: AssemblerError,
+        }
+        let too_large = "D=0\n".repeat(16385);
+        let too_many_variables: String = (0..16369).map(|idx| format!("@v{idx}\n")).collect();
+        let tests = vec![
+            Test {
+                source: "@32768",
+                expected: AssemblerError::InvalidConstant {
+                    line: 1,
+                    constant: "32768".to_string(),
+                },
+            },
+            Test {
+                source: "@1abc",
+                expected: AssemblerError::InvalidConstant {
+                    line: 1,
+                    constant: "1abc".to_string(),
+                },
+            },
+            Test {
+                source: "@foo-bar",
+                expected: AssemblerError::InvalidSymbol {
+                    line: 1,
+                    symbol: "foo-bar".to_string(),
+                },
+            },
+            Test {
+                source: "(LOOP)\n@LOOP\n(LOOP)",
+                expected: AssemblerError::DuplicateLabel {
+                    line: 3,
+                    label: "LOOP".to_string(),
+                },
+            },
+            Test {
+                source: "@R0\n(R0)",
+                expected: AssemblerError::PredefinedLabel {
+                    line: 2,
+                    label: "R0".to_string(),
+                },
+            },
+            Test {
+                source: "@0\nD=D*A",
+                expected: AssemblerError::InvalidComputation {
+                    line: 2,
+                    comp: "D*A".to_string(),
+                },
+            },
+            Test {
+                source: "DD=A",
+                expected: AssemblerError::InvalidDestination {
+                    line: 1,
+                    dest: "DD".to_string(),
+                },
+            },
+            Test {
+                source: &too_large,
+                expected: AssemblerError::ProgramTooLarge { lines: 16385 },
+            },
+            Test {
+                source: &too_many_variables,
+                expected: AssemblerError::TooManyVariables {
+                    line: 16369,
+                    symbol: "v16368".to_string(),
+                },
+            },
+            Test {
+                source: "0;JUMP",
+                expected: AssemblerError::InvalidJump {
+                    line: 1,
+                    jump: "JUMP".to_string(),
+                },
+            },
+        ];
+
+        for Test { source, expected } in tests {
+            assert_eq!(assemble(source), Err(expected))
+        }
+
+        assert_eq!(
+            assemble("(R0)").unwrap_err().to_string(),
+            "line 1: label R0 is a predefined symbol"
+        );
+        assert_eq!(
+            assemble(&"D=0\n".repeat(16385)).unwrap_err().to_string(),
+            "program has 16385 instructions, the ROM holds 16384"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+const VARIABLE_BASE: u16 = 16;
+/// Variables live below the screen memory map at `SCREEN`.
+const VARIABLE_END: u16 = 16384;
+
+/// The predefined symbols other than `R0` to `R15`.
+const PREDEFINED: [(&str, u16); 7] = [
+    ("SP", 0),
+    ("LCL", 1),
+    ("ARG", 2),
+    ("THIS", 3),
+    ("THAT", 4),
+    ("SCREEN", 16384),
+    ("KBD", 24576),
+];
+
+pub struct SymbolTable {
+    symbols: HashMap<String, u16>,
+    next_variable: u16,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        let mut symbols = HashMap::new();
+
+        for (name, address) in PREDEFINED {
+            symbols.insert(name.to_string(), address);
+        }
+
+        for register in 0..16 {
+            symbols.insert(format!("R{register}"), register);
+        }
+
+        SymbolTable {
+            symbols,
+            next_variable: VARIABLE_BASE,
+        }
+    }
+
+    /// Whether `symbol` is bound in every program before any label.
+    pub fn is_predefined(symbol: &str) -> bool {
+        PREDEFINED.iter().any(|(name, _)| *name == symbol)
+            || (0..16).any(|register| format!("R{register}") == symbol)
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.symbols.contains_key(symbol)
+    }
+
+    pub fn add_label(&mut self, symbol: &str, address: u16) {
+        self.symbols.insert(symbol.to_string(), address);
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<u16> {
+        self.symbols.get(symbol).copied()
+    }
+
+    /// Returns the address bound to `symbol`, allocating the next free RAM
+    /// slot starting at 16 if it is a new variable. Returns `None` once the
+    /// variables would reach `SCREEN`.
+    pub fn resolve(&mut self, symbol: &str) -> Option<u16> {
+        if let Some(address) = self.get(symbol) {
+            return Some(address);
+        }
+        if self.next_variable == VARIABLE_END {
+            return None;
+        }
+
+        let address = self.next_variable;
+        self.symbols.insert(symbol.to_string(), address);
+        self.next_variable += 1;
+        Some(address)
+    }
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        SymbolTable::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_predefined_symbols() {
+        let table = SymbolTable::new();
+
+        assert_eq!(table.get("SP"), Some(0));
+        assert_eq!(table.get("LCL"), Some(1));
+        assert_eq!(table.get("ARG"), Some(2));
+        assert_eq!(table.get("THIS"), Some(3));
+        assert_eq!(table.get("THAT"), Some(4));
+        assert_eq!(table.get("R0"), Some(0));
+        assert_eq!(table.get("R15"), Some(15));
+        assert_eq!(table.get("SCREEN"), Some(16384));
+        assert_eq!(table.get("KBD"), Some(24576));
+        assert_eq!(table.get("R16"), None);
+
+        assert!(SymbolTable::is_predefined("R15"));
+        assert!(SymbolTable::is_predefined("KBD"));
+        assert!(!SymbolTable::is_predefined("R16"));
+        assert!(!SymbolTable::is_predefined("R01"));
+    }
+
+    #[test]
+    fn test_variable_allocation() {
+        let mut table = SymbolTable::new();
+
+        assert_eq!(table.resolve("i"), Some(16));
+        assert_eq!(table.resolve("sum"), Some(17));
+        assert_eq!(table.resolve("i"), Some(16));
+        assert_eq!(table.resolve("R2"), Some(2));
+    }
+
+    #[test]
+    fn test_variables_end_at_screen() {
+        let mut table = SymbolTable::new();
+
+        for variable in 16..16384 {
+            assert_eq!(table.resolve(&format!("v{variable}")), Some(variable));
+        }
+        assert_eq!(table.resolve("full"), None);
+        assert_eq!(table.resolve("v16383"), Some(16383));
+        assert_eq!(table.resolve("KBD"), Some(24576));
+    }
+}
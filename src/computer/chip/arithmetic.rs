@@ -1,35 +1,47 @@
-use crate::computer::gate::{and, or, xor};
+use crate::computer::gate::{and, and_16, chip, mux_16, not, not_16, or, or_8_way, xor, Bit};
 
-pub fn half_adder(a: bool, b: bool) -> (bool, bool) {
-    (xor(a, b), and(a, b))
+pub fn half_adder<B: Bit>(a: B, b: B) -> (B, B) {
+    chip!("HalfAdder"("a": &[a], "b": &[b]) -> ("sum": &[sum], "carry": &[carry]) {
+        let (sum, carry) = (xor(a, b), and(a, b));
+    });
+    (sum, carry)
 }
 
-pub fn full_adder(a: bool, b: bool, c: bool) -> (bool, bool) {
-    let (sum0, carry0) = half_adder(a, b);
-    let (sum, carry1) = half_adder(sum0, c);
-    (sum, or(carry0, carry1))
+pub fn full_adder<B: Bit>(a: B, b: B, c: B) -> (B, B) {
+    chip!("FullAdder"("a": &[a], "b": &[b], "c": &[c]) -> ("sum": &[sum], "carry": &[carry]) {
+        let (sum0, carry0) = half_adder(a, b);
+        let (sum, carry1) = half_adder(sum0, c);
+        let carry = or(carry0, carry1);
+    });
+    (sum, carry)
 }
 
-pub fn add_16(a: [bool; 16], b: [bool; 16]) -> [bool; 16] {
-    let mut carry = false;
-    let mut out = [false; 16];
-    for (idx, (a, b)) in a.into_iter().zip(b.into_iter()).enumerate() {
-        let (sum, carry0) = full_adder(a, b, carry);
-        out[idx] = sum;
-        carry = carry0
-    }
+/// Ripple-carry addition, a half adder for bit 0 and full adders above it.
+pub fn add_16<B: Bit>(a: [B; 16], b: [B; 16]) -> [B; 16] {
+    chip!("Add16"("a": &a, "b": &b) -> ("out": &out) {
+        let (sum, mut carry) = half_adder(a[0], b[0]);
+        let mut out = [sum; 16];
+        for idx in 1..16 {
+            let (sum, carry0) = full_adder(a[idx], b[idx], carry);
+            out[idx] = sum;
+            carry = carry0
+        }
+    });
     out
 }
 
-pub fn inc_16(input: [bool; 16]) -> [bool; 16] {
-    let mut carry = true;
-    let mut out = [false; 16];
-
-    for (idx, a) in input.into_iter().enumerate() {
-        let (sum, carry0) = half_adder(a, carry);
-        out[idx] = sum;
-        carry = carry0;
-    }
+/// Adds one: bit 0 flips and carries itself, the rest are half adders.
+pub fn inc_16<B: Bit>(input: [B; 16]) -> [B; 16] {
+    chip!("Inc16"("in": &input) -> ("out": &out) {
+        let mut carry = input[0];
+        let mut out = [not(input[0]); 16];
+
+        for idx in 1..16 {
+            let (sum, carry0) = half_adder(input[idx], carry);
+            out[idx] = sum;
+            carry = carry0;
+        }
+    });
     out
 }
 
@@ -57,14 +69,19 @@ pub enum AluOp {
 }
 
 pub fn alu(x: u16, y: u16, op: AluOp) -> (u16, bool, bool) {
-    let flags = op as u8;
+    alu_control(x, y, op as u8)
+}
 
-    let zx = (flags & 0b100000) != 0;
-    let nx = (flags & 0b010000) != 0;
-    let zy = (flags & 0b001000) != 0;
-    let ny = (flags & 0b000100) != 0;
-    let f = (flags & 0b000010) != 0;
-    let no = (flags & 0b000001) != 0;
+/// Runs the ALU with the six `zx nx zy ny f no` control bits given directly,
+/// most significant bit first. Every one of the 64 combinations has a defined
+/// result, not only the ones named in `AluOp`.
+pub fn alu_control(x: u16, y: u16, control: u8) -> (u16, bool, bool) {
+    let zx = (control & 0b100000) != 0;
+    let nx = (control & 0b010000) != 0;
+    let zy = (control & 0b001000) != 0;
+    let ny = (control & 0b000100) != 0;
+    let f = (control & 0b000010) != 0;
+    let no = (control & 0b000001) != 0;
 
     let x0 = if zx { 0 } else { x };
     let y0 = if zy { 0 } else { y };
@@ -82,9 +99,73 @@ pub fn alu(x: u16, y: u16, op: AluOp) -> (u16, bool, bool) {
     (out, zr, ng)
 }
 
+/// The ALU built from gate chips only, with the `zr`/`ng` flags computed
+/// from the output bus. `control` holds the six control bits of
+/// `alu_control` least significant first, i.e. `[no, f, ny, zy, nx, zx]`.
+pub fn alu_gates<B: Bit>(x: [B; 16], y: [B; 16], control: [B; 6]) -> ([B; 16], B, B) {
+    let [no, f, ny, zy, nx, zx] = control;
+    chip!("ALU"(
+        "x": &x,
+        "y": &y,
+        "zx": &[zx],
+        "nx": &[nx],
+        "zy": &[zy],
+        "ny": &[ny],
+        "f": &[f],
+        "no": &[no],
+    ) -> (
+        "out": &out,
+        "zr": &[zr],
+        "ng": &[ng],
+    ) {
+        let zero = [B::constant(false); 16];
+
+        let x = mux_16(x, zero, zx);
+        let x = mux_16(x, not_16(x), nx);
+        let y = mux_16(y, zero, zy);
+        let y = mux_16(y, not_16(y), ny);
+
+        let out = mux_16(and_16(x, y), add_16(x, y), f);
+        let out = mux_16(out, not_16(out), no);
+
+        let low: [B; 8] = std::array::from_fn(|idx| out[idx]);
+        let high: [B; 8] = std::array::from_fn(|idx| out[idx + 8]);
+        let zr = not(or(or_8_way(low), or_8_way(high)));
+        let ng = out[15];
+    });
+    (out, zr, ng)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::computer::gate::{from_bits, to_bits};
+
+    const OPS: [AluOp; 18] = [
+        AluOp::Zero,
+        AluOp::One,
+        AluOp::NegOne,
+        AluOp::X,
+        AluOp::Y,
+        AluOp::NotX,
+        AluOp::NotY,
+        AluOp::NegX,
+        AluOp::NegY,
+        AluOp::IncX,
+        AluOp::IncY,
+        AluOp::DecX,
+        AluOp::DecY,
+        AluOp::Add,
+        AluOp::Sub,
+        AluOp::SubFromY,
+        AluOp::And,
+        AluOp::Or,
+    ];
+
+    fn alu_gates_words(x: u16, y: u16, control: u8) -> (u16, bool, bool) {
+        let (out, zr, ng) = alu_gates(to_bits(x), to_bits(y), to_bits(control as u16));
+        (from_bits(out), zr, ng)
+    }
 
     #[test]
     fn test_basic_operations() {
@@ -247,4 +328,76 @@ mod tests {
         assert!(zr);
         assert!(!ng);
     }
+
+    #[test]
+    fn test_alu_control_matches_alu() {
+        for op in OPS {
+            for (x, y) in [(42, 24), (0, 0), (u16::MAX, 1), (0x8000, 0x7FFF)] {
+                assert_eq!(alu_control(x, y, op as u8), alu(x, y, op));
+            }
+        }
+    }
+
+    #[test]
+    fn test_alu_control_undocumented() {
+        // zx nx zy ny f !no: (!0) + (!0) = -2
+        let (result, zr, ng) = alu_control(42, 24, 0b111110);
+        assert_eq!(result, (-2i16) as u16);
+        assert!(!zr);
+        assert!(ng);
+
+        // !zx !nx zy ny !f no: !(x & !0) = !x, same as NotX
+        assert_eq!(
+            alu_control(0b1100, 24, 0b001101),
+            alu(0b1100, 24, AluOp::NotX)
+        );
+
+        // zx nx !zy !ny !f !no: !0 & y = y
+        let (result, zr, ng) = alu_control(42, 24, 0b110000);
+        assert_eq!(result, 24);
+        assert!(!zr);
+        assert!(!ng);
+
+        // !zx nx !zy !ny f no: !(!x + y) = x - y
+        let (result, _, _) = alu_control(42, 24, 0b010011);
+        assert_eq!(result, 18);
+    }
+
+    #[test]
+    fn test_alu_gates_matches_alu() {
+        // Every operation on the edge values, paired both ways.
+        let edges = [
+            0, 1, 2, 0x00FF, 0x7FFE, 0x7FFF, 0x8000, 0x8001, 0xFFFE, 0xFFFF,
+        ];
+        for op in OPS {
+            for x in edges {
+                for y in edges {
+                    assert_eq!(
+                        alu_gates_words(x, y, op as u8),
+                        alu(x, y, op),
+                        "{op:?} {x} {y}"
+                    );
+                }
+            }
+        }
+
+        // Every one of the 64 control values on pseudo-random inputs.
+        let mut seed = 0x2545F491u32;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as u16
+        };
+        for _ in 0..200 {
+            let (x, y) = (next(), next());
+            for control in 0..64 {
+                assert_eq!(
+                    alu_gates_words(x, y, control),
+                    alu_control(x, y, control),
+                    "{control:#08b} {x} {y}"
+                );
+            }
+        }
+    }
 }
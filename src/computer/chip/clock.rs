@@ -0,0 +1,95 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockError {
+    /// `tick` was called while the clock was already high.
+    AlreadyHigh,
+    /// `tock` was called without a `tick` first.
+    AlreadyLow,
+}
+
+impl Display for ClockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClockError::AlreadyHigh => write!(f, "tick while the clock is high"),
+            ClockError::AlreadyLow => write!(f, "tock without a tick"),
+        }
+    }
+}
+
+/// Counts clock cycles split into the rising edge (`tick`), where
+/// sequential chips sample their inputs, and the falling edge (`tock`),
+/// where their outputs change. Displays as the course's time column: `3+`
+/// between the tick and tock of cycle 3, then `4`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Clock {
+    cycle: u64,
+    high: bool,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Clock::at(0)
+    }
+
+    /// A low clock with `cycle` full cycles already completed.
+    pub fn at(cycle: u64) -> Self {
+        Clock { cycle, high: false }
+    }
+
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    pub fn is_high(&self) -> bool {
+        self.high
+    }
+
+    pub fn tick(&mut self) -> Result<(), ClockError> {
+        if self.high {
+            return Err(ClockError::AlreadyHigh);
+        }
+        self.high = true;
+        Ok(())
+    }
+
+    pub fn tock(&mut self) -> Result<(), ClockError> {
+        if !self.high {
+            return Err(ClockError::AlreadyLow);
+        }
+        self.high = false;
+        self.cycle += 1;
+        Ok(())
+    }
+}
+
+impl Display for Clock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cycle)?;
+        if self.high {
+            write!(f, "+")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock() -> Result<(), ClockError> {
+        let mut clock = Clock::new();
+        assert_eq!(clock.to_string(), "0");
+
+        clock.tick()?;
+        assert_eq!(clock.to_string(), "0+");
+        assert_eq!(clock.tick(), Err(ClockError::AlreadyHigh));
+
+        clock.tock()?;
+        assert_eq!(clock.to_string(), "1");
+        assert_eq!(clock.cycle(), 1);
+        assert_eq!(clock.tock(), Err(ClockError::AlreadyLow));
+        Ok(())
+    }
+}
@@ -1,75 +1,60 @@
-use crate::computer::chip::arithmetic::{alu, AluOp};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(PartialEq, Debug)]
+use super::instructions::{DecodeError, ParseInstructionError};
+use crate::computer::chip::arithmetic::alu_control;
+
+/// The `a cccccc` field of a C-instruction. The `cccccc` part is fed to the
+/// ALU as its `zx nx zy ny f no` control bits and `a` selects A or M as `y`.
+///
+/// The variants carry no discriminants since `Undocumented` holds its bits;
+/// `bits` and `from_bits` are the one place the encodings are listed.
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum Computation {
-    Zero = 0b0101010,
-    One = 0b0111111,
-    NegOne = 0b0111010,
-    D = 0b0001100,
-    A = 0b0110000,
-    M = 0b1110000,
-    NotD = 0b0001101,
-    NotA = 0b0110001,
-    NotM = 0b1110001,
-    NegD = 0b0001111,
-    NegA = 0b0110011,
-    NegM = 0b1110011,
-    DPlusOne = 0b0011111,
-    APlusOne = 0b0110111,
-    MPlusOne = 0b1110111,
-    DMinusOne = 0b0001110,
-    AMinusOne = 0b0110010,
-    MMinusOne = 0b1110010,
-    DPlusA = 0b0000010,
-    DPlusM = 0b1000010,
-    DMinusA = 0b0010011,
-    DMinusM = 0b1010011,
-    AMinusD = 0b0000111,
-    MMinusD = 0b1000111,
-    DAndA = 0b0000000,
-    DAndM = 0b0100000,
-    DOrA = 0b0010101,
-    DOrM = 0b1010101,
+    Zero,
+    One,
+    NegOne,
+    D,
+    A,
+    M,
+    NotD,
+    NotA,
+    NotM,
+    NegD,
+    NegA,
+    NegM,
+    DPlusOne,
+    APlusOne,
+    MPlusOne,
+    DMinusOne,
+    AMinusOne,
+    MMinusOne,
+    DPlusA,
+    DPlusM,
+    DMinusA,
+    DMinusM,
+    AMinusD,
+    MMinusD,
+    DAndA,
+    DAndM,
+    DOrA,
+    DOrM,
+    /// One of the 100 encodings without a mnemonic in the Hack specification.
+    Undocumented(u16),
 }
 
 impl Computation {
+    /// Runs the ALU the way the Hack CPU wires it: `x` is always D and `y` is
+    /// A or M depending on the `a` bit.
     pub fn execute(&self, d_value: u16, a_value: u16, m_value: u16) -> (u16, bool, bool) {
-        match self {
-            Computation::Zero => alu(0, 0, AluOp::Zero),
-            Computation::One => alu(0, 0, AluOp::One),
-            Computation::NegOne => alu(0, 0, AluOp::NegOne),
-            Computation::D => alu(d_value, 0, AluOp::X),
-            Computation::A => alu(a_value, 0, AluOp::X),
-            Computation::M => alu(m_value, 0, AluOp::X),
-            Computation::NotD => alu(d_value, 0, AluOp::NotX),
-            Computation::NotA => alu(a_value, 0, AluOp::NotX),
-            Computation::NotM => alu(m_value, 0, AluOp::NotX),
-            Computation::NegD => alu(d_value, 0, AluOp::NegX),
-            Computation::NegA => alu(a_value, 0, AluOp::NegX),
-            Computation::NegM => alu(m_value, 0, AluOp::NegX),
-            Computation::DPlusOne => alu(d_value, 0, AluOp::IncX),
-            Computation::APlusOne => alu(a_value, 0, AluOp::IncX),
-            Computation::MPlusOne => alu(m_value, 0, AluOp::IncX),
-            Computation::DMinusOne => alu(d_value, 0, AluOp::DecX),
-            Computation::AMinusOne => alu(a_value, 0, AluOp::DecX),
-            Computation::MMinusOne => alu(m_value, 0, AluOp::DecX),
-            Computation::DPlusA => alu(d_value, a_value, AluOp::Add),
-            Computation::DPlusM => alu(d_value, m_value, AluOp::Add),
-            Computation::DMinusA => alu(d_value, a_value, AluOp::Sub),
-            Computation::DMinusM => alu(d_value, m_value, AluOp::Sub),
-            Computation::AMinusD => alu(a_value, d_value, AluOp::Sub),
-            Computation::MMinusD => alu(m_value, d_value, AluOp::Sub),
-            Computation::DAndA => alu(d_value, a_value, AluOp::And),
-            Computation::DAndM => alu(d_value, m_value, AluOp::And),
-            Computation::DOrA => alu(d_value, a_value, AluOp::Or),
-            Computation::DOrM => alu(d_value, m_value, AluOp::Or),
-        }
+        let y = if self.reads_m() { m_value } else { a_value };
+        alu_control(d_value, y, (self.bits() & 0b111111) as u8)
     }
-}
 
-impl From<u16> for Computation {
-    fn from(value: u16) -> Self {
-        match value {
+    /// Decodes any 7-bit `a cccccc` pattern, falling back to `Undocumented`
+    /// for the encodings that have no mnemonic.
+    pub fn from_bits(value: u16) -> Self {
+        match value & 0b1111111 {
             0b0101010 => Computation::Zero,
             0b0111111 => Computation::One,
             0b0111010 => Computation::NegOne,
@@ -95,10 +80,259 @@ impl From<u16> for Computation {
             0b0000111 => Computation::AMinusD,
             0b1000111 => Computation::MMinusD,
             0b0000000 => Computation::DAndA,
-            0b0100000 => Computation::DAndM,
+            0b1000000 => Computation::DAndM,
             0b0010101 => Computation::DOrA,
             0b1010101 => Computation::DOrM,
-            _ => unreachable!(),
+            bits => Computation::Undocumented(bits),
+        }
+    }
+
+    /// Whether the `a` bit selects M as the ALU's `y` input.
+    pub fn reads_m(&self) -> bool {
+        self.bits() & 0b1000000 != 0
+    }
+
+    pub fn bits(&self) -> u16 {
+        match self {
+            Computation::Zero => 0b0101010,
+            Computation::One => 0b0111111,
+            Computation::NegOne => 0b0111010,
+            Computation::D => 0b0001100,
+            Computation::A => 0b0110000,
+            Computation::M => 0b1110000,
+            Computation::NotD => 0b0001101,
+            Computation::NotA => 0b0110001,
+            Computation::NotM => 0b1110001,
+            Computation::NegD => 0b0001111,
+            Computation::NegA => 0b0110011,
+            Computation::NegM => 0b1110011,
+            Computation::DPlusOne => 0b0011111,
+            Computation::APlusOne => 0b0110111,
+            Computation::MPlusOne => 0b1110111,
+            Computation::DMinusOne => 0b0001110,
+            Computation::AMinusOne => 0b0110010,
+            Computation::MMinusOne => 0b1110010,
+            Computation::DPlusA => 0b0000010,
+            Computation::DPlusM => 0b1000010,
+            Computation::DMinusA => 0b0010011,
+            Computation::DMinusM => 0b1010011,
+            Computation::AMinusD => 0b0000111,
+            Computation::MMinusD => 0b1000111,
+            Computation::DAndA => 0b0000000,
+            Computation::DAndM => 0b1000000,
+            Computation::DOrA => 0b0010101,
+            Computation::DOrM => 0b1010101,
+            Computation::Undocumented(bits) => *bits,
+        }
+    }
+
+    pub fn mnemonic(&self) -> Option<&'static str> {
+        let mnemonic = match self {
+            Computation::Zero => "0",
+            Computation::One => "1",
+            Computation::NegOne => "-1",
+            Computation::D => "D",
+            Computation::A => "A",
+            Computation::M => "M",
+            Computation::NotD => "!D",
+            Computation::NotA => "!A",
+            Computation::NotM => "!M",
+            Computation::NegD => "-D",
+            Computation::NegA => "-A",
+            Computation::NegM => "-M",
+            Computation::DPlusOne => "D+1",
+            Computation::APlusOne => "A+1",
+            Computation::MPlusOne => "M+1",
+            Computation::DMinusOne => "D-1",
+            Computation::AMinusOne => "A-1",
+            Computation::MMinusOne => "M-1",
+            Computation::DPlusA => "D+A",
+            Computation::DPlusM => "D+M",
+            Computation::DMinusA => "D-A",
+            Computation::DMinusM => "D-M",
+            Computation::AMinusD => "A-D",
+            Computation::MMinusD => "M-D",
+            Computation::DAndA => "D&A",
+            Computation::DAndM => "D&M",
+            Computation::DOrA => "D|A",
+            Computation::DOrM => "D|M",
+            Computation::Undocumented(_) => return None,
+        };
+        Some(mnemonic)
+    }
+}
+
+impl TryFrom<u16> for Computation {
+    type Error = DecodeError;
+
+    /// Decodes the 7 `a cccccc` bits of a C-instruction. Only the 28
+    /// encodings documented by the Hack specification are accepted.
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match Computation::from_bits(value) {
+            Computation::Undocumented(bits) => Err(DecodeError::UnknownComputation(bits)),
+            computation => Ok(computation),
+        }
+    }
+}
+
+impl fmt::Display for Computation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mnemonic() {
+            Some(mnemonic) => f.write_str(mnemonic),
+            None => write!(f, "{:#09b}", self.bits()),
+        }
+    }
+}
+
+impl FromStr for Computation {
+    type Err = ParseInstructionError;
+
+    /// Accepts the canonical mnemonics plus the commutative spellings of
+    /// `+`, `&` and `|` (e.g. `A+D`, `M&D`). Raw `0b` followed by the seven
+    /// `a cccccc` bits is also accepted, which is how `Display` writes
+    /// undocumented encodings.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let comp: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+
+        if let Some(bits) = comp.strip_prefix("0b") {
+            if bits.len() != 7 || !bits.chars().all(|c| matches!(c, '0' | '1')) {
+                return Err(ParseInstructionError::InvalidComputation(s.to_string()));
+            }
+            return Ok(Computation::from_bits(
+                bits.chars()
+                    .fold(0, |value, c| value << 1 | u16::from(c == '1')),
+            ));
+        }
+        let computation = match comp.as_str() {
+            "0" => Computation::Zero,
+            "1" => Computation::One,
+            "-1" => Computation::NegOne,
+            "D" => Computation::D,
+            "A" => Computation::A,
+            "M" => Computation::M,
+            "!D" => Computation::NotD,
+            "!A" => Computation::NotA,
+            "!M" => Computation::NotM,
+            "-D" => Computation::NegD,
+            "-A" => Computation::NegA,
+            "-M" => Computation::NegM,
+            "D+1" | "1+D" => Computation::DPlusOne,
+            "A+1" | "1+A" => Computation::APlusOne,
+            "M+1" | "1+M" => Computation::MPlusOne,
+            "D-1" => Computation::DMinusOne,
+            "A-1" => Computation::AMinusOne,
+            "M-1" => Computation::MMinusOne,
+            "D+A" | "A+D" => Computation::DPlusA,
+            "D+M" | "M+D" => Computation::DPlusM,
+            "D-A" => Computation::DMinusA,
+            "D-M" => Computation::DMinusM,
+            "A-D" => Computation::AMinusD,
+            "M-D" => Computation::MMinusD,
+            "D&A" | "A&D" => Computation::DAndA,
+            "D&M" | "M&D" => Computation::DAndM,
+            "D|A" | "A|D" => Computation::DOrA,
+            "D|M" | "M|D" => Computation::DOrM,
+            _ => return Err(ParseInstructionError::InvalidComputation(s.to_string())),
+        };
+        Ok(computation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::chip::arithmetic::{alu, AluOp};
+
+    #[test]
+    fn test_documented_computations() {
+        struct Test {
+            comp: Computation,
+            expected: u16,
+        }
+        let (d, a, m) = (0b1100, 0b1010, 0b0110);
+        let tests = vec![
+            Test {
+                comp: Computation::DAndA,
+                expected: 0b1000,
+            },
+            Test {
+                comp: Computation::DAndM,
+                expected: 0b0100,
+            },
+            Test {
+                comp: Computation::DOrM,
+                expected: 0b1110,
+            },
+            Test {
+                comp: Computation::AMinusD,
+                expected: (-2i16) as u16,
+            },
+            Test {
+                comp: Computation::MMinusD,
+                expected: (-6i16) as u16,
+            },
+            Test {
+                comp: Computation::NegM,
+                expected: (-6i16) as u16,
+            },
+            Test {
+                comp: Computation::APlusOne,
+                expected: 0b1011,
+            },
+        ];
+
+        for Test { comp, expected } in tests {
+            assert_eq!(comp.execute(d, a, m).0, expected)
         }
     }
+
+    #[test]
+    fn test_d_and_m_encoding() {
+        // D&M is `a`=1 with the D&A control bits, not 0b0100000.
+        assert_eq!(Computation::DAndM.bits(), 0b1000000);
+        assert_eq!(Computation::from_bits(0b1000000), Computation::DAndM);
+        assert_eq!(
+            Computation::from_bits(0b0100000),
+            Computation::Undocumented(0b0100000)
+        );
+        assert_eq!(Computation::DAndM.execute(0b1100, 0, 0b0110).0, 0b0100);
+    }
+
+    #[test]
+    fn test_every_encoding() {
+        let (d, a, m) = (1234, 4321, 999);
+
+        for bits in 0..0b10000000 {
+            let comp = Computation::from_bits(bits);
+            let y = if bits & 0b1000000 != 0 { m } else { a };
+
+            assert_eq!(comp.bits(), bits);
+            assert_eq!(
+                comp.execute(d, a, m),
+                alu_control(d, y, (bits & 0b111111) as u8)
+            );
+            assert_eq!(comp.to_string().parse(), Ok(comp));
+        }
+    }
+
+    #[test]
+    fn test_undocumented_encodings() {
+        // -1 with a=1, as emitted by some toolchains.
+        let comp = Computation::from_bits(0b1111010);
+        assert_eq!(comp, Computation::Undocumented(0b1111010));
+        assert_eq!(comp.execute(1, 2, 3), alu(0, 0, AluOp::NegOne));
+        assert_eq!(comp.to_string(), "0b1111010");
+        assert_eq!(
+            Computation::try_from(0b1111010),
+            Err(DecodeError::UnknownComputation(0b1111010))
+        );
+
+        assert_eq!("0b0101010".parse(), Ok(Computation::Zero));
+        assert_eq!(
+            "0b111".parse::<Computation>(),
+            Err(ParseInstructionError::InvalidComputation(
+                "0b111".to_string()
+            ))
+        );
+    }
 }
@@ -0,0 +1,286 @@
+use super::CPUResponse;
+use crate::computer::chip::arithmetic::alu_gates;
+use crate::computer::chip::sequential::{Pc, Register};
+use crate::computer::gate::{and, from_bits, mux_16, not, or, to_bits};
+
+/// The Hack CPU built only from gates and the sequential chips, following
+/// the course's CPU diagram. It decodes the raw instruction word like
+/// `CpuInstructions::from_bits`: a C-instruction needs its top three bits
+/// set, and any other word loads its low 15 bits into A.
+#[derive(Clone, Default)]
+pub struct GateCpu {
+    a_register: Register,
+    d_register: Register,
+    pc: Pc,
+}
+
+impl GateCpu {
+    pub fn new() -> Self {
+        GateCpu {
+            a_register: Register::new(),
+            d_register: Register::new(),
+            pc: Pc::new(),
+        }
+    }
+
+    pub fn a_register(&self) -> u16 {
+        from_bits(self.a_register.out())
+    }
+
+    pub fn d_register(&self) -> u16 {
+        from_bits(self.d_register.out())
+    }
+
+    pub fn pc(&self) -> u16 {
+        from_bits(self.pc.out())
+    }
+
+    /// Clocks the PC with its reset pin set.
+    pub fn reset(&mut self) {
+        self.pc.clock([false; 16], false, false, true);
+    }
+
+    /// Runs one full clock cycle. The response follows `Cpu::execute`: for
+    /// A-instructions `out_m` is 0 and `address_m` is the loaded value, and
+    /// `pc` is the address of the next instruction.
+    pub fn execute(&mut self, instruction: u16, input_m: u16) -> CPUResponse {
+        let signals = self.signals(instruction, input_m);
+        let a_out = self.a_register.out();
+        let is_a = not(signals.is_c);
+
+        self.tick(instruction, input_m, false);
+        self.tock();
+
+        CPUResponse {
+            out_m: from_bits(mux_16([false; 16], signals.alu_out, signals.is_c)),
+            write_m: signals.write_m,
+            address_m: from_bits(mux_16(a_out, signals.constant, is_a)),
+            pc: self.pc(),
+        }
+    }
+
+    /// The outputs as the course's `CPU` chip shows them between clock
+    /// edges: `outM` and `writeM` for `instruction`, and `addressM` and `pc`
+    /// straight from the registers.
+    pub fn outputs(&self, instruction: u16, input_m: u16) -> CPUResponse {
+        let signals = self.signals(instruction, input_m);
+        CPUResponse {
+            out_m: from_bits(signals.alu_out),
+            write_m: signals.write_m,
+            address_m: self.a_register(),
+            pc: self.pc(),
+        }
+    }
+
+    /// The rising edge: the registers sample their next values.
+    pub fn tick(&mut self, instruction: u16, input_m: u16, reset: bool) {
+        let signals = self.signals(instruction, input_m);
+        let a_out = self.a_register.out();
+
+        let a_in = mux_16(signals.constant, signals.alu_out, signals.is_c);
+        self.a_register.tick(a_in, signals.load_a);
+        self.d_register.tick(signals.alu_out, signals.load_d);
+        self.pc.tick(a_out, signals.jump, not(signals.jump), reset);
+    }
+
+    /// The falling edge: the registers show the values sampled by `tick`.
+    pub fn tock(&mut self) {
+        self.a_register.tock();
+        self.d_register.tock();
+        self.pc.tock();
+    }
+
+    /// The ALU output and control lines for `instruction`.
+    fn signals(&self, instruction: u16, input_m: u16) -> Signals {
+        let instruction = to_bits::<16>(instruction);
+        let a_out = self.a_register.out();
+        let d_out = self.d_register.out();
+
+        let is_c = and(instruction[15], and(instruction[14], instruction[13]));
+        let is_a = not(is_c);
+        let mut constant = instruction;
+        constant[15] = false;
+
+        let y = mux_16(a_out, to_bits(input_m), and(is_c, instruction[12]));
+        let control = std::array::from_fn(|idx| instruction[6 + idx]);
+        let (alu_out, zr, ng) = alu_gates(d_out, y, control);
+
+        let positive = and(not(zr), not(ng));
+        let jump = or(
+            or(and(instruction[2], ng), and(instruction[1], zr)),
+            and(instruction[0], positive),
+        );
+
+        Signals {
+            is_c,
+            constant,
+            alu_out,
+            load_a: or(is_a, and(is_c, instruction[5])),
+            load_d: and(is_c, instruction[4]),
+            write_m: and(is_c, instruction[3]),
+            jump: and(is_c, jump),
+        }
+    }
+}
+
+struct Signals {
+    is_c: bool,
+    /// The value an A-instruction loads.
+    constant: [bool; 16],
+    alu_out: [bool; 16],
+    load_a: bool,
+    load_d: bool,
+    write_m: bool,
+    jump: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::instructions::CpuInstructions;
+    use super::super::Cpu;
+    use super::*;
+
+    #[test]
+    fn test_gate_cpu() {
+        struct Test {
+            input: (u16, u16),
+            expected: (u16, u16, CPUResponse),
+        }
+        let tests = vec![
+            // @12345
+            Test {
+                input: (0b0011000000111001, 0),
+                expected: (
+                    12345,
+                    0,
+                    CPUResponse {
+                        out_m: 0,
+                        write_m: false,
+                        address_m: 12345,
+                        pc: 1,
+                    },
+                ),
+            },
+            // D=A
+            Test {
+                input: (0b1110110000010000, 0),
+                expected: (
+                    12345,
+                    12345,
+                    CPUResponse {
+                        out_m: 12345,
+                        write_m: false,
+                        address_m: 12345,
+                        pc: 2,
+                    },
+                ),
+            },
+            // AM=D-M with M = 345
+            Test {
+                input: (0b1111010011101000, 345),
+                expected: (
+                    12000,
+                    12345,
+                    CPUResponse {
+                        out_m: 12000,
+                        write_m: true,
+                        address_m: 12345,
+                        pc: 3,
+                    },
+                ),
+            },
+            // D;JGT jumps to the A register.
+            Test {
+                input: (0b1110001100000001, 0),
+                expected: (
+                    12000,
+                    12345,
+                    CPUResponse {
+                        out_m: 12345,
+                        write_m: false,
+                        address_m: 12000,
+                        pc: 12000,
+                    },
+                ),
+            },
+            // D;JLT falls through.
+            Test {
+                input: (0b1110001100000100, 0),
+                expected: (
+                    12000,
+                    12345,
+                    CPUResponse {
+                        out_m: 12345,
+                        write_m: false,
+                        address_m: 12000,
+                        pc: 12001,
+                    },
+                ),
+            },
+            // Without bits 13 and 14 a word with bit 15 set is an
+            // A-instruction, loading its low 15 bits.
+            Test {
+                input: (0b1000000000000101, 0),
+                expected: (
+                    5,
+                    12345,
+                    CPUResponse {
+                        out_m: 0,
+                        write_m: false,
+                        address_m: 5,
+                        pc: 12002,
+                    },
+                ),
+            },
+        ];
+
+        let mut cpu = GateCpu::new();
+        for Test {
+            input: (instruction, input_m),
+            expected: (a_register, d_register, response),
+        } in tests
+        {
+            assert_eq!(cpu.execute(instruction, input_m), response);
+            assert_eq!(cpu.a_register(), a_register);
+            assert_eq!(cpu.d_register(), d_register);
+            assert_eq!(cpu.pc(), response.pc);
+        }
+
+        cpu.reset();
+        assert_eq!(cpu.pc(), 0);
+    }
+
+    #[test]
+    fn test_matches_cpu_on_every_c_instruction() {
+        // `@d`, `D=A` or `D=!A`, then `@a` to reach each starting state.
+        let setups = [
+            [0, 0b1110110000010000, 0],
+            [17, 0b1110110001010000, 17],
+            [12345, 0b1110110000010000, 0x7FFF],
+            [1, 0b1110110001010000, 0x4000],
+        ];
+
+        for setup in setups {
+            let mut cpu = Cpu::new();
+            let mut gate_cpu = GateCpu::new();
+            for word in setup {
+                cpu.execute(CpuInstructions::from_bits(word), 0);
+                gate_cpu.execute(word, 0);
+            }
+
+            // Every comp, dest and jump field.
+            for bits in 0..(1 << 13) {
+                let word = 0b1110_0000_0000_0000 | bits;
+                let (mut cpu, mut gate_cpu) = (cpu.clone(), gate_cpu.clone());
+
+                assert_eq!(
+                    cpu.execute(CpuInstructions::from_bits(word), 777),
+                    gate_cpu.execute(word, 777),
+                    "{word:#018b}"
+                );
+                assert_eq!(cpu.a_register, gate_cpu.a_register(), "{word:#018b}");
+                assert_eq!(cpu.d_register, gate_cpu.d_register(), "{word:#018b}");
+            }
+        }
+    }
+}
@@ -1,7 +1,23 @@
+use std::fmt;
+use std::str::FromStr;
+
 use super::computation::Computation;
 use super::PC;
 
-#[derive(PartialEq, Debug)]
+#[derive(Debug, PartialEq)]
+pub enum ParseInstructionError {
+    InvalidAddress(String),
+    InvalidComputation(String),
+    InvalidDestination(String),
+    InvalidJump(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    UnknownComputation(u16),
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum CpuInstructions {
     Ainstruction(u16),
     CInstruction {
@@ -11,11 +27,13 @@ pub enum CpuInstructions {
     },
 }
 
-impl From<u16> for CpuInstructions {
-    fn from(value: u16) -> Self {
+impl CpuInstructions {
+    /// Decodes any word the way the hardware would, keeping undocumented comp
+    /// bits as `Computation::Undocumented` instead of rejecting them.
+    pub fn from_bits(value: u16) -> Self {
         if value >> 13 & 0b111 == 0b111 {
             return CpuInstructions::CInstruction {
-                comp: Computation::from(value >> 6 & 0b1111111),
+                comp: Computation::from_bits(value >> 6),
                 dest: Destination::from(value >> 3 & 0b111),
                 jump: Jump::from(value & 0b111),
             };
@@ -24,13 +42,27 @@ impl From<u16> for CpuInstructions {
     }
 }
 
+impl TryFrom<u16> for CpuInstructions {
+    type Error = DecodeError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match CpuInstructions::from_bits(value) {
+            CpuInstructions::CInstruction {
+                comp: Computation::Undocumented(bits),
+                ..
+            } => Err(DecodeError::UnknownComputation(bits)),
+            instruction => Ok(instruction),
+        }
+    }
+}
+
 impl From<CpuInstructions> for u16 {
     fn from(value: CpuInstructions) -> Self {
         match value {
             CpuInstructions::Ainstruction(value) => value & 0x7FFF,
             CpuInstructions::CInstruction { comp, dest, jump } => {
                 let mut binary: u16 = 0b1110_0000_0000_0000;
-                binary |= (comp as u16) << 6;
+                binary |= comp.bits() << 6;
                 binary |= u16::from(dest) << 3;
                 binary |= jump as u16;
                 binary
@@ -39,7 +71,57 @@ impl From<CpuInstructions> for u16 {
     }
 }
 
-#[derive(PartialEq, Debug)]
+impl fmt::Display for CpuInstructions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CpuInstructions::Ainstruction(value) => write!(f, "@{value}"),
+            CpuInstructions::CInstruction { comp, dest, jump } => {
+                if *dest != Destination::from(0b000) {
+                    write!(f, "{dest}=")?;
+                }
+                write!(f, "{comp}")?;
+                if *jump != Jump::Null {
+                    write!(f, ";{jump}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for CpuInstructions {
+    type Err = ParseInstructionError;
+
+    /// Parses a single `@value` or `dest=comp;jump` instruction. Symbols are
+    /// not resolved here, that is the assembler's job.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Some(value) = s.strip_prefix('@') {
+            return match value.trim().parse::<u16>() {
+                Ok(value) if value <= 0x7FFF => Ok(CpuInstructions::Ainstruction(value)),
+                _ => Err(ParseInstructionError::InvalidAddress(value.to_string())),
+            };
+        }
+
+        let (dest, rest) = match s.split_once('=') {
+            Some((dest, rest)) => (dest, rest),
+            None => ("", s),
+        };
+        let (comp, jump) = match rest.split_once(';') {
+            Some((comp, jump)) => (comp, jump),
+            None => (rest, ""),
+        };
+
+        Ok(CpuInstructions::CInstruction {
+            comp: comp.parse()?,
+            dest: dest.parse()?,
+            jump: jump.parse()?,
+        })
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum Jump {
     Null = 0b000,
     Jgt = 0b001,
@@ -68,6 +150,19 @@ impl From<u16> for Jump {
 }
 
 impl Jump {
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Jump::Null => "",
+            Jump::Jgt => "JGT",
+            Jump::Jeq => "JEQ",
+            Jump::Jge => "JGE",
+            Jump::Jlt => "JLT",
+            Jump::Jne => "JNE",
+            Jump::Jle => "JLE",
+            Jump::Jmp => "JMP",
+        }
+    }
+
     pub fn execute(&self, a_value: u16, pc: &mut PC, zr: bool, ng: bool) {
         match self {
             Jump::Null => {
@@ -98,7 +193,6 @@ impl Jump {
                 if ng {
                     *pc = a_value
                 } else {
-                    println!("incorrect");
                     *pc += 1;
                 }
             }
@@ -121,7 +215,32 @@ impl Jump {
     }
 }
 
-#[derive(PartialEq, Debug)]
+impl fmt::Display for Jump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mnemonic())
+    }
+}
+
+impl FromStr for Jump {
+    type Err = ParseInstructionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let jump = match s.trim() {
+            "" => Jump::Null,
+            "JGT" => Jump::Jgt,
+            "JEQ" => Jump::Jeq,
+            "JGE" => Jump::Jge,
+            "JLT" => Jump::Jlt,
+            "JNE" => Jump::Jne,
+            "JLE" => Jump::Jle,
+            "JMP" => Jump::Jmp,
+            _ => return Err(ParseInstructionError::InvalidJump(s.to_string())),
+        };
+        Ok(jump)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub struct Destination {
     pub a: bool,
     pub m: bool,
@@ -148,6 +267,46 @@ impl From<u16> for Destination {
     }
 }
 
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.a {
+            f.write_str("A")?;
+        }
+        if self.m {
+            f.write_str("M")?;
+        }
+        if self.d {
+            f.write_str("D")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Destination {
+    type Err = ParseInstructionError;
+
+    /// Accepts the registers in any order, each at most once, so both the
+    /// classic `MD` and the newer `DM` spellings parse.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut destination = Destination::from(0b000);
+
+        for register in s.trim().chars() {
+            let slot = match register {
+                'A' => &mut destination.a,
+                'M' => &mut destination.m,
+                'D' => &mut destination.d,
+                _ => return Err(ParseInstructionError::InvalidDestination(s.to_string())),
+            };
+            if *slot {
+                return Err(ParseInstructionError::InvalidDestination(s.to_string()));
+            }
+            *slot = true;
+        }
+
+        Ok(destination)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,7 +390,7 @@ mod tests {
     }
 
     #[test]
-    fn test_from_u16_cpu_instruction() {
+    fn test_try_from_u16_cpu_instruction() {
         struct Test {
             instruction: u16,
             expected: CpuInstructions,
@@ -304,7 +463,148 @@ mod tests {
             expected,
         } in tests
         {
-            assert_eq!(CpuInstructions::from(instruction), expected)
+            assert_eq!(CpuInstructions::try_from(instruction), Ok(expected))
+        }
+    }
+
+    #[test]
+    fn test_try_from_unknown_computation() {
+        struct Test {
+            instruction: u16,
+            expected: DecodeError,
+        }
+        let tests = vec![
+            Test {
+                instruction: 0b1110111110000000,
+                expected: DecodeError::UnknownComputation(0b0111110),
+            },
+            Test {
+                instruction: 0b1111111111111111,
+                expected: DecodeError::UnknownComputation(0b1111111),
+            },
+            Test {
+                instruction: 0b1111101010000111,
+                expected: DecodeError::UnknownComputation(0b1101010),
+            },
+        ];
+
+        for Test {
+            instruction,
+            expected,
+        } in tests
+        {
+            assert_eq!(CpuInstructions::try_from(instruction), Err(expected))
+        }
+    }
+
+    #[test]
+    fn test_display_cpu_instruction() -> Result<(), DecodeError> {
+        struct Test {
+            instruction: u16,
+            expected: &'static str,
+        }
+        let tests = vec![
+            Test {
+                instruction: 0b0000000001111011,
+                expected: "@123",
+            },
+            Test {
+                instruction: 0b1111110010111001,
+                expected: "AMD=M-1;JGT",
+            },
+            Test {
+                instruction: 0b1110101010000111,
+                expected: "0;JMP",
+            },
+            Test {
+                instruction: 0b1110001100011000,
+                expected: "MD=D",
+            },
+            Test {
+                instruction: 0b1110001100000000,
+                expected: "D",
+            },
+            Test {
+                instruction: 0b1111000010100000,
+                expected: "A=D+M",
+            },
+        ];
+
+        for Test {
+            instruction,
+            expected,
+        } in tests
+        {
+            assert_eq!(
+                CpuInstructions::try_from(instruction)?.to_string(),
+                expected
+            )
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_str_cpu_instruction() {
+        struct Test {
+            instruction: &'static str,
+            expected: Result<u16, ParseInstructionError>,
+        }
+        let tests = vec![
+            Test {
+                instruction: "@123",
+                expected: Ok(0b0000000001111011),
+            },
+            Test {
+                instruction: "AMD=M-1;JGT",
+                expected: Ok(0b1111110010111001),
+            },
+            Test {
+                instruction: " 0 ; JMP ",
+                expected: Ok(0b1110101010000111),
+            },
+            Test {
+                instruction: "DM=D",
+                expected: Ok(0b1110001100011000),
+            },
+            Test {
+                instruction: "A=M+D",
+                expected: Ok(0b1111000010100000),
+            },
+            Test {
+                instruction: "D=D&M",
+                expected: Ok(0b1111000000010000),
+            },
+            Test {
+                instruction: "@32768",
+                expected: Err(ParseInstructionError::InvalidAddress("32768".to_string())),
+            },
+            Test {
+                instruction: "@LOOP",
+                expected: Err(ParseInstructionError::InvalidAddress("LOOP".to_string())),
+            },
+            Test {
+                instruction: "D=D*A",
+                expected: Err(ParseInstructionError::InvalidComputation("D*A".to_string())),
+            },
+            Test {
+                instruction: "AA=1",
+                expected: Err(ParseInstructionError::InvalidDestination("AA".to_string())),
+            },
+            Test {
+                instruction: "0;JPM",
+                expected: Err(ParseInstructionError::InvalidJump("JPM".to_string())),
+            },
+        ];
+
+        for Test {
+            instruction,
+            expected,
+        } in tests
+        {
+            assert_eq!(
+                instruction.parse::<CpuInstructions>().map(u16::from),
+                expected
+            )
         }
     }
 }
@@ -1,17 +1,28 @@
 use self::instructions::CpuInstructions;
 
-mod computation;
+pub mod computation;
+pub mod gate_cpu;
 pub mod instructions;
 
 type PC = u16;
 
+#[derive(Clone)]
 pub struct Cpu {
     pub d_register: u16,
     pub a_register: u16,
     pub pc: PC,
+    latched: Option<Registers>,
 }
 
-#[derive(PartialEq, Debug)]
+/// Register values sampled on `tick`, shown on the outputs at `tock`.
+#[derive(Clone, Copy)]
+struct Registers {
+    d_register: u16,
+    a_register: u16,
+    pc: PC,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub struct CPUResponse {
     pub out_m: u16,
     pub write_m: bool,
@@ -25,14 +36,31 @@ impl Cpu {
             d_register: 0,
             a_register: 0,
             pc: 0,
+            latched: None,
         }
     }
 
     pub fn reset(&mut self) {
         self.pc = 0;
+        self.latched = None;
     }
 
+    /// A full clock cycle: `tick` followed by `tock`.
     pub fn execute(&mut self, instruction: CpuInstructions, input_m: u16) -> CPUResponse {
+        let res = self.tick(instruction, input_m);
+        self.tock();
+        res
+    }
+
+    /// The rising edge: computes the outputs for `instruction` and latches
+    /// the next register values, leaving the visible registers unchanged
+    /// until `tock`.
+    pub fn tick(&mut self, instruction: CpuInstructions, input_m: u16) -> CPUResponse {
+        let mut next = Registers {
+            d_register: self.d_register,
+            a_register: self.a_register,
+            pc: self.pc,
+        };
         let mut res = CPUResponse {
             out_m: 0,
             write_m: false,
@@ -41,9 +69,9 @@ impl Cpu {
         };
         match instruction {
             CpuInstructions::Ainstruction(register_a) => {
-                self.a_register = register_a;
+                next.a_register = register_a;
                 res.address_m = register_a;
-                self.pc += 1;
+                next.pc += 1;
             }
             CpuInstructions::CInstruction { comp, dest, jump } => {
                 let (alu_out, zr, ng) = comp.execute(self.d_register, self.a_register, input_m);
@@ -51,29 +79,78 @@ impl Cpu {
                 res.address_m = self.a_register;
 
                 if dest.a {
-                    self.a_register = alu_out;
+                    next.a_register = alu_out;
                 }
                 if dest.d {
-                    self.d_register = alu_out;
+                    next.d_register = alu_out;
                 }
                 if dest.m {
                     res.write_m = true;
                 }
 
-                jump.execute(self.a_register, &mut self.pc, zr, ng);
+                // The PC loads the A register as it was before this
+                // instruction, even when `dest` also writes A.
+                jump.execute(self.a_register, &mut next.pc, zr, ng);
             }
         };
-        res.pc = self.pc;
+        res.pc = next.pc;
+        self.latched = Some(next);
         res
     }
+
+    /// The falling edge: the registers take the values latched by `tick`.
+    pub fn tock(&mut self) {
+        if let Some(next) = self.latched.take() {
+            self.d_register = next.d_register;
+            self.a_register = next.a_register;
+            self.pc = next.pc;
+        }
+    }
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Cpu::new()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::instructions::DecodeError;
     use super::*;
 
     #[test]
-    fn test_cpu() {
+    fn test_tick_tock() -> Result<(), DecodeError> {
+        let mut cpu = Cpu::new();
+        cpu.execute(CpuInstructions::try_from(0b0000000000000111)?, 0);
+
+        // D=A+1: outputs change on the tick, registers only on the tock.
+        let res = cpu.tick(CpuInstructions::try_from(0b1110110111010000)?, 0);
+        assert_eq!(res.out_m, 8);
+        assert_eq!(res.pc, 2);
+        assert_eq!((cpu.d_register, cpu.pc), (0, 1));
+
+        cpu.tock();
+        assert_eq!((cpu.d_register, cpu.pc), (8, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_jump_uses_a_before_dest() -> Result<(), DecodeError> {
+        let mut cpu = Cpu::new();
+        cpu.execute(CpuInstructions::try_from(5)?, 0);
+        cpu.execute(CpuInstructions::try_from(0b1110110000010000)?, 0);
+        cpu.execute(CpuInstructions::try_from(3)?, 0);
+
+        // A=D;JMP: A becomes 5 but the jump goes to the 3 it held before.
+        let res = cpu.execute(CpuInstructions::try_from(0b1110001100100111)?, 0);
+        assert_eq!(res.pc, 3);
+        assert_eq!((cpu.a_register, cpu.pc), (5, 3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cpu() -> Result<(), DecodeError> {
         struct Test {
             instructions: (CpuInstructions, bool, u16),
             expected: (CPUResponse, u16),
@@ -81,7 +158,7 @@ mod tests {
         let mut cpu = Cpu::new();
         let tests = vec![
             Test {
-                instructions: (CpuInstructions::from(0b0011000000111001), false, 0),
+                instructions: (CpuInstructions::try_from(0b0011000000111001)?, false, 0),
                 expected: (
                     CPUResponse {
                         out_m: 0,
@@ -93,7 +170,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b1110110000010000), false, 0),
+                instructions: (CpuInstructions::try_from(0b1110110000010000)?, false, 0),
                 expected: (
                     CPUResponse {
                         out_m: 12345,
@@ -105,7 +182,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b0101101110100000), false, 0),
+                instructions: (CpuInstructions::try_from(0b0101101110100000)?, false, 0),
                 expected: (
                     CPUResponse {
                         out_m: 0,
@@ -117,7 +194,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b1110000111010000), false, 0),
+                instructions: (CpuInstructions::try_from(0b1110000111010000)?, false, 0),
                 expected: (
                     CPUResponse {
                         out_m: 11111,
@@ -129,7 +206,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b0000001111101000), false, 0),
+                instructions: (CpuInstructions::try_from(0b0000001111101000)?, false, 0),
                 expected: (
                     CPUResponse {
                         out_m: 0,
@@ -141,7 +218,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b1110001100001000), false, 0),
+                instructions: (CpuInstructions::try_from(0b1110001100001000)?, false, 0),
                 expected: (
                     CPUResponse {
                         out_m: 11111,
@@ -153,7 +230,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b0000001111101001), false, 0),
+                instructions: (CpuInstructions::try_from(0b0000001111101001)?, false, 0),
                 expected: (
                     CPUResponse {
                         out_m: 0,
@@ -165,7 +242,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b1110001110011000), false, 0),
+                instructions: (CpuInstructions::try_from(0b1110001110011000)?, false, 0),
                 expected: (
                     CPUResponse {
                         out_m: 11110,
@@ -177,7 +254,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b1110001110001000), false, 0),
+                instructions: (CpuInstructions::try_from(0b1110001110001000)?, false, 0),
                 expected: (
                     CPUResponse {
                         out_m: 11109,
@@ -189,7 +266,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b0000001111101000), false, 0),
+                instructions: (CpuInstructions::try_from(0b0000001111101000)?, false, 0),
                 expected: (
                     CPUResponse {
                         out_m: 0,
@@ -201,7 +278,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b1111010011010000), false, 11111),
+                instructions: (CpuInstructions::try_from(0b1111010011010000)?, false, 11111),
                 expected: (
                     CPUResponse {
                         out_m: 65535,
@@ -213,7 +290,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b0000000000001110), false, 11111),
+                instructions: (CpuInstructions::try_from(0b0000000000001110)?, false, 11111),
                 expected: (
                     CPUResponse {
                         out_m: 0,
@@ -225,7 +302,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b1110001100000100), false, 11111),
+                instructions: (CpuInstructions::try_from(0b1110001100000100)?, false, 11111),
                 expected: (
                     CPUResponse {
                         out_m: 65535,
@@ -237,7 +314,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b0000001111100111), false, 11111),
+                instructions: (CpuInstructions::try_from(0b0000001111100111)?, false, 11111),
                 expected: (
                     CPUResponse {
                         out_m: 0,
@@ -249,7 +326,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b1110110111100000), false, 11111),
+                instructions: (CpuInstructions::try_from(0b1110110111100000)?, false, 11111),
                 expected: (
                     CPUResponse {
                         out_m: 1000,
@@ -261,7 +338,7 @@ mod tests {
                 ),
             },
             Test {
-                instructions: (CpuInstructions::from(0b1110001100001000), false, 11111),
+                instructions: (CpuInstructions::try_from(0b1110001100001000)?, false, 11111),
                 expected: (
                     CPUResponse {
                         out_m: 65535,
@@ -284,5 +361,6 @@ mod tests {
             assert_eq!(cpu_response, test_res);
             assert_eq!(cpu.d_register, d_register);
         }
+        Ok(())
     }
 }
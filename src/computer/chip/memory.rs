@@ -1,26 +1,50 @@
+use std::fmt;
+
+use crate::computer::Screen;
+
 #[derive(Debug)]
 pub enum MemoryError {
     OutOfBound(String),
 }
 
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryError::OutOfBound(message) => write!(f, "address out of bounds: {message}"),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Ram {
     ram16k: [u16; 16384],
     screen: [u16; 8192],
     keyboard: u16,
+    pending: Option<(u16, u16)>,
 }
 
+#[derive(Clone)]
 pub struct Rom {
-    rom: [u16; 16384],
+    rom: [u16; Rom::SIZE],
+    /// Words from the start that make up the program.
+    len: usize,
 }
 
 impl Rom {
+    /// Words of program memory.
+    pub const SIZE: usize = 16384;
+
     pub fn new() -> Self {
-        Rom { rom: [0; 16384] }
+        Rom {
+            rom: [0; Rom::SIZE],
+            len: 0,
+        }
     }
     pub fn load(&mut self, input: u16, address: u16) -> Result<u16, MemoryError> {
         let address = address as usize;
-        if address <= 16384 {
+        if address < self.rom.len() {
             self.rom[address] = input;
+            self.len = self.len.max(address + 1);
             return Ok(self.rom[address]);
         }
 
@@ -28,12 +52,41 @@ impl Rom {
     }
 
     pub fn read(&self, address: u16) -> Result<u16, MemoryError> {
-        if address <= 16384 {
+        if (address as usize) < self.rom.len() {
             return Ok(self.rom[address as usize]);
         }
 
         Err(MemoryError::OutOfBound("ROM Error".to_string()))
     }
+
+    pub fn load_program(&mut self, program: &[u16]) -> Result<(), MemoryError> {
+        if program.len() > self.rom.len() {
+            return Err(MemoryError::OutOfBound(
+                "ROM Error on program load".to_string(),
+            ));
+        }
+
+        self.rom[..program.len()].copy_from_slice(program);
+        self.rom[program.len()..].fill(0);
+        self.len = program.len();
+        Ok(())
+    }
+
+    pub fn words(&self) -> &[u16] {
+        &self.rom
+    }
+
+    /// The words given to `load_program`, extended up to the highest
+    /// address written by `load` since, trailing zeros included.
+    pub fn program(&self) -> &[u16] {
+        &self.rom[..self.len]
+    }
+}
+
+impl Default for Rom {
+    fn default() -> Self {
+        Rom::new()
+    }
 }
 
 impl Ram {
@@ -42,6 +95,7 @@ impl Ram {
             ram16k: [0; 16384],
             screen: [0; 8192],
             keyboard: 0,
+            pending: None,
         }
     }
 
@@ -49,6 +103,25 @@ impl Ram {
         self.ram16k.fill(0);
         self.screen.fill(0);
         self.keyboard = 0;
+        self.pending = None;
+    }
+
+    /// The rising edge: latches `input` for `address` when `load` is set.
+    /// Reads keep returning the old word until `tock`.
+    pub fn tick(&mut self, input: u16, load: bool, address: u16) -> Result<(), MemoryError> {
+        if load {
+            self.read(address)?;
+        }
+        self.pending = load.then_some((address, input));
+        Ok(())
+    }
+
+    /// The falling edge: stores the word latched by `tick`, if any.
+    pub fn tock(&mut self) -> Result<(), MemoryError> {
+        if let Some((address, input)) = self.pending.take() {
+            self.load(input, address)?;
+        }
+        Ok(())
     }
 
     pub fn load(&mut self, input: u16, address: u16) -> Result<u16, MemoryError> {
@@ -58,7 +131,7 @@ impl Ram {
             return Ok(self.ram16k[address]);
         }
 
-        if address >= 16384 && address < 8192 + 16384 {
+        if (16384..8192 + 16384).contains(&address) {
             self.screen[address - 16384] = input;
             return Ok(self.screen[address - 16384]);
         }
@@ -77,7 +150,7 @@ impl Ram {
             return Ok(self.ram16k[address]);
         }
 
-        if address >= 16384 && address < 8192 + 16384 {
+        if (16384..8192 + 16384).contains(&address) {
             return Ok(self.screen[address - 16384]);
         }
 
@@ -87,4 +160,24 @@ impl Ram {
 
         Err(MemoryError::OutOfBound("RAM Error on read".to_string()))
     }
+
+    pub fn screen(&self) -> Screen<'_> {
+        Screen::new(&self.screen)
+    }
+
+    /// Every addressable word in Hack address order: RAM16K, the screen map,
+    /// then the keyboard register.
+    pub fn words(&self) -> impl Iterator<Item = u16> + '_ {
+        self.ram16k
+            .iter()
+            .chain(self.screen.iter())
+            .chain(std::iter::once(&self.keyboard))
+            .copied()
+    }
+}
+
+impl Default for Ram {
+    fn default() -> Self {
+        Ram::new()
+    }
 }
@@ -1,3 +1,6 @@
 pub mod arithmetic;
+pub mod clock;
 pub mod cpu;
 pub mod memory;
+pub mod netlist;
+pub mod sequential;
@@ -0,0 +1,1094 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::ops::Range;
+
+use super::arithmetic::{add_16, alu_gates, full_adder, half_adder, inc_16};
+use crate::computer::gate::symbolic::{Circuit, Instance, Node, Wire};
+use crate::computer::gate::{
+    and, and_16, dmux, dmux_4_way, dmux_8_way, mux, mux_16, mux_4_way_16, mux_8_way_16, nand, not,
+    not_16, or, or_16, or_8_way, xor,
+};
+
+/// A recorded chip: the NAND circuit plus the names of its output pins.
+/// Input pins are the circuit's inputs.
+#[derive(Debug, Clone)]
+pub struct Netlist {
+    name: String,
+    circuit: Circuit,
+    outputs: Pins,
+}
+
+/// The cost of a chip in NAND gates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub chip: String,
+    pub nand_count: usize,
+    /// The critical path, in NAND delays.
+    pub depth: usize,
+    /// The most gate inputs any one signal drives.
+    pub max_fan_out: usize,
+}
+
+impl Report {
+    /// A report for every library chip, in catalog order.
+    pub fn catalog() -> Vec<Report> {
+        CHIPS
+            .iter()
+            .map(|(chip, build)| Netlist::capture(chip, build).report())
+            .collect()
+    }
+}
+
+/// Named output pins in order.
+pub type Pins = Vec<(String, Wire)>;
+
+/// Names an output pin.
+pub fn pin(name: &str, wire: Wire) -> Pins {
+    vec![(name.to_string(), wire)]
+}
+
+/// Names an output bus `name[0]` to `name[N - 1]`, least significant first.
+pub fn bus_pins(name: &str, wires: &[Wire]) -> Pins {
+    wires
+        .iter()
+        .enumerate()
+        .map(|(idx, wire)| (format!("{name}[{idx}]"), *wire))
+        .collect()
+}
+
+type Build = fn() -> Pins;
+
+/// Every combinational chip of the gate library and the arithmetic module,
+/// under its course name.
+const CHIPS: &[(&str, Build)] = &[
+    ("Nand", || {
+        pin("out", nand(Wire::input("a"), Wire::input("b")))
+    }),
+    ("Not", || pin("out", not(Wire::input("in")))),
+    ("And", || {
+        pin("out", and(Wire::input("a"), Wire::input("b")))
+    }),
+    ("Or", || pin("out", or(Wire::input("a"), Wire::input("b")))),
+    ("Xor", || {
+        pin("out", xor(Wire::input("a"), Wire::input("b")))
+    }),
+    ("Mux", || {
+        let (a, b, sel) = (Wire::input("a"), Wire::input("b"), Wire::input("sel"));
+        pin("out", mux(a, b, sel))
+    }),
+    ("DMux", || {
+        let [a, b] = dmux(Wire::input("in"), Wire::input("sel"));
+        [pin("a", a), pin("b", b)].concat()
+    }),
+    ("Not16", || bus_pins("out", &not_16(Wire::bus("in")))),
+    ("And16", || {
+        bus_pins("out", &and_16(Wire::bus("a"), Wire::bus("b")))
+    }),
+    ("Or16", || {
+        bus_pins("out", &or_16(Wire::bus("a"), Wire::bus("b")))
+    }),
+    ("Mux16", || {
+        let (a, b) = (Wire::bus("a"), Wire::bus("b"));
+        bus_pins("out", &mux_16(a, b, Wire::input("sel")))
+    }),
+    ("Or8Way", || pin("out", or_8_way(Wire::bus("in")))),
+    ("Mux4Way16", || {
+        let (a, b, c, d) = (
+            Wire::bus("a"),
+            Wire::bus("b"),
+            Wire::bus("c"),
+            Wire::bus("d"),
+        );
+        bus_pins("out", &mux_4_way_16(a, b, c, d, Wire::bus("sel")))
+    }),
+    ("Mux8Way16", || {
+        let low = (
+            Wire::bus("a"),
+            Wire::bus("b"),
+            Wire::bus("c"),
+            Wire::bus("d"),
+        );
+        let high = (
+            Wire::bus("e"),
+            Wire::bus("f"),
+            Wire::bus("g"),
+            Wire::bus("h"),
+        );
+        bus_pins("out", &mux_8_way_16(low, high, Wire::bus("sel")))
+    }),
+    ("DMux4Way", || {
+        let out = dmux_4_way(Wire::input("in"), Wire::bus("sel"));
+        ["a", "b", "c", "d"]
+            .iter()
+            .zip(out)
+            .flat_map(|(name, wire)| pin(name, wire))
+            .collect()
+    }),
+    ("DMux8Way", || {
+        let out = dmux_8_way(Wire::input("in"), Wire::bus("sel"));
+        ["a", "b", "c", "d", "e", "f", "g", "h"]
+            .iter()
+            .zip(out)
+            .flat_map(|(name, wire)| pin(name, wire))
+            .collect()
+    }),
+    ("HalfAdder", || {
+        let (sum, carry) = half_adder(Wire::input("a"), Wire::input("b"));
+        [pin("sum", sum), pin("carry", carry)].concat()
+    }),
+    ("FullAdder", || {
+        let (a, b, c) = (Wire::input("a"), Wire::input("b"), Wire::input("c"));
+        let (sum, carry) = full_adder(a, b, c);
+        [pin("sum", sum), pin("carry", carry)].concat()
+    }),
+    ("Add16", || {
+        bus_pins("out", &add_16(Wire::bus("a"), Wire::bus("b")))
+    }),
+    ("Inc16", || bus_pins("out", &inc_16(Wire::bus("in")))),
+    ("ALU", || {
+        let (x, y) = (Wire::bus("x"), Wire::bus("y"));
+        let [zx, nx, zy, ny, f, no] = ["zx", "nx", "zy", "ny", "f", "no"].map(Wire::input);
+        let (out, zr, ng) = alu_gates(x, y, [no, f, ny, zy, nx, zx]);
+        [bus_pins("out", &out), pin("zr", zr), pin("ng", ng)].concat()
+    }),
+];
+
+impl Netlist {
+    /// Records `build`, which creates the input wires, runs the chip on them
+    /// and names the outputs.
+    pub fn capture(name: &str, build: impl FnOnce() -> Pins) -> Netlist {
+        let (circuit, outputs) = Circuit::record(build);
+        Netlist {
+            name: name.to_string(),
+            circuit,
+            outputs,
+        }
+    }
+
+    /// One of the library chips by its course name, e.g. `Mux4Way16`.
+    pub fn chip(name: &str) -> Option<Netlist> {
+        CHIPS
+            .iter()
+            .find(|(chip, _)| chip.eq_ignore_ascii_case(name))
+            .map(|(chip, build)| Netlist::capture(chip, build))
+    }
+
+    pub fn chip_names() -> impl Iterator<Item = &'static str> {
+        CHIPS.iter().map(|(name, _)| *name)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn circuit(&self) -> &Circuit {
+        &self.circuit
+    }
+
+    pub fn outputs(&self) -> &[(String, Wire)] {
+        &self.outputs
+    }
+
+    /// Which nodes drive an output, directly or through other gates.
+    /// Constants a chip created but never used are left out.
+    pub fn live(&self) -> Vec<bool> {
+        let nodes = self.circuit.nodes();
+        let mut live = vec![false; nodes.len()];
+        for (_, wire) in &self.outputs {
+            live[wire.index()] = true;
+        }
+        // Operands always come before the gate using them.
+        for idx in (0..nodes.len()).rev() {
+            if let (true, Node::Nand(a, b)) = (live[idx], &nodes[idx]) {
+                live[a.index()] = true;
+                live[b.index()] = true;
+            }
+        }
+        live
+    }
+
+    pub fn nand_count(&self) -> usize {
+        let live = self.live();
+        self.circuit
+            .nodes()
+            .iter()
+            .zip(live)
+            .filter(|(node, live)| *live && matches!(node, Node::Nand(..)))
+            .count()
+    }
+
+    /// How many NAND gates each node is behind: zero for inputs and
+    /// constants, one more than its deeper operand for a gate.
+    pub fn depths(&self) -> Vec<usize> {
+        let nodes = self.circuit.nodes();
+        let mut depths = vec![0; nodes.len()];
+        for (idx, node) in nodes.iter().enumerate() {
+            if let Node::Nand(a, b) = node {
+                depths[idx] = 1 + depths[a.index()].max(depths[b.index()]);
+            }
+        }
+        depths
+    }
+
+    /// The longest path from an input to an output, in NAND delays.
+    pub fn depth(&self) -> usize {
+        let depths = self.depths();
+        self.outputs
+            .iter()
+            .map(|(_, wire)| depths[wire.index()])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// How many live gate inputs each node drives. A gate with both inputs
+    /// on the same wire counts twice, as both pins load it.
+    pub fn fan_out(&self) -> Vec<usize> {
+        let live = self.live();
+        let nodes = self.circuit.nodes();
+        let mut fan_out = vec![0; nodes.len()];
+        for (node, live) in nodes.iter().zip(live) {
+            if let (true, Node::Nand(a, b)) = (live, node) {
+                fan_out[a.index()] += 1;
+                fan_out[b.index()] += 1;
+            }
+        }
+        fan_out
+    }
+
+    pub fn report(&self) -> Report {
+        Report {
+            chip: self.name.clone(),
+            nand_count: self.nand_count(),
+            depth: self.depth(),
+            max_fan_out: self.fan_out().into_iter().max().unwrap_or(0),
+        }
+    }
+
+    /// The live part of the circuit as a Graphviz digraph, flowing left to
+    /// right from the input pins to the output pins.
+    pub fn to_dot(&self) -> String {
+        let live = self.live();
+        let mut dot = String::new();
+
+        writeln!(dot, "digraph \"{}\" {{", self.name).unwrap();
+        writeln!(dot, "    rankdir=LR;").unwrap();
+
+        for (idx, node) in self.circuit.nodes().iter().enumerate() {
+            match node {
+                Node::Input(name) => {
+                    writeln!(dot, "    n{idx} [label=\"{name}\", shape=circle];").unwrap()
+                }
+                Node::Constant(value) if live[idx] => writeln!(
+                    dot,
+                    "    n{idx} [label=\"{}\", shape=plaintext];",
+                    *value as u8
+                )
+                .unwrap(),
+                Node::Nand(a, b) if live[idx] => {
+                    writeln!(dot, "    n{idx} [label=\"NAND\", shape=box];").unwrap();
+                    writeln!(dot, "    n{} -> n{idx};", a.index()).unwrap();
+                    writeln!(dot, "    n{} -> n{idx};", b.index()).unwrap();
+                }
+                _ => {}
+            }
+        }
+
+        for (idx, (name, wire)) in self.outputs.iter().enumerate() {
+            writeln!(dot, "    out{idx} [label=\"{name}\", shape=doublecircle];").unwrap();
+            writeln!(dot, "    n{} -> out{idx};", wire.index()).unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The chip hierarchy as structural Verilog, one module per library chip
+    /// built from `nand` primitives and instances of the chips below it.
+    /// The netlist's own module comes first.
+    pub fn to_verilog(&self) -> String {
+        let mut verilog = String::new();
+        for (idx, module) in self.modules().iter().enumerate() {
+            if idx > 0 {
+                verilog.push('\n');
+            }
+            module.write_verilog(&mut verilog);
+        }
+        verilog
+    }
+
+    /// The chip hierarchy as BLIF, one `.model` per library chip with
+    /// `.subckt` lines for the chips inside it. The first model is the
+    /// netlist itself.
+    pub fn to_blif(&self) -> String {
+        let mut blif = String::new();
+        for (idx, module) in self.modules().iter().enumerate() {
+            if idx > 0 {
+                blif.push('\n');
+            }
+            module.write_blif(&mut blif);
+        }
+        blif
+    }
+
+    /// The netlist followed by one module per library chip it uses, in the
+    /// order they were first called. A chip is described by its first call
+    /// whose input pins are all different wires. When the netlist is just
+    /// one call of a chip with the same name and pins, that chip's module
+    /// stands for the netlist.
+    fn modules(&self) -> Vec<Module> {
+        let instances = self.circuit.instances();
+        let children = |parent: Option<usize>| -> Vec<&Instance> {
+            instances
+                .iter()
+                .filter(|instance| instance.parent == parent)
+                .collect()
+        };
+        let direct = |nodes: Range<usize>, children: &[&Instance]| -> Vec<Wire> {
+            self.circuit
+                .wires(nodes)
+                .filter(|wire| {
+                    !matches!(self.circuit.node(*wire), Node::Input(_))
+                        && !children
+                            .iter()
+                            .any(|child| child.nodes.contains(&wire.index()))
+                })
+                .collect()
+        };
+        let ports = |pins: &[(&str, Vec<Wire>)]| -> Vec<Port> {
+            pins.iter()
+                .map(|(name, wires)| Port::from_pins(name, wires))
+                .collect()
+        };
+
+        let mut modules = Vec::new();
+        let inputs = Port::group(
+            self.circuit
+                .inputs()
+                .map(|(wire, name)| (name.to_string(), wire)),
+        );
+        let outputs = Port::group(self.outputs.iter().cloned());
+        let top = children(None);
+        let nodes = direct(0..self.circuit.nodes().len(), &top);
+        let is_chip = match top.as_slice() {
+            [chip] => {
+                nodes.is_empty()
+                    && chip.chip == self.name
+                    && ports(&chip.inputs) == inputs
+                    && ports(&chip.outputs) == outputs
+            }
+            _ => false,
+        };
+        if !is_chip {
+            modules.push(Module::new(
+                &self.name,
+                inputs,
+                outputs,
+                &top,
+                &nodes,
+                &self.circuit,
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        for instance in instances {
+            if !seen.insert(instance.chip) {
+                continue;
+            }
+            let calls = || {
+                instances
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, call)| call.chip == instance.chip)
+            };
+            let (idx, call) = calls()
+                .find(|(_, call)| {
+                    let wires: Vec<Wire> = call
+                        .inputs
+                        .iter()
+                        .flat_map(|(_, wires)| wires.iter().copied())
+                        .collect();
+                    wires.iter().collect::<HashSet<_>>().len() == wires.len()
+                })
+                .or_else(|| calls().next())
+                .expect("instance is one of the calls");
+
+            let children = children(Some(idx));
+            let nodes = direct(call.nodes.clone(), &children);
+            modules.push(Module::new(
+                call.chip,
+                ports(&call.inputs),
+                ports(&call.outputs),
+                &children,
+                &nodes,
+                &self.circuit,
+            ));
+        }
+        modules
+    }
+}
+
+/// A module port and the net name of each of its bits: the port name for a
+/// single pin, `name[i]` for a bus.
+#[derive(Debug, Clone, PartialEq)]
+struct Port {
+    name: String,
+    bits: Vec<(String, Wire)>,
+}
+
+impl Port {
+    fn from_pins(name: &str, wires: &[Wire]) -> Port {
+        let bits = match wires {
+            [wire] => vec![(name.to_string(), *wire)],
+            _ => bus_pins(name, wires),
+        };
+        Port {
+            name: name.to_string(),
+            bits,
+        }
+    }
+
+    /// Groups pins named `name[i]` back into buses.
+    fn group(pins: impl Iterator<Item = (String, Wire)>) -> Vec<Port> {
+        let mut ports: Vec<Port> = Vec::new();
+        for (pin, wire) in pins {
+            let name = pin.split('[').next().unwrap_or(&pin).to_string();
+            match ports.last_mut() {
+                Some(port) if port.name == name && pin != name => port.bits.push((pin, wire)),
+                _ => ports.push(Port {
+                    name,
+                    bits: vec![(pin, wire)],
+                }),
+            }
+        }
+        ports
+    }
+
+    fn is_bus(&self) -> bool {
+        self.bits.len() > 1 || self.bits[0].0 != self.name
+    }
+}
+
+/// What a module body is made of, with every signal given by net name.
+enum Item {
+    Nand(String, String, String),
+    Constant(String, bool),
+    Assign(String, String),
+    Instance {
+        chip: &'static str,
+        inputs: Vec<(String, Vec<String>)>,
+        outputs: Vec<(String, Vec<String>)>,
+    },
+}
+
+/// One level of the chip hierarchy, ready to be written out.
+struct Module {
+    name: String,
+    inputs: Vec<Port>,
+    outputs: Vec<Port>,
+    /// Internal nets and their widths.
+    wires: Vec<(String, usize)>,
+    items: Vec<Item>,
+}
+
+impl Module {
+    /// Names the nets of a chip whose own pins are `inputs` and `outputs`,
+    /// built from the instances in `children` and the NAND gates and
+    /// constants among `nodes`.
+    fn new(
+        name: &str,
+        inputs: Vec<Port>,
+        outputs: Vec<Port>,
+        children: &[&Instance],
+        nodes: &[Wire],
+        circuit: &Circuit,
+    ) -> Module {
+        let mut names: HashMap<Wire, String> = HashMap::new();
+        let mut driven: HashSet<Wire> = HashSet::new();
+        let mut wires = Vec::new();
+        let mut items = Vec::new();
+
+        for (bit, wire) in inputs.iter().flat_map(|port| &port.bits) {
+            names.entry(*wire).or_insert_with(|| bit.clone());
+            driven.insert(*wire);
+        }
+        // An output pin names the net driving it, unless that net already
+        // has a name, in which case it is copied across at the end.
+        let mut assigns = Vec::new();
+        for (bit, wire) in outputs.iter().flat_map(|port| &port.bits) {
+            if names.contains_key(wire) {
+                assigns.push((bit.clone(), *wire));
+            } else {
+                names.insert(*wire, bit.clone());
+            }
+        }
+
+        let net =
+            |wire: Wire, names: &mut HashMap<Wire, String>, wires: &mut Vec<(String, usize)>| {
+                names
+                    .entry(wire)
+                    .or_insert_with(|| {
+                        wires.push((format!("n{}", wire.index()), 1));
+                        format!("n{}", wire.index())
+                    })
+                    .clone()
+            };
+
+        let mut children = children.iter().peekable();
+        let mut nodes = nodes.iter().peekable();
+        let mut count = 0;
+        loop {
+            let child_first = match (children.peek(), nodes.peek()) {
+                (Some(child), Some(wire)) => child.nodes.start <= wire.index(),
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+
+            if child_first {
+                let child = children.next().expect("peeked above");
+                let inputs = child
+                    .inputs
+                    .iter()
+                    .map(|(port, bits)| {
+                        let bits = bits
+                            .iter()
+                            .map(|wire| net(*wire, &mut names, &mut wires))
+                            .collect();
+                        (port.to_string(), bits)
+                    })
+                    .collect();
+                // Outputs not named yet drive a new net named after the
+                // instance and pin, `u0_out[3]`. One already driven here, an
+                // input passed through or a wire returned twice, gets that
+                // name too but nothing reads it.
+                let mut outputs = Vec::new();
+                for (port, bits) in &child.outputs {
+                    let name = format!("u{count}_{port}");
+                    let fresh = |idx: usize| match bits.len() {
+                        1 => name.clone(),
+                        _ => format!("{name}[{idx}]"),
+                    };
+                    let mut declare = false;
+                    let bits: Vec<String> = bits
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, wire)| {
+                            if !driven.insert(*wire) {
+                                declare = true;
+                                fresh(idx)
+                            } else {
+                                names
+                                    .entry(*wire)
+                                    .or_insert_with(|| {
+                                        declare = true;
+                                        fresh(idx)
+                                    })
+                                    .clone()
+                            }
+                        })
+                        .collect();
+                    if declare {
+                        wires.push((name, bits.len()));
+                    }
+                    outputs.push((port.to_string(), bits));
+                }
+                count += 1;
+                items.push(Item::Instance {
+                    chip: child.chip,
+                    inputs,
+                    outputs,
+                });
+            } else {
+                let wire = *nodes.next().expect("peeked above");
+                match circuit.node(wire) {
+                    Node::Nand(a, b) => {
+                        let a = net(*a, &mut names, &mut wires);
+                        let b = net(*b, &mut names, &mut wires);
+                        items.push(Item::Nand(net(wire, &mut names, &mut wires), a, b));
+                    }
+                    Node::Constant(value) => {
+                        items.push(Item::Constant(net(wire, &mut names, &mut wires), *value))
+                    }
+                    Node::Input(_) => {}
+                }
+                driven.insert(wire);
+            }
+        }
+
+        for (bit, wire) in assigns {
+            items.push(Item::Assign(bit, net(wire, &mut names, &mut wires)));
+        }
+
+        Module {
+            name: name.to_string(),
+            inputs,
+            outputs,
+            wires,
+            items,
+        }
+    }
+
+    /// A bus connection in Verilog: a whole port or net when the bits are
+    /// exactly one, otherwise a concatenation, most significant bit first.
+    fn verilog_bus(&self, bits: &[String]) -> String {
+        let port = self
+            .inputs
+            .iter()
+            .chain(&self.outputs)
+            .find(|port| port.bits.iter().map(|(bit, _)| bit).eq(bits))
+            .map(|port| &port.name);
+        let wire = self
+            .wires
+            .iter()
+            .find(|(name, width)| {
+                *width > 1
+                    && (0..*width)
+                        .map(|idx| format!("{name}[{idx}]"))
+                        .eq(bits.iter().cloned())
+            })
+            .map(|(name, _)| name);
+
+        match (port.or(wire), bits) {
+            (Some(name), _) => name.clone(),
+            (None, [bit]) => bit.clone(),
+            (None, _) => {
+                let bits: Vec<&str> = bits.iter().rev().map(String::as_str).collect();
+                format!("{{{}}}", bits.join(", "))
+            }
+        }
+    }
+
+    fn write_verilog(&self, verilog: &mut String) {
+        let ports: Vec<&str> = self
+            .inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(|port| port.name.as_str())
+            .collect();
+        writeln!(verilog, "module {} ({});", self.name, ports.join(", ")).unwrap();
+
+        for (direction, ports) in [("input", &self.inputs), ("output", &self.outputs)] {
+            for port in ports {
+                match port.is_bus() {
+                    true => writeln!(
+                        verilog,
+                        "    {direction} [{}:0] {};",
+                        port.bits.len() - 1,
+                        port.name
+                    ),
+                    false => writeln!(verilog, "    {direction} {};", port.name),
+                }
+                .unwrap();
+            }
+        }
+        for (wire, width) in &self.wires {
+            match width {
+                1 => writeln!(verilog, "    wire {wire};"),
+                _ => writeln!(verilog, "    wire [{}:0] {wire};", width - 1),
+            }
+            .unwrap();
+        }
+
+        let mut count = 0;
+        for item in &self.items {
+            match item {
+                Item::Nand(out, a, b) => writeln!(verilog, "    nand ({out}, {a}, {b});"),
+                Item::Constant(out, value) => {
+                    writeln!(verilog, "    assign {out} = 1'b{};", *value as u8)
+                }
+                Item::Assign(out, from) => writeln!(verilog, "    assign {out} = {from};"),
+                Item::Instance {
+                    chip,
+                    inputs,
+                    outputs,
+                } => {
+                    let pins: Vec<String> = inputs
+                        .iter()
+                        .chain(outputs)
+                        .map(|(port, bits)| format!(".{port}({})", self.verilog_bus(bits)))
+                        .collect();
+                    count += 1;
+                    writeln!(verilog, "    {chip} u{} ({});", count - 1, pins.join(", "))
+                }
+            }
+            .unwrap();
+        }
+
+        verilog.push_str("endmodule\n");
+    }
+
+    fn write_blif(&self, blif: &mut String) {
+        writeln!(blif, ".model {}", self.name).unwrap();
+        for (keyword, ports) in [(".inputs", &self.inputs), (".outputs", &self.outputs)] {
+            let bits: Vec<&str> = ports
+                .iter()
+                .flat_map(|port| &port.bits)
+                .map(|(bit, _)| bit.as_str())
+                .collect();
+            writeln!(blif, "{keyword} {}", bits.join(" ")).unwrap();
+        }
+
+        for item in &self.items {
+            match item {
+                Item::Nand(out, a, b) if a == b => writeln!(blif, ".names {a} {out}\n0 1"),
+                Item::Nand(out, a, b) => writeln!(blif, ".names {a} {b} {out}\n0- 1\n-0 1"),
+                Item::Constant(out, true) => writeln!(blif, ".names {out}\n1"),
+                Item::Constant(out, false) => writeln!(blif, ".names {out}"),
+                Item::Assign(out, from) => writeln!(blif, ".names {from} {out}\n1 1"),
+                Item::Instance {
+                    chip,
+                    inputs,
+                    outputs,
+                } => {
+                    let pins: Vec<String> = inputs
+                        .iter()
+                        .chain(outputs)
+                        .flat_map(|(port, bits)| {
+                            let formal: Vec<String> = match bits.len() {
+                                1 => vec![port.clone()],
+                                _ => (0..bits.len())
+                                    .map(|idx| format!("{port}[{idx}]"))
+                                    .collect(),
+                            };
+                            formal
+                                .into_iter()
+                                .zip(bits)
+                                .map(|(formal, actual)| format!("{formal}={actual}"))
+                        })
+                        .collect();
+                    writeln!(blif, ".subckt {chip} {}", pins.join(" "))
+                }
+            }
+            .unwrap();
+        }
+
+        blif.push_str(".end\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::gate::{from_bits, to_bits};
+
+    type Model<'a> = Vec<Vec<&'a str>>;
+
+    /// Runs the model `name` of a BLIF file from `to_blif`, flattening its
+    /// `.subckt` lines.
+    fn run_blif(models: &[Model], name: &str, inputs: &[bool]) -> Vec<bool> {
+        let model = models
+            .iter()
+            .find(|model| model[0][1] == name)
+            .unwrap_or_else(|| panic!("no model {name}"));
+        let mut nets: HashMap<&str, bool> = model[1][1..]
+            .iter()
+            .copied()
+            .zip(inputs.iter().copied())
+            .collect();
+
+        for (idx, line) in model.iter().enumerate().skip(3) {
+            match line[0] {
+                ".names" => {
+                    let (operands, out) = line[1..].split_at(line.len() - 2);
+                    let values: Vec<bool> = operands.iter().map(|net| nets[net]).collect();
+                    let value = model[idx + 1..]
+                        .iter()
+                        .take_while(|row| !row[0].starts_with('.'))
+                        .any(|row| {
+                            row[0]
+                                .chars()
+                                .zip(&values)
+                                .all(|(cube, value)| cube == '-' || (cube == '1') == *value)
+                        });
+                    nets.insert(out[0], value);
+                }
+                ".subckt" => {
+                    let pins: HashMap<&str, &str> = line[2..]
+                        .iter()
+                        .map(|pin| pin.split_once('=').unwrap())
+                        .collect();
+                    let chip = models.iter().find(|model| model[0][1] == line[1]).unwrap();
+                    let inputs: Vec<bool> =
+                        chip[1][1..].iter().map(|pin| nets[pins[pin]]).collect();
+                    let outputs = run_blif(models, line[1], &inputs);
+                    for (pin, value) in chip[2][1..].iter().zip(outputs) {
+                        nets.insert(pins[pin], value);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        model[2][1..].iter().map(|net| nets[net]).collect()
+    }
+
+    #[test]
+    fn test_not_dot() {
+        let netlist = Netlist::chip("not").unwrap();
+
+        assert_eq!(netlist.name(), "Not");
+        assert_eq!(
+            netlist.to_dot(),
+            [
+                "digraph \"Not\" {",
+                "    rankdir=LR;",
+                "    n0 [label=\"in\", shape=circle];",
+                "    n1 [label=\"NAND\", shape=box];",
+                "    n0 -> n1;",
+                "    n0 -> n1;",
+                "    out0 [label=\"out\", shape=doublecircle];",
+                "    n1 -> out0;",
+                "}",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_nand_counts() {
+        struct Test {
+            chip: &'static str,
+            expected: usize,
+        }
+        let tests = vec![
+            Test {
+                chip: "Nand",
+                expected: 1,
+            },
+            Test {
+                chip: "And",
+                expected: 2,
+            },
+            Test {
+                chip: "Or",
+                expected: 3,
+            },
+            Test {
+                chip: "Xor",
+                expected: 9,
+            },
+            Test {
+                chip: "Mux",
+                expected: 8,
+            },
+            Test {
+                chip: "HalfAdder",
+                expected: 11,
+            },
+        ];
+
+        for Test { chip, expected } in tests {
+            assert_eq!(
+                Netlist::chip(chip).unwrap().nand_count(),
+                expected,
+                "{chip}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_catalog() {
+        // The recorded ALU computing x-1.
+        let netlist = Netlist::chip("ALU").unwrap();
+        let mut inputs = to_bits::<16>(1000).to_vec();
+        inputs.extend(to_bits::<16>(77));
+        inputs.extend([false, false, true, true, true, false]);
+
+        let outputs: Vec<Wire> = netlist.outputs().iter().map(|(_, wire)| *wire).collect();
+        let values = netlist.circuit().evaluate(&inputs, &outputs);
+
+        let mut out = [false; 16];
+        out.copy_from_slice(&values[..16]);
+        assert_eq!(from_bits(out), 999);
+        assert_eq!(&values[16..], [false, false]);
+
+        for name in Netlist::chip_names() {
+            let netlist = Netlist::chip(name).unwrap();
+            assert!(netlist.nand_count() > 0, "{name}");
+            assert!(netlist.to_dot().contains("shape=doublecircle"), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_and_export() {
+        let netlist = Netlist::chip("And").unwrap();
+
+        assert_eq!(
+            netlist.to_verilog(),
+            [
+                "module And (a, b, out);",
+                "    input a;",
+                "    input b;",
+                "    output out;",
+                "    wire n2;",
+                "    nand (n2, a, b);",
+                "    Not u0 (.in(n2), .out(out));",
+                "endmodule",
+                "",
+                "module Not (in, out);",
+                "    input in;",
+                "    output out;",
+                "    nand (out, in, in);",
+                "endmodule",
+                "",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            netlist.to_blif(),
+            [
+                ".model And",
+                ".inputs a b",
+                ".outputs out",
+                ".names a b n2",
+                "0- 1",
+                "-0 1",
+                ".subckt Not in=n2 out=out",
+                ".end",
+                "",
+                ".model Not",
+                ".inputs in",
+                ".outputs out",
+                ".names in out",
+                "0 1",
+                ".end",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_verilog_hierarchy() {
+        let verilog = Netlist::chip("Mux4Way16").unwrap().to_verilog();
+
+        assert!(verilog.starts_with("module Mux4Way16 (a, b, c, d, sel, out);\n"));
+        assert!(verilog.contains("    Mux16 u0 (.a(a), .b(b), .sel(sel[0]), .out(u0_out));\n"));
+        assert!(
+            verilog.contains("    Mux16 u2 (.a(u0_out), .b(u1_out), .sel(sel[1]), .out(out));\n")
+        );
+        let modules: Vec<&str> = verilog
+            .lines()
+            .filter_map(|line| line.strip_prefix("module "))
+            .map(|line| line.split(' ').next().unwrap())
+            .collect();
+        assert_eq!(modules, ["Mux4Way16", "Mux16", "Mux", "Not", "And", "Or"]);
+
+        // The ALU's ng is its out[15], copied onto the output port.
+        let verilog = Netlist::chip("ALU").unwrap().to_verilog();
+        assert!(verilog.contains("    assign ng = out[15];\n"));
+        assert!(verilog.contains("module FullAdder (a, b, c, sum, carry);\n"));
+    }
+
+    #[test]
+    fn test_blif_matches_circuit() {
+        let mut seed = 0x2545F4914F6CDD1Du64;
+        for name in Netlist::chip_names() {
+            let netlist = Netlist::chip(name).unwrap();
+            let blif = netlist.to_blif();
+            let models: Vec<Model> = blif
+                .split(".end")
+                .map(|model| {
+                    model
+                        .lines()
+                        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+                        .filter(|line| !line.is_empty())
+                        .collect::<Model>()
+                })
+                .filter(|model| !model.is_empty())
+                .collect();
+            let outputs: Vec<Wire> = netlist.outputs().iter().map(|(_, wire)| *wire).collect();
+
+            for _ in 0..20 {
+                let inputs: Vec<bool> = netlist
+                    .circuit()
+                    .inputs()
+                    .map(|_| {
+                        seed ^= seed << 13;
+                        seed ^= seed >> 7;
+                        seed ^= seed << 17;
+                        seed & 1 == 1
+                    })
+                    .collect();
+                assert_eq!(
+                    run_blif(&models, name, &inputs),
+                    netlist.circuit().evaluate(&inputs, &outputs),
+                    "{name} {inputs:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_reports() {
+        struct Test {
+            chip: &'static str,
+            nand_count: usize,
+            depth: usize,
+            max_fan_out: usize,
+        }
+        let tests = vec![
+            Test {
+                chip: "Not",
+                nand_count: 1,
+                depth: 1,
+                max_fan_out: 2,
+            },
+            Test {
+                chip: "Mux",
+                nand_count: 8,
+                depth: 5,
+                max_fan_out: 3,
+            },
+            Test {
+                chip: "Mux16",
+                nand_count: 128,
+                depth: 5,
+                max_fan_out: 48,
+            },
+            Test {
+                chip: "Mux8Way16",
+                nand_count: 896,
+                depth: 13,
+                max_fan_out: 192,
+            },
+            Test {
+                chip: "Add16",
+                nand_count: 379,
+                depth: 66,
+                max_fan_out: 4,
+            },
+        ];
+
+        for test in tests {
+            let report = Netlist::chip(test.chip).unwrap().report();
+            assert_eq!(
+                report,
+                Report {
+                    chip: test.chip.to_string(),
+                    nand_count: test.nand_count,
+                    depth: test.depth,
+                    max_fan_out: test.max_fan_out,
+                }
+            );
+        }
+        assert_eq!(Report::catalog().len(), Netlist::chip_names().count());
+    }
+
+    #[test]
+    fn test_compare_implementations() {
+        // Or8Way folds left to right; a balanced tree has the same gates
+        // but half the depth.
+        let tree = Netlist::capture("Or8Way", || {
+            let input: [Wire; 8] = Wire::bus("in");
+            let pairs: [Wire; 4] =
+                std::array::from_fn(|idx| or(input[2 * idx], input[2 * idx + 1]));
+            pin("out", or(or(pairs[0], pairs[1]), or(pairs[2], pairs[3])))
+        });
+        let chain = Netlist::chip("Or8Way").unwrap();
+
+        assert_eq!(tree.nand_count(), chain.nand_count());
+        assert_eq!((tree.depth(), chain.depth()), (6, 14));
+    }
+}
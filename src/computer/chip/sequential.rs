@@ -0,0 +1,487 @@
+use crate::computer::chip::arithmetic::inc_16;
+use crate::computer::gate::{dmux_4_way, dmux_8_way, mux, mux_16, mux_4_way_16, mux_8_way_16, or};
+
+/// The one primitive sequential chip: `out(t) = in(t - 1)`. Everything else
+/// in this module is built from it and the combinational gates.
+///
+/// Every chip here has the same three clock methods: `tick` samples the
+/// inputs on the rising edge, `tock` changes the outputs on the falling edge,
+/// and `clock` does both.
+#[derive(Clone, Default)]
+pub struct Dff {
+    state: bool,
+    sampled: bool,
+}
+
+impl Dff {
+    pub fn new() -> Self {
+        Dff {
+            state: false,
+            sampled: false,
+        }
+    }
+
+    pub fn out(&self) -> bool {
+        self.state
+    }
+
+    pub fn tick(&mut self, input: bool) {
+        self.sampled = input;
+    }
+
+    pub fn tock(&mut self) {
+        self.state = self.sampled;
+    }
+
+    pub fn clock(&mut self, input: bool) {
+        self.tick(input);
+        self.tock();
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Bit {
+    dff: Dff,
+}
+
+impl Bit {
+    pub fn new() -> Self {
+        Bit { dff: Dff::new() }
+    }
+
+    pub fn out(&self) -> bool {
+        self.dff.out()
+    }
+
+    pub fn tick(&mut self, input: bool, load: bool) {
+        self.dff.tick(mux(self.dff.out(), input, load));
+    }
+
+    pub fn tock(&mut self) {
+        self.dff.tock();
+    }
+
+    pub fn clock(&mut self, input: bool, load: bool) {
+        self.tick(input, load);
+        self.tock();
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Register {
+    bits: [Bit; 16],
+}
+
+impl Register {
+    pub fn new() -> Self {
+        Register {
+            bits: std::array::from_fn(|_| Bit::new()),
+        }
+    }
+
+    pub fn out(&self) -> [bool; 16] {
+        std::array::from_fn(|idx| self.bits[idx].out())
+    }
+
+    pub fn tick(&mut self, input: [bool; 16], load: bool) {
+        for (bit, input) in self.bits.iter_mut().zip(input) {
+            bit.tick(input, load);
+        }
+    }
+
+    pub fn tock(&mut self) {
+        self.bits.iter_mut().for_each(Bit::tock);
+    }
+
+    pub fn clock(&mut self, input: [bool; 16], load: bool) {
+        self.tick(input, load);
+        self.tock();
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Ram8 {
+    registers: [Register; 8],
+}
+
+impl Ram8 {
+    pub fn new() -> Self {
+        Ram8 {
+            registers: std::array::from_fn(|_| Register::new()),
+        }
+    }
+
+    pub fn out(&self, address: [bool; 3]) -> [bool; 16] {
+        let out = |idx: usize| self.registers[idx].out();
+        mux_8_way_16(
+            (out(0), out(1), out(2), out(3)),
+            (out(4), out(5), out(6), out(7)),
+            address,
+        )
+    }
+
+    pub fn tick(&mut self, input: [bool; 16], load: bool, address: [bool; 3]) {
+        for (register, load) in self.registers.iter_mut().zip(dmux_8_way(load, address)) {
+            register.tick(input, load);
+        }
+    }
+
+    pub fn tock(&mut self) {
+        self.registers.iter_mut().for_each(Register::tock);
+    }
+
+    pub fn clock(&mut self, input: [bool; 16], load: bool, address: [bool; 3]) {
+        self.tick(input, load, address);
+        self.tock();
+    }
+}
+
+/// Splits an address bus into the low bits that go to every part and the
+/// high bits that select one of them.
+fn split<const N: usize, const LOW: usize, const HIGH: usize>(
+    address: [bool; N],
+) -> ([bool; LOW], [bool; HIGH]) {
+    (
+        std::array::from_fn(|idx| address[idx]),
+        std::array::from_fn(|idx| address[LOW + idx]),
+    )
+}
+
+/// Builds a memory out of eight copies of `$part`, using the top three
+/// address bits to pick the part.
+macro_rules! ram_8_way {
+    ($name:ident, $part:ident, $bits:literal, $part_bits:literal) => {
+        #[derive(Clone)]
+        pub struct $name {
+            parts: Vec<$part>,
+        }
+
+        impl $name {
+            pub fn new() -> Self {
+                $name {
+                    parts: vec![$part::new(); 8],
+                }
+            }
+
+            pub fn out(&self, address: [bool; $bits]) -> [bool; 16] {
+                let (low, high) = split::<$bits, $part_bits, 3>(address);
+                let out = |idx: usize| self.parts[idx].out(low);
+                mux_8_way_16(
+                    (out(0), out(1), out(2), out(3)),
+                    (out(4), out(5), out(6), out(7)),
+                    high,
+                )
+            }
+
+            pub fn tick(&mut self, input: [bool; 16], load: bool, address: [bool; $bits]) {
+                let (low, high) = split::<$bits, $part_bits, 3>(address);
+                for (part, load) in self.parts.iter_mut().zip(dmux_8_way(load, high)) {
+                    part.tick(input, load, low);
+                }
+            }
+
+            pub fn tock(&mut self) {
+                self.parts.iter_mut().for_each($part::tock);
+            }
+
+            pub fn clock(&mut self, input: [bool; 16], load: bool, address: [bool; $bits]) {
+                self.tick(input, load, address);
+                self.tock();
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::new()
+            }
+        }
+    };
+}
+
+ram_8_way!(Ram64, Ram8, 6, 3);
+ram_8_way!(Ram512, Ram64, 9, 6);
+ram_8_way!(Ram4K, Ram512, 12, 9);
+
+/// The data memory below the screen map, four `Ram4K` selected by the top
+/// two address bits.
+#[derive(Clone)]
+pub struct Ram16K {
+    parts: Vec<Ram4K>,
+}
+
+impl Ram16K {
+    pub fn new() -> Self {
+        Ram16K {
+            parts: vec![Ram4K::new(); 4],
+        }
+    }
+
+    pub fn out(&self, address: [bool; 14]) -> [bool; 16] {
+        let (low, high) = split::<14, 12, 2>(address);
+        let out = |idx: usize| self.parts[idx].out(low);
+        mux_4_way_16(out(0), out(1), out(2), out(3), high)
+    }
+
+    pub fn tick(&mut self, input: [bool; 16], load: bool, address: [bool; 14]) {
+        let (low, high) = split::<14, 12, 2>(address);
+        for (part, load) in self.parts.iter_mut().zip(dmux_4_way(load, high)) {
+            part.tick(input, load, low);
+        }
+    }
+
+    pub fn tock(&mut self) {
+        self.parts.iter_mut().for_each(Ram4K::tock);
+    }
+
+    pub fn clock(&mut self, input: [bool; 16], load: bool, address: [bool; 14]) {
+        self.tick(input, load, address);
+        self.tock();
+    }
+}
+
+impl Default for Ram16K {
+    fn default() -> Self {
+        Ram16K::new()
+    }
+}
+
+/// The program counter. On each clock `reset` wins over `load`, which wins
+/// over `inc`; with none of them set it keeps its value.
+#[derive(Clone, Default)]
+pub struct Pc {
+    register: Register,
+}
+
+impl Pc {
+    pub fn new() -> Self {
+        Pc {
+            register: Register::new(),
+        }
+    }
+
+    pub fn out(&self) -> [bool; 16] {
+        self.register.out()
+    }
+
+    pub fn tick(&mut self, input: [bool; 16], load: bool, inc: bool, reset: bool) {
+        let out = self.register.out();
+        let next = mux_16(out, inc_16(out), inc);
+        let next = mux_16(next, input, load);
+        let next = mux_16(next, [false; 16], reset);
+        self.register.tick(next, or(or(load, inc), reset));
+    }
+
+    pub fn tock(&mut self) {
+        self.register.tock();
+    }
+
+    pub fn clock(&mut self, input: [bool; 16], load: bool, inc: bool, reset: bool) {
+        self.tick(input, load, inc, reset);
+        self.tock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::chip::clock::Clock;
+    use crate::computer::chip::memory::Ram;
+    use crate::computer::gate::{from_bits, to_bits};
+
+    /// A small deterministic generator so memory tests touch scattered
+    /// addresses without pulling in a dependency.
+    struct Lcg(u32);
+
+    impl Lcg {
+        fn next(&mut self) -> u16 {
+            self.0 = self.0.wrapping_mul(1103515245).wrapping_add(12345);
+            (self.0 >> 16) as u16
+        }
+    }
+
+    #[test]
+    fn test_bit() {
+        struct Test {
+            input: (bool, bool),
+            expected: bool,
+        }
+        let tests = vec![
+            Test {
+                input: (true, false),
+                expected: false,
+            },
+            Test {
+                input: (true, true),
+                expected: true,
+            },
+            Test {
+                input: (false, false),
+                expected: true,
+            },
+            Test {
+                input: (false, true),
+                expected: false,
+            },
+        ];
+
+        let mut bit = Bit::new();
+        for Test {
+            input: (input, load),
+            expected,
+        } in tests
+        {
+            bit.clock(input, load);
+            assert_eq!(expected, bit.out());
+        }
+    }
+
+    #[test]
+    fn test_half_cycles() {
+        let mut clock = Clock::new();
+        let mut ram = Ram64::new();
+        let address = to_bits(42);
+
+        // `tick, output; tock, output;` as in RAM64.tst.
+        ram.tick(to_bits(1234), true, address);
+        clock.tick().unwrap();
+        assert_eq!(format!("{clock} {}", from_bits(ram.out(address))), "0+ 0");
+        ram.tock();
+        clock.tock().unwrap();
+        assert_eq!(format!("{clock} {}", from_bits(ram.out(address))), "1 1234");
+
+        let mut pc = Pc::new();
+        pc.tick(to_bits(0), false, true, false);
+        assert_eq!(from_bits(pc.out()), 0);
+        pc.tock();
+        assert_eq!(from_bits(pc.out()), 1);
+    }
+
+    #[test]
+    fn test_register() {
+        let mut register = Register::new();
+
+        register.clock(to_bits(12345), false);
+        assert_eq!(from_bits(register.out()), 0);
+        register.clock(to_bits(12345), true);
+        assert_eq!(from_bits(register.out()), 12345);
+        register.clock(to_bits(0xFFFF), false);
+        assert_eq!(from_bits(register.out()), 12345);
+    }
+
+    #[test]
+    fn test_pc() {
+        struct Test {
+            input: (u16, bool, bool, bool),
+            expected: u16,
+        }
+        let tests = vec![
+            Test {
+                input: (0, false, false, false),
+                expected: 0,
+            },
+            Test {
+                input: (0, false, true, false),
+                expected: 1,
+            },
+            Test {
+                input: (-32123i16 as u16, false, true, false),
+                expected: 2,
+            },
+            Test {
+                input: (-32123i16 as u16, true, true, false),
+                expected: -32123i16 as u16,
+            },
+            Test {
+                input: (-32123i16 as u16, false, true, false),
+                expected: -32122i16 as u16,
+            },
+            Test {
+                input: (12345, true, false, false),
+                expected: 12345,
+            },
+            Test {
+                input: (12345, true, true, true),
+                expected: 0,
+            },
+            Test {
+                input: (0xFFFF, true, false, false),
+                expected: 0xFFFF,
+            },
+            Test {
+                input: (0, false, true, false),
+                expected: 0,
+            },
+            Test {
+                input: (0, false, false, false),
+                expected: 0,
+            },
+        ];
+
+        let mut pc = Pc::new();
+        for Test {
+            input: (input, load, inc, reset),
+            expected,
+        } in tests
+        {
+            pc.clock(to_bits(input), load, inc, reset);
+            assert_eq!(expected, from_bits(pc.out()));
+        }
+    }
+
+    /// Drives `chip` and the fast `Ram` with the same writes and checks that
+    /// every read agrees, including the word a write has just replaced.
+    fn compare_with_ram<C, const N: usize>(
+        chip: &mut C,
+        out: fn(&C, [bool; N]) -> [bool; 16],
+        clock: fn(&mut C, [bool; 16], bool, [bool; N]),
+        operations: usize,
+    ) {
+        let mut ram = Ram::new();
+        let mut random = Lcg(N as u32);
+
+        for _ in 0..operations {
+            let address = random.next() & ((1 << N) - 1);
+            let value = random.next();
+            let load = random.next().is_multiple_of(2);
+
+            assert_eq!(
+                from_bits(out(chip, to_bits(address))),
+                ram.read(address).unwrap()
+            );
+
+            clock(chip, to_bits(value), load, to_bits(address));
+            if load {
+                ram.load(value, address).unwrap();
+            }
+            assert_eq!(
+                from_bits(out(chip, to_bits(address))),
+                ram.read(address).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_ram8() {
+        compare_with_ram(&mut Ram8::new(), Ram8::out, Ram8::clock, 200);
+    }
+
+    #[test]
+    fn test_ram64() {
+        compare_with_ram(&mut Ram64::new(), Ram64::out, Ram64::clock, 200);
+    }
+
+    #[test]
+    fn test_ram512() {
+        compare_with_ram(&mut Ram512::new(), Ram512::out, Ram512::clock, 100);
+    }
+
+    #[test]
+    fn test_ram4k() {
+        compare_with_ram(&mut Ram4K::new(), Ram4K::out, Ram4K::clock, 50);
+    }
+
+    #[test]
+    fn test_ram16k() {
+        compare_with_ram(&mut Ram16K::new(), Ram16K::out, Ram16K::clock, 20);
+    }
+}
@@ -0,0 +1,236 @@
+use std::ops::RangeInclusive;
+
+use super::{Computer, Cycle};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchKind {
+    /// The CPU reads the address through M.
+    Read,
+    /// The CPU writes the address, even with the value it already holds.
+    Write,
+    /// The CPU writes a value different from the one stored.
+    Change,
+}
+
+/// A RAM address range, which can span the screen and keyboard maps, plus
+/// the kind of access that should stop the run loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watchpoint {
+    pub addresses: RangeInclusive<u16>,
+    pub kind: WatchKind,
+}
+
+impl Watchpoint {
+    pub fn new(address: u16, kind: WatchKind) -> Self {
+        Watchpoint {
+            addresses: address..=address,
+            kind,
+        }
+    }
+
+    pub fn range(addresses: RangeInclusive<u16>, kind: WatchKind) -> Self {
+        Watchpoint { addresses, kind }
+    }
+
+    fn check(&self, cycle: &Cycle) -> Option<WatchHit> {
+        let hit = |address: u16, old: u16, new: u16| WatchHit {
+            kind: self.kind,
+            address,
+            pc: cycle.pc,
+            old,
+            new,
+        };
+
+        match self.kind {
+            WatchKind::Read => cycle
+                .read
+                .filter(|read| self.addresses.contains(&read.address))
+                .map(|read| hit(read.address, read.value, read.value)),
+            WatchKind::Write => cycle
+                .write
+                .filter(|write| self.addresses.contains(&write.address))
+                .map(|write| hit(write.address, write.old, write.new)),
+            WatchKind::Change => cycle
+                .write
+                .filter(|write| self.addresses.contains(&write.address) && write.old != write.new)
+                .map(|write| hit(write.address, write.old, write.new)),
+        }
+    }
+}
+
+/// Which watchpoint fired, at which instruction, and the value before and
+/// after the access. For reads `old` and `new` are both the value read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatchHit {
+    pub kind: WatchKind,
+    pub address: u16,
+    pub pc: u16,
+    pub old: u16,
+    pub new: u16,
+}
+
+impl Computer {
+    /// Stops the run loop before the instruction at `address` executes.
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        let len = self.watchpoints.len();
+        self.watchpoints.retain(|watch| watch != watchpoint);
+        self.watchpoints.len() != len
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn clear_debug_points(&mut self) {
+        self.breakpoints.clear();
+        self.watchpoints.clear();
+    }
+
+    pub(super) fn at_breakpoint(&self) -> bool {
+        self.breakpoints.contains(&self.pc())
+    }
+
+    /// The first watchpoint, in the order they were added, that `cycle`
+    /// triggers.
+    pub(super) fn check_watchpoints(&self, cycle: &Cycle) -> Option<WatchHit> {
+        self.watchpoints
+            .iter()
+            .find_map(|watchpoint| watchpoint.check(cycle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::computer::StopReason;
+
+    fn computer(source: &str) -> Computer {
+        let mut computer = Computer::new();
+        computer.load_program(assemble(source).unwrap()).unwrap();
+        computer
+    }
+
+    const COUNTER: &str = "
+        @i
+        M=0
+    (LOOP)
+        @i
+        M=M+1
+        @SCREEN
+        M=0
+        @KBD
+        D=M
+        @LOOP
+        0;JMP
+    ";
+
+    #[test]
+    fn test_breakpoint() {
+        let mut computer = computer(COUNTER);
+        computer.add_breakpoint(4);
+
+        assert!(matches!(
+            computer.run(100),
+            StopReason::Breakpoint { pc: 4 }
+        ));
+        assert_eq!(computer.cycles(), 4);
+
+        // Resuming steps over the breakpoint we are sitting on.
+        assert!(matches!(
+            computer.run(100),
+            StopReason::Breakpoint { pc: 4 }
+        ));
+        assert_eq!(computer.cycles(), 12);
+
+        assert!(computer.remove_breakpoint(4));
+        assert!(matches!(computer.run(100), StopReason::CycleLimit));
+    }
+
+    #[test]
+    fn test_change_watchpoint() {
+        let mut computer = computer(COUNTER);
+        computer.ram_mut().load(7, 16).unwrap();
+        computer.add_watchpoint(Watchpoint::new(16, WatchKind::Change));
+
+        let reason = computer.run(100);
+
+        assert!(matches!(
+            reason,
+            StopReason::Watchpoint(WatchHit {
+                kind: WatchKind::Change,
+                address: 16,
+                pc: 1,
+                old: 7,
+                new: 0,
+            })
+        ));
+
+        assert!(matches!(
+            computer.run(100),
+            StopReason::Watchpoint(WatchHit {
+                pc: 3,
+                old: 0,
+                new: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_write_and_change_on_screen() {
+        let mut computer = computer(COUNTER);
+        computer.add_watchpoint(Watchpoint::range(16384..=24575, WatchKind::Change));
+
+        // The screen word is written with the zero it already holds.
+        assert!(matches!(computer.run(100), StopReason::CycleLimit));
+
+        computer.reset();
+        computer.add_watchpoint(Watchpoint::range(16384..=24575, WatchKind::Write));
+        assert!(matches!(
+            computer.run(100),
+            StopReason::Watchpoint(WatchHit {
+                kind: WatchKind::Write,
+                address: 16384,
+                pc: 5,
+                old: 0,
+                new: 0,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_read_keyboard() {
+        let mut computer = computer(COUNTER);
+        computer.ram_mut().load(65, 24576).unwrap();
+        computer.add_watchpoint(Watchpoint::new(24576, WatchKind::Read));
+
+        assert!(matches!(
+            computer.run(100),
+            StopReason::Watchpoint(WatchHit {
+                kind: WatchKind::Read,
+                address: 24576,
+                pc: 7,
+                old: 65,
+                new: 65,
+            })
+        ));
+        assert_eq!(computer.cpu().d_register, 65);
+    }
+}
@@ -0,0 +1,208 @@
+use super::chip::cpu::gate_cpu::GateCpu;
+use super::chip::cpu::instructions::CpuInstructions;
+use super::chip::cpu::{CPUResponse, Cpu};
+use super::chip::memory::{Ram, Rom};
+use super::Error;
+
+/// The registers and outputs of one CPU after a cycle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CpuState {
+    pub a_register: u16,
+    pub d_register: u16,
+    pub pc: u16,
+    pub response: CPUResponse,
+}
+
+/// The first cycle where `Cpu` and `GateCpu` disagreed, numbered from 1 like
+/// `Computer::cycles`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Divergence {
+    pub cycle: u64,
+    pub pc: u16,
+    pub instruction: u16,
+    pub cpu: CpuState,
+    pub gate_cpu: CpuState,
+}
+
+/// Executes one ROM on the fast `Cpu` and on `GateCpu`, each with its own
+/// RAM, and compares them after every cycle. Instructions are decoded
+/// permissively on the `Cpu` side since the gate CPU accepts any word.
+/// Both read a word as a C-instruction only when its top three bits are set.
+pub struct DifferentialRunner {
+    rom: Rom,
+    cpu: Cpu,
+    cpu_ram: Ram,
+    gate_cpu: GateCpu,
+    gate_ram: Ram,
+    cycles: u64,
+}
+
+impl DifferentialRunner {
+    pub fn new(program: &[u16]) -> Result<Self, Error> {
+        let mut rom = Rom::new();
+        rom.load_program(program)?;
+        Ok(DifferentialRunner {
+            rom,
+            cpu: Cpu::new(),
+            cpu_ram: Ram::new(),
+            gate_cpu: GateCpu::new(),
+            gate_ram: Ram::new(),
+            cycles: 0,
+        })
+    }
+
+    /// Sets a RAM word on both sides, e.g. the inputs of a test program.
+    pub fn load_ram(&mut self, value: u16, address: u16) -> Result<(), Error> {
+        self.cpu_ram.load(value, address)?;
+        self.gate_ram.load(value, address)?;
+        Ok(())
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Runs one cycle on both CPUs, returning the divergence if their
+    /// registers or responses differ afterwards.
+    pub fn step(&mut self) -> Result<Option<Divergence>, Error> {
+        let pc = self.cpu.pc;
+        let instruction = self.rom.read(pc)?;
+
+        let response = self.cpu.execute(
+            CpuInstructions::from_bits(instruction),
+            read_m(&self.cpu_ram, self.cpu.a_register),
+        );
+        if response.write_m {
+            self.cpu_ram.load(response.out_m, response.address_m)?;
+        }
+
+        let gate_response = self.gate_cpu.execute(
+            self.rom.read(self.gate_cpu.pc())?,
+            read_m(&self.gate_ram, self.gate_cpu.a_register()),
+        );
+        if gate_response.write_m {
+            self.gate_ram
+                .load(gate_response.out_m, gate_response.address_m)?;
+        }
+
+        self.cycles += 1;
+
+        let cpu = CpuState {
+            a_register: self.cpu.a_register,
+            d_register: self.cpu.d_register,
+            pc: self.cpu.pc,
+            response,
+        };
+        let gate_cpu = CpuState {
+            a_register: self.gate_cpu.a_register(),
+            d_register: self.gate_cpu.d_register(),
+            pc: self.gate_cpu.pc(),
+            response: gate_response,
+        };
+
+        if cpu == gate_cpu {
+            return Ok(None);
+        }
+        Ok(Some(Divergence {
+            cycle: self.cycles,
+            pc,
+            instruction,
+            cpu,
+            gate_cpu,
+        }))
+    }
+
+    /// Runs up to `max_cycles` cycles, stopping at the first divergence.
+    pub fn run(&mut self, max_cycles: u64) -> Result<Option<Divergence>, Error> {
+        for _ in 0..max_cycles {
+            if let Some(divergence) = self.step()? {
+                return Ok(Some(divergence));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The CPU always presents `RAM[A]` on `inM`; addresses past the keyboard
+/// read as 0.
+fn read_m(ram: &Ram, address: u16) -> u16 {
+    ram.read(address).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    const MULT: &str = "
+        @R2
+        M=0
+    (LOOP)
+        @R1
+        D=M
+        @END
+        D;JEQ
+        @R0
+        D=M
+        @R2
+        M=D+M
+        @R1
+        M=M-1
+        @LOOP
+        0;JMP
+    (END)
+        @END
+        0;JMP
+    ";
+
+    #[test]
+    fn test_programs_agree() -> Result<(), Error> {
+        let mut runner = DifferentialRunner::new(&assemble(MULT).unwrap())?;
+        runner.load_ram(123, 0)?;
+        runner.load_ram(45, 1)?;
+
+        assert_eq!(runner.run(1000)?, None);
+        assert_eq!(runner.cpu_ram.read(2)?, 123 * 45);
+        assert_eq!(runner.gate_ram.read(2)?, 123 * 45);
+
+        // Words starting 0b100, 0b101 and 0b110 load A on both.
+        let program = [
+            0b1000_0000_0000_0101,
+            0b1010_0000_0000_0110,
+            0b1100_0000_0000_0111,
+        ];
+        let mut runner = DifferentialRunner::new(&program)?;
+        assert_eq!(runner.run(3)?, None);
+        assert_eq!(runner.gate_cpu.a_register(), 0b0100_0000_0000_0111);
+        Ok(())
+    }
+
+    #[test]
+    fn test_jump_with_a_dest() -> Result<(), Error> {
+        // A jump whose dest also writes A goes to the old A on both.
+        let mut runner = DifferentialRunner::new(&assemble("@5\nD=A\n@3\nA=D;JMP").unwrap())?;
+        assert_eq!(runner.run(4)?, None);
+        assert_eq!(runner.cpu.pc, 3);
+        assert_eq!(runner.run(1)?, None);
+        assert_eq!(runner.cpu.pc, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reports_first_divergence() -> Result<(), Error> {
+        let mut runner = DifferentialRunner::new(&assemble("@7\nD=D+A\n@0\n0;JMP").unwrap())?;
+        assert_eq!(runner.step()?, None);
+
+        runner.cpu.d_register = 1;
+        let divergence = runner.run(10)?.unwrap();
+
+        assert_eq!(divergence.cycle, 2);
+        assert_eq!(divergence.pc, 1);
+        assert_eq!(divergence.instruction, 0b1110000010010000);
+        assert_eq!(divergence.cpu.d_register, 8);
+        assert_eq!(divergence.gate_cpu.d_register, 7);
+        assert_eq!(divergence.cpu.response.out_m, 8);
+        assert_eq!(divergence.gate_cpu.response.out_m, 7);
+        Ok(())
+    }
+}
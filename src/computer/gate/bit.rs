@@ -0,0 +1,48 @@
+/// Named pins of a chip, each a single wire or a bus.
+pub type Ports<'a, B> = &'a [(&'static str, &'a [B])];
+
+/// A value the gate library can compute on. Everything is built from
+/// `nand`; `constant` supplies the fixed `false`/`true` wires some chips
+/// need, such as the zero bus in the ALU.
+pub trait Bit: Copy {
+    fn nand(self, other: Self) -> Self;
+
+    fn constant(value: bool) -> Self;
+
+    /// Whether the library chips call `enter` and `leave`. Values leave it
+    /// unset so the chips skip gathering their pins.
+    const RECORDS: bool = false;
+
+    /// Called by every library chip before computing, with its input pins,
+    /// when `RECORDS` is set. Recorders use it to keep the chip hierarchy.
+    fn enter(_chip: &'static str, _inputs: Ports<Self>) {}
+
+    /// Closes the chip opened by the matching `enter`, with its output pins.
+    fn leave(_outputs: Ports<Self>) {}
+}
+
+impl Bit for bool {
+    fn nand(self, other: Self) -> Self {
+        !(self & other)
+    }
+
+    fn constant(value: bool) -> Self {
+        value
+    }
+}
+
+/// 64 independent lanes, bit `i` of every operand belonging to test vector
+/// `i`, so one call evaluates a chip on 64 inputs at once.
+impl Bit for u64 {
+    fn nand(self, other: Self) -> Self {
+        !(self & other)
+    }
+
+    fn constant(value: bool) -> Self {
+        if value {
+            u64::MAX
+        } else {
+            0
+        }
+    }
+}
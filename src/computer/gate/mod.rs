@@ -1,97 +1,195 @@
+pub use self::bit::{Bit, Ports};
+
+mod bit;
+pub mod symbolic;
 #[cfg(test)]
 mod tests;
 
-pub fn nand(a: bool, b: bool) -> bool {
-    !(a & b)
+/// Runs the statements of a library chip between `Bit::enter` and
+/// `Bit::leave`, with its input and output pins:
+///
+/// ```ignore
+/// chip!("And"("a": &[a], "b": &[b]) -> ("out": &[out]) {
+///     let out = not(nand(a, b));
+/// });
+/// ```
+///
+/// The pins are only gathered when `B::RECORDS` is set, so for plain values
+/// this is just the statements. Their bindings stay in scope afterwards.
+macro_rules! chip {
+    (
+        $name:literal($($input:literal: $in:expr),* $(,)?)
+        -> ($($output:literal: $out:expr),* $(,)?)
+        { $($body:tt)* }
+    ) => {
+        if B::RECORDS {
+            B::enter($name, &[$(($input, $in)),*]);
+        }
+        $($body)*
+        if B::RECORDS {
+            B::leave(&[$(($output, $out)),*]);
+        }
+    };
+}
+pub(crate) use chip;
+
+pub fn nand<B: Bit>(a: B, b: B) -> B {
+    a.nand(b)
 }
-pub fn not(a: bool) -> bool {
-    nand(a, a)
+pub fn not<B: Bit>(a: B) -> B {
+    chip!("Not"("in": &[a]) -> ("out": &[out]) {
+        let out = nand(a, a);
+    });
+    out
 }
 
-pub fn and(a: bool, b: bool) -> bool {
-    not(nand(a, b))
+pub fn and<B: Bit>(a: B, b: B) -> B {
+    chip!("And"("a": &[a], "b": &[b]) -> ("out": &[out]) {
+        let out = not(nand(a, b));
+    });
+    out
 }
 
-pub fn or(a: bool, b: bool) -> bool {
-    nand(nand(a, a), nand(b, b))
+pub fn or<B: Bit>(a: B, b: B) -> B {
+    chip!("Or"("a": &[a], "b": &[b]) -> ("out": &[out]) {
+        let out = nand(nand(a, a), nand(b, b));
+    });
+    out
 }
 
-pub fn xor(a: bool, b: bool) -> bool {
-    or(and(not(a), b), and(a, not(b)))
+pub fn xor<B: Bit>(a: B, b: B) -> B {
+    chip!("Xor"("a": &[a], "b": &[b]) -> ("out": &[out]) {
+        let out = or(and(not(a), b), and(a, not(b)));
+    });
+    out
 }
 
-pub fn mux(a: bool, b: bool, sel: bool) -> bool {
-    or(and(a, not(sel)), and(b, sel))
+pub fn mux<B: Bit>(a: B, b: B, sel: B) -> B {
+    chip!("Mux"("a": &[a], "b": &[b], "sel": &[sel]) -> ("out": &[out]) {
+        let out = or(and(a, not(sel)), and(b, sel));
+    });
+    out
 }
 
-pub fn dmux(input: bool, sel: bool) -> [bool; 2] {
-    [and(input, not(sel)), and(input, sel)]
+pub fn dmux<B: Bit>(input: B, sel: B) -> [B; 2] {
+    chip!("DMux"("in": &[input], "sel": &[sel]) -> ("a": &[a], "b": &[b]) {
+        let [a, b] = [and(input, not(sel)), and(input, sel)];
+    });
+    [a, b]
 }
 
-pub fn not_16(input: [bool; 16]) -> [bool; 16] {
-    input.map(not)
+pub fn not_16<B: Bit>(input: [B; 16]) -> [B; 16] {
+    chip!("Not16"("in": &input) -> ("out": &out) {
+        let out = input.map(not);
+    });
+    out
 }
 
-pub fn and_16(a: [bool; 16], b: [bool; 16]) -> [bool; 16] {
-    let mut out = [false; 16];
-    for (idx, (a, b)) in a.iter().zip(b.iter()).enumerate() {
-        out[idx] = and(*a, *b)
-    }
+pub fn and_16<B: Bit>(a: [B; 16], b: [B; 16]) -> [B; 16] {
+    chip!("And16"("a": &a, "b": &b) -> ("out": &out) {
+        let out = std::array::from_fn(|idx| and(a[idx], b[idx]));
+    });
     out
 }
 
-pub fn or_16(a: [bool; 16], b: [bool; 16]) -> [bool; 16] {
-    let mut out = [false; 16];
-    for (idx, (a, b)) in a.iter().zip(b.iter()).enumerate() {
-        out[idx] = or(*a, *b)
-    }
+pub fn or_16<B: Bit>(a: [B; 16], b: [B; 16]) -> [B; 16] {
+    chip!("Or16"("a": &a, "b": &b) -> ("out": &out) {
+        let out = std::array::from_fn(|idx| or(a[idx], b[idx]));
+    });
     out
 }
 
-pub fn mux_16(a: [bool; 16], b: [bool; 16], sel: bool) -> [bool; 16] {
-    let mut out = [false; 16];
-    for (idx, (a, b)) in a.iter().zip(b.iter()).enumerate() {
-        out[idx] = mux(*a, *b, sel)
-    }
+pub fn mux_16<B: Bit>(a: [B; 16], b: [B; 16], sel: B) -> [B; 16] {
+    chip!("Mux16"("a": &a, "b": &b, "sel": &[sel]) -> ("out": &out) {
+        let out = std::array::from_fn(|idx| mux(a[idx], b[idx], sel));
+    });
     out
 }
 
-pub fn or_8_way(input: [bool; 8]) -> bool {
-    input.iter().fold(false, |acc, a| or(*a, acc))
+pub fn or_8_way<B: Bit>(input: [B; 8]) -> B {
+    chip!("Or8Way"("in": &input) -> ("out": &[out]) {
+        let out = input[1..].iter().fold(input[0], |acc, a| or(*a, acc));
+    });
+    out
 }
 
-pub fn mux_4_way_16(
-    a: [bool; 16],
-    b: [bool; 16],
-    c: [bool; 16],
-    d: [bool; 16],
-    sel: [bool; 2],
-) -> [bool; 16] {
-    mux_16(mux_16(a, b, sel[0]), mux_16(c, d, sel[0]), sel[1])
+pub fn mux_4_way_16<B: Bit>(
+    a: [B; 16],
+    b: [B; 16],
+    c: [B; 16],
+    d: [B; 16],
+    sel: [B; 2],
+) -> [B; 16] {
+    chip!("Mux4Way16"("a": &a, "b": &b, "c": &c, "d": &d, "sel": &sel) -> ("out": &out) {
+        let out = mux_16(mux_16(a, b, sel[0]), mux_16(c, d, sel[0]), sel[1]);
+    });
+    out
 }
 
-pub fn mux_8_way_16(
-    a: ([bool; 16], [bool; 16], [bool; 16], [bool; 16]),
-    b: ([bool; 16], [bool; 16], [bool; 16], [bool; 16]),
-    sel: [bool; 3],
-) -> [bool; 16] {
-    mux_16(
-        mux_4_way_16(a.0, a.1, a.2, a.3, [sel[0], sel[1]]),
-        mux_4_way_16(b.0, b.1, b.2, b.3, [sel[0], sel[1]]),
-        sel[2],
-    )
+pub fn mux_8_way_16<B: Bit>(
+    a: ([B; 16], [B; 16], [B; 16], [B; 16]),
+    b: ([B; 16], [B; 16], [B; 16], [B; 16]),
+    sel: [B; 3],
+) -> [B; 16] {
+    chip!("Mux8Way16"(
+        "a": &a.0,
+        "b": &a.1,
+        "c": &a.2,
+        "d": &a.3,
+        "e": &b.0,
+        "f": &b.1,
+        "g": &b.2,
+        "h": &b.3,
+        "sel": &sel,
+    ) -> ("out": &out) {
+        let out = mux_16(
+            mux_4_way_16(a.0, a.1, a.2, a.3, [sel[0], sel[1]]),
+            mux_4_way_16(b.0, b.1, b.2, b.3, [sel[0], sel[1]]),
+            sel[2],
+        );
+    });
+    out
 }
 
-pub fn dmux_4_way(input: bool, sel: [bool; 2]) -> [bool; 4] {
-    let [c0, c1] = dmux(input, sel[1]);
-    let [a, b] = dmux(c0, sel[0]);
-    let [c, d] = dmux(c1, sel[0]);
+pub fn dmux_4_way<B: Bit>(input: B, sel: [B; 2]) -> [B; 4] {
+    chip!("DMux4Way"("in": &[input], "sel": &sel) -> ("a": &[a], "b": &[b], "c": &[c], "d": &[d]) {
+        let [c0, c1] = dmux(input, sel[1]);
+        let [a, b] = dmux(c0, sel[0]);
+        let [c, d] = dmux(c1, sel[0]);
+    });
     [a, b, c, d]
 }
 
-pub fn dmux_8_way(input: bool, sel: [bool; 3]) -> [bool; 8] {
-    let [c0, c1] = dmux(input, sel[2]);
-    let [a, b, c, d] = dmux_4_way(c0, [sel[0], sel[1]]);
-    let [e, f, g, h] = dmux_4_way(c1, [sel[0], sel[1]]);
+pub fn dmux_8_way<B: Bit>(input: B, sel: [B; 3]) -> [B; 8] {
+    chip!("DMux8Way"(
+        "in": &[input],
+        "sel": &sel,
+    ) -> (
+        "a": &[a],
+        "b": &[b],
+        "c": &[c],
+        "d": &[d],
+        "e": &[e],
+        "f": &[f],
+        "g": &[g],
+        "h": &[h],
+    ) {
+        let [c0, c1] = dmux(input, sel[2]);
+        let [a, b, c, d] = dmux_4_way(c0, [sel[0], sel[1]]);
+        let [e, f, g, h] = dmux_4_way(c1, [sel[0], sel[1]]);
+    });
     [a, b, c, d, e, f, g, h]
 }
+
+/// Splits `value` into its `N` low bits, least significant first, the order
+/// every bus in this module uses.
+pub fn to_bits<const N: usize>(value: u16) -> [bool; N] {
+    std::array::from_fn(|idx| (value >> idx) & 1 == 1)
+}
+
+/// The inverse of `to_bits`.
+pub fn from_bits<const N: usize>(bits: [bool; N]) -> u16 {
+    bits.iter()
+        .enumerate()
+        .fold(0, |acc, (idx, bit)| acc | (*bit as u16) << idx)
+}
@@ -0,0 +1,326 @@
+use std::cell::RefCell;
+use std::ops::Range;
+
+use super::{Bit, Ports};
+
+/// A signal in the circuit being recorded by `Circuit::record`. Running a
+/// gate function on wires adds its NAND gates to that circuit instead of
+/// computing a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Wire(usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Input(String),
+    Constant(bool),
+    Nand(Wire, Wire),
+}
+
+/// One call of a library chip while recording: the pins it was called with
+/// and the nodes it created, including those of the chips inside it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub chip: &'static str,
+    /// The index of the enclosing instance, `None` at the top level.
+    pub parent: Option<usize>,
+    pub inputs: Vec<(&'static str, Vec<Wire>)>,
+    pub outputs: Vec<(&'static str, Vec<Wire>)>,
+    pub nodes: Range<usize>,
+}
+
+/// Every node created while recording, in creation order, so a gate's
+/// operands always come before it. Instances are in the order they were
+/// entered, so a parent comes before its children.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Circuit {
+    nodes: Vec<Node>,
+    instances: Vec<Instance>,
+}
+
+#[derive(Default)]
+struct Recording {
+    nodes: Vec<Node>,
+    instances: Vec<Instance>,
+    open: Vec<usize>,
+}
+
+thread_local! {
+    static RECORDING: RefCell<Vec<Recording>> = const { RefCell::new(Vec::new()) };
+}
+
+fn with_recording<T>(f: impl FnOnce(&mut Recording) -> T) -> T {
+    RECORDING.with_borrow_mut(|recording| {
+        f(recording
+            .last_mut()
+            .expect("wires can only be created inside Circuit::record"))
+    })
+}
+
+fn push(node: Node) -> Wire {
+    with_recording(|recording| {
+        recording.nodes.push(node);
+        Wire(recording.nodes.len() - 1)
+    })
+}
+
+fn pins(ports: Ports<Wire>) -> Vec<(&'static str, Vec<Wire>)> {
+    ports
+        .iter()
+        .map(|(name, wires)| (*name, wires.to_vec()))
+        .collect()
+}
+
+impl Wire {
+    pub fn input(name: &str) -> Wire {
+        push(Node::Input(name.to_string()))
+    }
+
+    /// `N` inputs named `name[0]` to `name[N - 1]`, least significant first.
+    pub fn bus<const N: usize>(name: &str) -> [Wire; N] {
+        std::array::from_fn(|idx| Wire::input(&format!("{name}[{idx}]")))
+    }
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl Bit for Wire {
+    const RECORDS: bool = true;
+
+    fn nand(self, other: Self) -> Self {
+        push(Node::Nand(self, other))
+    }
+
+    fn constant(value: bool) -> Self {
+        push(Node::Constant(value))
+    }
+
+    fn enter(chip: &'static str, inputs: Ports<Self>) {
+        with_recording(|recording| {
+            let start = recording.nodes.len();
+            recording.instances.push(Instance {
+                chip,
+                parent: recording.open.last().copied(),
+                inputs: pins(inputs),
+                outputs: Vec::new(),
+                nodes: start..start,
+            });
+            recording.open.push(recording.instances.len() - 1);
+        })
+    }
+
+    fn leave(outputs: Ports<Self>) {
+        with_recording(|recording| {
+            let idx = recording.open.pop().expect("leave without enter");
+            let instance = &mut recording.instances[idx];
+            instance.outputs = pins(outputs);
+            instance.nodes.end = recording.nodes.len();
+        })
+    }
+}
+
+impl Circuit {
+    /// Runs `build` with a fresh circuit that records every input, constant
+    /// and NAND gate it creates. Wires only mean something in the circuit
+    /// they were recorded in.
+    pub fn record<T>(build: impl FnOnce() -> T) -> (Circuit, T) {
+        RECORDING.with_borrow_mut(|recording| recording.push(Recording::default()));
+        let out = build();
+        let recording = RECORDING
+            .with_borrow_mut(|recording| recording.pop())
+            .expect("recording started above");
+        let circuit = Circuit {
+            nodes: recording.nodes,
+            instances: recording.instances,
+        };
+        (circuit, out)
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// The wires of the nodes at `range`, such as `Instance::nodes`.
+    pub fn wires(&self, range: Range<usize>) -> impl Iterator<Item = Wire> {
+        range.map(Wire)
+    }
+
+    /// The library chips called while recording, outermost first.
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    pub fn node(&self, wire: Wire) -> &Node {
+        &self.nodes[wire.0]
+    }
+
+    pub fn nand_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| matches!(node, Node::Nand(..)))
+            .count()
+    }
+
+    /// The input wires in the order they were created.
+    pub fn inputs(&self) -> impl Iterator<Item = (Wire, &str)> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, node)| match node {
+                Node::Input(name) => Some((Wire(idx), name.as_str())),
+                _ => None,
+            })
+    }
+
+    /// Computes every node with `inputs` given in the order of `inputs()`,
+    /// returning the values of `outputs`. With `u64` this runs 64 input
+    /// vectors at once.
+    pub fn evaluate<B: Bit>(&self, inputs: &[B], outputs: &[Wire]) -> Vec<B> {
+        let mut inputs = inputs.iter();
+        let mut values: Vec<B> = Vec::with_capacity(self.nodes.len());
+
+        for node in &self.nodes {
+            let value = match node {
+                Node::Input(name) => *inputs
+                    .next()
+                    .unwrap_or_else(|| panic!("no value for input {name}")),
+                Node::Constant(value) => B::constant(*value),
+                Node::Nand(a, b) => values[a.0].nand(values[b.0]),
+            };
+            values.push(value);
+        }
+
+        outputs.iter().map(|wire| values[wire.0]).collect()
+    }
+
+    /// `wire` written out as nested `nand(..)` calls over the input names.
+    /// Shared subcircuits are repeated, so keep this to small chips.
+    pub fn expression(&self, wire: Wire) -> String {
+        match self.node(wire) {
+            Node::Input(name) => name.clone(),
+            Node::Constant(value) => value.to_string(),
+            Node::Nand(a, b) => format!("nand({}, {})", self.expression(*a), self.expression(*b)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::chip::arithmetic::add_16;
+    use crate::computer::gate::{and, dmux_8_way, mux, not, to_bits, xor};
+
+    #[test]
+    fn test_expression() {
+        let (circuit, out) = Circuit::record(|| {
+            let a = Wire::input("a");
+            let b = Wire::input("b");
+            and(a, not(b))
+        });
+
+        assert_eq!(
+            circuit.expression(out),
+            "nand(nand(a, nand(b, b)), nand(a, nand(b, b)))"
+        );
+        assert_eq!(circuit.nand_count(), 3);
+        assert_eq!(
+            circuit.inputs().map(|(_, name)| name).collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_matches_bool() {
+        let (circuit, out) = Circuit::record(|| {
+            let [a, b, sel] = [Wire::input("a"), Wire::input("b"), Wire::input("sel")];
+            [xor(a, b), mux(a, b, sel)]
+        });
+
+        for bits in 0..8 {
+            let [a, b, sel] = to_bits::<3>(bits);
+            assert_eq!(
+                circuit.evaluate(&[a, b, sel], &out),
+                [xor(a, b), mux(a, b, sel)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluate_lanes() {
+        let (circuit, (sum, outputs)) = Circuit::record(|| {
+            let a = Wire::bus::<16>("a");
+            let b = Wire::bus::<16>("b");
+            let input = Wire::input("in");
+            let sel = Wire::bus::<3>("sel");
+            (add_16(a, b), dmux_8_way(input, sel))
+        });
+
+        // Lane `i` adds `i * 1000` and `i * 7 + 3`.
+        let mut inputs = vec![0u64; 36];
+        for lane in 0..64u16 {
+            for (idx, bit) in to_bits::<16>(lane * 1000).into_iter().enumerate() {
+                inputs[idx] |= (bit as u64) << lane;
+            }
+            for (idx, bit) in to_bits::<16>(lane * 7 + 3).into_iter().enumerate() {
+                inputs[16 + idx] |= (bit as u64) << lane;
+            }
+        }
+        let values = circuit.evaluate(&inputs, &sum);
+        for lane in 0..64u16 {
+            let word = values.iter().enumerate().fold(0, |acc, (idx, bits)| {
+                acc | (((bits >> lane) & 1) as u16) << idx
+            });
+            assert_eq!(word, lane * 1000 + lane * 7 + 3);
+        }
+
+        // Lanes 0..16 cover every `in` and `sel` combination of dmux_8_way.
+        let mut inputs = vec![0u64; 36];
+        inputs[32] = 0xFF00;
+        inputs[33] = 0b1010101010101010;
+        inputs[34] = 0b1100110011001100;
+        inputs[35] = 0b1111000011110000;
+        let values = circuit.evaluate(&inputs, &outputs);
+        for lane in 0..16 {
+            let selected = if lane >= 8 { Some(lane % 8) } else { None };
+            for (output, bits) in values.iter().enumerate() {
+                assert_eq!((bits >> lane) & 1 == 1, selected == Some(output));
+            }
+        }
+    }
+
+    #[test]
+    fn test_instances() {
+        let (circuit, out) = Circuit::record(|| xor(Wire::input("a"), Wire::input("b")));
+
+        let chips: Vec<_> = circuit
+            .instances()
+            .iter()
+            .map(|instance| (instance.chip, instance.parent))
+            .collect();
+        assert_eq!(
+            chips,
+            [
+                ("Xor", None),
+                ("Not", Some(0)),
+                ("And", Some(0)),
+                ("Not", Some(2)),
+                ("Not", Some(0)),
+                ("And", Some(0)),
+                ("Not", Some(5)),
+                ("Or", Some(0)),
+            ]
+        );
+
+        let xor = &circuit.instances()[0];
+        assert_eq!(xor.inputs, [("a", vec![Wire(0)]), ("b", vec![Wire(1)])]);
+        assert_eq!(xor.outputs, [("out", vec![out])]);
+        assert_eq!(xor.nodes, 2..circuit.nodes().len());
+    }
+
+    #[test]
+    #[should_panic(expected = "Circuit::record")]
+    fn test_wire_outside_recording() {
+        Wire::input("a");
+    }
+}
@@ -1,6 +1,6 @@
 use super::{
-    and, and_16, dmux, dmux_4_way, dmux_8_way, mux, mux_16, mux_4_way_16, mux_8_way_16, not,
-    not_16, or, or_16, or_8_way, xor,
+    and, and_16, dmux, dmux_4_way, dmux_8_way, from_bits, mux, mux_16, mux_4_way_16, mux_8_way_16,
+    not, not_16, or, or_16, or_8_way, to_bits, xor,
 };
 
 use super::nand;
@@ -1089,3 +1089,34 @@ fn test_dmux_8_way() {
         assert_eq!(expected, dmux_8_way(input, sel))
     }
 }
+
+#[test]
+fn test_bits() {
+    assert_eq!(to_bits::<3>(0b110), [false, true, true]);
+    assert_eq!(from_bits([true, false, true]), 0b101);
+
+    for value in [0, 1, 0x00FF, 0x8000, 0xBEEF, 0xFFFF] {
+        assert_eq!(from_bits(to_bits::<16>(value)), value);
+    }
+}
+
+#[test]
+fn test_u64_lanes() {
+    // Lane `i` holds a = bit 0, b = bit 1, sel = bit 2 of `i`.
+    let a: u64 = 0b10101010;
+    let b: u64 = 0b11001100;
+    let sel: u64 = 0b11110000;
+
+    let lanes = [
+        xor(a, b),
+        mux(a, b, sel),
+        or_8_way([a, b, sel, 0, 0, 0, 0, 0]),
+    ];
+    for lane in 0..8 {
+        let [a, b, sel] = to_bits::<3>(lane);
+        let bit = |value: u64| (value >> lane) & 1 == 1;
+        assert_eq!(bit(lanes[0]), xor(a, b));
+        assert_eq!(bit(lanes[1]), mux(a, b, sel));
+        assert_eq!(bit(lanes[2]), a | b | sel);
+    }
+}
@@ -0,0 +1,523 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::screen::{crc32, png, PNG_SIGNATURE, ROW_WORDS};
+use super::Screen;
+
+/// The smallest rectangle holding every differing pixel, edges inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub left: usize,
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+}
+
+/// How a screen differs from a reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenDiff {
+    pub count: usize,
+    pub bounds: Option<Bounds>,
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// A reference that is not a 512x256 PBM or a PNG as `Screen::to_png`
+    /// writes it. PNGs from other tools are usually compressed, which is
+    /// not supported.
+    InvalidImage {
+        path: PathBuf,
+        reason: String,
+    },
+    Mismatch {
+        reference: PathBuf,
+        diff: PathBuf,
+        count: usize,
+        bounds: Bounds,
+    },
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoldenError::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            GoldenError::InvalidImage { path, reason } => write!(f, "{}: {reason}", path.display()),
+            GoldenError::Mismatch {
+                reference,
+                diff,
+                count,
+                bounds,
+            } => write!(
+                f,
+                "screen differs from {} in {count} pixels within ({}, {})-({}, {}), see {}",
+                reference.display(),
+                bounds.left,
+                bounds.top,
+                bounds.right,
+                bounds.bottom,
+                diff.display()
+            ),
+        }
+    }
+}
+
+impl Screen<'_> {
+    pub fn compare(&self, reference: &Screen) -> ScreenDiff {
+        let mut diff = ScreenDiff {
+            count: 0,
+            bounds: None,
+        };
+        for (y, (row, expected)) in self.rows().zip(reference.rows()).enumerate() {
+            for (idx, (word, expected)) in row.iter().zip(expected).enumerate() {
+                let changed = word ^ expected;
+                if changed == 0 {
+                    continue;
+                }
+                diff.count += changed.count_ones() as usize;
+                let left = 16 * idx + changed.trailing_zeros() as usize;
+                let right = 16 * idx + 15 - changed.leading_zeros() as usize;
+                let bounds = diff.bounds.get_or_insert(Bounds {
+                    left,
+                    top: y,
+                    right,
+                    bottom: y,
+                });
+                bounds.left = bounds.left.min(left);
+                bounds.right = bounds.right.max(right);
+                bounds.bottom = y;
+            }
+        }
+        diff
+    }
+
+    /// An RGB PNG of the two screens: pixels that agree are white or light
+    /// gray, pixels only on the reference red and pixels only on `self` blue.
+    pub fn diff_png(&self, reference: &Screen) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(Screen::HEIGHT * (1 + 3 * Screen::WIDTH));
+        for y in 0..Screen::HEIGHT {
+            raw.push(0);
+            for x in 0..Screen::WIDTH {
+                raw.extend_from_slice(match (self.pixel(x, y), reference.pixel(x, y)) {
+                    (false, false) => &[0xFF, 0xFF, 0xFF],
+                    (true, true) => &[0xC0, 0xC0, 0xC0],
+                    (false, true) => &[0xFF, 0x00, 0x00],
+                    (true, false) => &[0x00, 0x00, 0xFF],
+                });
+            }
+        }
+        // Bit depth 8, RGB.
+        png(Screen::WIDTH, Screen::HEIGHT, 8, 2, &raw)
+    }
+
+    /// Compares the screen with the reference image at `path`, read by
+    /// `read_screen_image`. On a mismatch writes `Name.diff.png` beside the
+    /// reference, see `diff_png`.
+    pub fn check_golden(&self, path: impl AsRef<Path>) -> Result<(), GoldenError> {
+        let path = path.as_ref();
+        let words = read_screen_image(path)?;
+        let reference = Screen::new(&words);
+
+        let ScreenDiff {
+            count,
+            bounds: Some(bounds),
+        } = self.compare(&reference)
+        else {
+            return Ok(());
+        };
+
+        let diff = path.with_extension("diff.png");
+        fs::write(&diff, self.diff_png(&reference)).map_err(|error| GoldenError::Io {
+            path: diff.clone(),
+            error,
+        })?;
+        Err(GoldenError::Mismatch {
+            reference: path.to_path_buf(),
+            diff,
+            count,
+            bounds,
+        })
+    }
+}
+
+/// Reads a screen image: a PBM, plain (`P1`) or binary (`P4`), or a PNG as
+/// `Screen::to_png` writes it, 1-bit grayscale with uncompressed data.
+///
+/// There is no inflate, so compressed PNGs, which is what image editors
+/// and most other tools write, are rejected. Keep hand-made references as
+/// PBM, or produce them with `Screen::save_png`.
+pub fn read_screen_image(path: impl AsRef<Path>) -> Result<Box<[u16; 8192]>, GoldenError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|error| GoldenError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let image = match bytes.get(..2) {
+        Some(b"P1" | b"P4") => read_pbm(&bytes),
+        _ if bytes.starts_with(PNG_SIGNATURE) => read_png(&bytes),
+        _ => Err("not a PBM or PNG image".to_string()),
+    };
+    image.map_err(|reason| GoldenError::InvalidImage {
+        path: path.to_path_buf(),
+        reason,
+    })
+}
+
+fn set_pixel(words: &mut [u16; 8192], x: usize, y: usize) {
+    words[y * ROW_WORDS + x / 16] |= 1 << (x % 16);
+}
+
+fn check_size(width: usize, height: usize) -> Result<(), String> {
+    match (width, height) {
+        (Screen::WIDTH, Screen::HEIGHT) => Ok(()),
+        _ => Err(format!(
+            "image is {width}x{height}, expected {}x{}",
+            Screen::WIDTH,
+            Screen::HEIGHT
+        )),
+    }
+}
+
+fn read_pbm(bytes: &[u8]) -> Result<Box<[u16; 8192]>, String> {
+    // The magic number, width and height, separated by whitespace and
+    // `#` comments.
+    let mut pos = 0;
+    let mut fields = Vec::new();
+    while fields.len() < 3 {
+        match bytes.get(pos) {
+            Some(b'#') => {
+                while bytes.get(pos).is_some_and(|byte| *byte != b'\n') {
+                    pos += 1;
+                }
+            }
+            Some(byte) if byte.is_ascii_whitespace() => pos += 1,
+            Some(_) => {
+                let start = pos;
+                while bytes
+                    .get(pos)
+                    .is_some_and(|byte| !byte.is_ascii_whitespace())
+                {
+                    pos += 1;
+                }
+                fields.push(String::from_utf8_lossy(&bytes[start..pos]).to_string());
+            }
+            None => return Err("truncated PBM header".to_string()),
+        }
+    }
+    let size = |field: &str| {
+        field
+            .parse::<usize>()
+            .map_err(|_| format!("invalid PBM size {field}"))
+    };
+    check_size(size(&fields[1])?, size(&fields[2])?)?;
+
+    let mut words = Box::new([0; 8192]);
+    if fields[0] == "P4" {
+        let raster = bytes.get(pos + 1..).unwrap_or_default();
+        if raster.len() < Screen::WIDTH * Screen::HEIGHT / 8 {
+            return Err("truncated PBM data".to_string());
+        }
+        for (idx, byte) in raster
+            .iter()
+            .take(Screen::WIDTH * Screen::HEIGHT / 8)
+            .enumerate()
+        {
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    let pixel = 8 * idx + bit;
+                    set_pixel(&mut words, pixel % Screen::WIDTH, pixel / Screen::WIDTH);
+                }
+            }
+        }
+    } else {
+        let mut pixels = bytes[pos..]
+            .iter()
+            .filter(|byte| !byte.is_ascii_whitespace());
+        for pixel in 0..Screen::WIDTH * Screen::HEIGHT {
+            match pixels.next() {
+                Some(b'1') => set_pixel(&mut words, pixel % Screen::WIDTH, pixel / Screen::WIDTH),
+                Some(b'0') => {}
+                Some(byte) => return Err(format!("invalid PBM pixel {}", *byte as char)),
+                None => return Err("truncated PBM data".to_string()),
+            }
+        }
+    }
+    Ok(words)
+}
+
+/// The `IHDR` fields and the inflated image data of a PNG whose data is
+/// stored uncompressed, as `png` writes it.
+fn png_data(bytes: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let mut header = None;
+    let mut zlib = Vec::new();
+    let mut rest = &bytes[PNG_SIGNATURE.len()..];
+    while rest.len() >= 12 {
+        let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let (body, tail) = rest[4..]
+            .split_at_checked(4 + len)
+            .ok_or("truncated PNG chunk")?;
+        let crc = tail.get(..4).ok_or("truncated PNG chunk")?;
+        if crc32(body) != u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+            return Err("PNG chunk CRC mismatch".to_string());
+        }
+        match &body[..4] {
+            b"IHDR" => header = Some(body[4..].to_vec()),
+            b"IDAT" => zlib.extend_from_slice(&body[4..]),
+            _ => {}
+        }
+        rest = &tail[4..];
+    }
+
+    let header = header
+        .filter(|header| header.len() == 13)
+        .ok_or("missing PNG header")?;
+
+    // Stored deflate blocks each start on a byte boundary: the block
+    // header, then the length and its complement.
+    let mut raw = Vec::new();
+    let mut pos = 2;
+    loop {
+        let block = zlib.get(pos..pos + 5).ok_or("truncated PNG data")?;
+        if block[0] & 0b110 != 0 {
+            return Err("compressed PNG data is not supported, save references as PBM".to_string());
+        }
+        let len = u16::from_le_bytes([block[1], block[2]]) as usize;
+        let data = zlib
+            .get(pos + 5..pos + 5 + len)
+            .ok_or("truncated PNG data")?;
+        raw.extend_from_slice(data);
+        pos += 5 + len;
+        if block[0] & 1 == 1 {
+            break;
+        }
+    }
+    Ok((header, raw))
+}
+
+fn read_png(bytes: &[u8]) -> Result<Box<[u16; 8192]>, String> {
+    let (header, raw) = png_data(bytes)?;
+    let dimension = |at: usize| {
+        u32::from_be_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]]) as usize
+    };
+    check_size(dimension(0), dimension(4))?;
+    if header[8..] != [1, 0, 0, 0, 0] {
+        return Err(
+            "only 1-bit grayscale PNGs without interlacing are supported, save references as PBM"
+                .to_string(),
+        );
+    }
+
+    let mut words = Box::new([0; 8192]);
+    let stride = 1 + Screen::WIDTH / 8;
+    if raw.len() < stride * Screen::HEIGHT {
+        return Err("truncated PNG data".to_string());
+    }
+    for (y, row) in raw.chunks(stride).take(Screen::HEIGHT).enumerate() {
+        if row[0] != 0 {
+            return Err("filtered PNG rows are not supported".to_string());
+        }
+        for (idx, byte) in row[1..].iter().enumerate() {
+            for bit in 0..8 {
+                // Grayscale 0 is black.
+                if byte & (0x80 >> bit) == 0 {
+                    set_pixel(&mut words, 8 * idx + bit, y);
+                }
+            }
+        }
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler;
+    use crate::computer::Computer;
+    use crate::test_util::TempDir;
+
+    const RECT: &str = "
+@0
+D=M
+@INFINITE_LOOP
+D;JLE
+@counter
+M=D
+@SCREEN
+D=A
+@address
+M=D
+(LOOP)
+@address
+A=M
+M=-1
+@address
+D=M
+@32
+D=D+A
+@address
+M=D
+@counter
+MD=M-1
+@LOOP
+D;JGT
+(INFINITE_LOOP)
+@INFINITE_LOOP
+0;JMP
+";
+
+    /// A 16-pixel-wide rectangle of `rows` rows in the top left corner.
+    fn rectangle(rows: usize) -> Box<[u16; 8192]> {
+        let mut words = Box::new([0; 8192]);
+        for row in 0..rows {
+            words[row * ROW_WORDS] = 0xFFFF;
+        }
+        words
+    }
+
+    #[test]
+    fn test_golden() {
+        let dir = TempDir::new("golden");
+
+        let mut computer = Computer::new();
+        computer
+            .load_program(assembler::assemble(RECT).unwrap())
+            .unwrap();
+        computer.ram_mut().load(4, 0).unwrap();
+        for _ in 0..100 {
+            computer.execute().unwrap();
+        }
+        let screen = computer.ram().screen();
+
+        let expected = rectangle(4);
+        Screen::new(&expected)
+            .save_pbm(dir.join("Rect.pbm"))
+            .unwrap();
+        Screen::new(&expected)
+            .save_png(dir.join("Rect.png"))
+            .unwrap();
+        screen.check_golden(dir.join("Rect.pbm")).unwrap();
+        screen.check_golden(dir.join("Rect.png")).unwrap();
+        assert!(!dir.join("Rect.diff.png").exists());
+
+        // The reference lacks the first row, and has a fifth row and a stray
+        // pixel at (40, 100).
+        let mut expected = rectangle(5);
+        expected[0] = 0;
+        set_pixel(&mut expected, 40, 100);
+        Screen::new(&expected)
+            .save_pbm(dir.join("Rect.pbm"))
+            .unwrap();
+        let error = screen.check_golden(dir.join("Rect.pbm")).unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                GoldenError::Mismatch {
+                    count: 33,
+                    bounds: Bounds {
+                        left: 0,
+                        top: 0,
+                        right: 40,
+                        bottom: 100
+                    },
+                    ..
+                }
+            ),
+            "{error}"
+        );
+        let (header, raw) = png_data(&fs::read(dir.join("Rect.diff.png")).unwrap()).unwrap();
+        assert_eq!(header, [0, 0, 2, 0, 0, 0, 1, 0, 8, 2, 0, 0, 0]);
+        let rgb = |x: usize, y: usize| {
+            let at = y * (1 + 3 * Screen::WIDTH) + 1 + 3 * x;
+            [raw[at], raw[at + 1], raw[at + 2]]
+        };
+        // Only on the screen: blue. Only on the reference: red.
+        assert_eq!(rgb(3, 0), [0x00, 0x00, 0xFF]);
+        assert_eq!(rgb(3, 4), [0xFF, 0x00, 0x00]);
+        assert_eq!(rgb(40, 100), [0xFF, 0x00, 0x00]);
+        assert_eq!(rgb(3, 2), [0xC0, 0xC0, 0xC0]);
+        assert_eq!(rgb(16, 0), [0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_compare() {
+        let (left, right) = (rectangle(3), rectangle(0));
+        let diff = Screen::new(&left).compare(&Screen::new(&right));
+        assert_eq!(
+            diff,
+            ScreenDiff {
+                count: 48,
+                bounds: Some(Bounds {
+                    left: 0,
+                    top: 0,
+                    right: 15,
+                    bottom: 2
+                })
+            }
+        );
+        assert_eq!(
+            Screen::new(&left).compare(&Screen::new(&left)),
+            ScreenDiff {
+                count: 0,
+                bounds: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_image() {
+        struct Test {
+            name: &'static str,
+            image: Vec<u8>,
+            expected: Result<Box<[u16; 8192]>, &'static str>,
+        }
+        let mut plain = b"P1\n# a comment\n512 256\n".to_vec();
+        for pixel in 0..Screen::WIDTH * Screen::HEIGHT {
+            plain.extend_from_slice(if pixel < 16 { b"1 " } else { b"0\n" });
+        }
+        let tests = vec![
+            Test {
+                name: "plain.pbm",
+                image: plain,
+                expected: Ok(rectangle(1)),
+            },
+            Test {
+                name: "binary.pbm",
+                image: Screen::new(&rectangle(7)).to_pbm(),
+                expected: Ok(rectangle(7)),
+            },
+            Test {
+                name: "small.pbm",
+                image: b"P4 16 16\n".to_vec(),
+                expected: Err("image is 16x16, expected 512x256"),
+            },
+            Test {
+                name: "short.pbm",
+                image: b"P4 512 256\n\x00".to_vec(),
+                expected: Err("truncated PBM data"),
+            },
+            Test {
+                name: "text.txt",
+                image: b"hello".to_vec(),
+                expected: Err("not a PBM or PNG image"),
+            },
+        ];
+
+        let dir = TempDir::new("images");
+        for test in tests {
+            fs::write(dir.join(test.name), &test.image).unwrap();
+            let result = read_screen_image(dir.join(test.name));
+            match (result, test.expected) {
+                (Ok(words), Ok(expected)) => assert!(words == expected, "{}", test.name),
+                (Err(GoldenError::InvalidImage { reason, .. }), Err(expected)) => {
+                    assert_eq!(reason, expected, "{}", test.name)
+                }
+                (result, _) => panic!("{}: {:?}", test.name, result.err()),
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::VecDeque;
+
+use super::chip::clock::Clock;
+use super::chip::cpu::CPUResponse;
+use super::{Computer, Error, MemoryWrite};
+
+/// The state one cycle overwrote, enough to put the machine back exactly as
+/// it was before that cycle.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Undo {
+    pub a_register: u16,
+    pub d_register: u16,
+    pub response: CPUResponse,
+    pub write: Option<MemoryWrite>,
+}
+
+/// A bounded journal of the most recent cycles, oldest first.
+pub(super) struct History {
+    entries: VecDeque<Undo>,
+    limit: usize,
+}
+
+impl History {
+    fn new(limit: usize) -> Self {
+        History {
+            entries: VecDeque::new(),
+            limit,
+        }
+    }
+
+    pub fn push(&mut self, undo: Undo) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.entries.len() == self.limit {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(undo);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Computer {
+    /// Starts journaling the last `limit` cycles so they can be undone.
+    /// RAM changed from outside the CPU, such as keyboard input, is not
+    /// journaled.
+    pub fn enable_history(&mut self, limit: usize) {
+        self.history = Some(History::new(limit));
+    }
+
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// How many cycles can currently be undone.
+    pub fn history_len(&self) -> usize {
+        self.history
+            .as_ref()
+            .map_or(0, |history| history.entries.len())
+    }
+
+    /// Undoes the last executed cycle. Returns false when there is nothing
+    /// left in the journal.
+    pub fn step_back(&mut self) -> Result<bool, Error> {
+        self.finish_cycle()?;
+        let Some(undo) = self
+            .history
+            .as_mut()
+            .and_then(|history| history.entries.pop_back())
+        else {
+            return Ok(false);
+        };
+
+        if let Some(write) = undo.write {
+            self.ram.load(write.old, write.address)?;
+        }
+        self.cpu.a_register = undo.a_register;
+        self.cpu.d_register = undo.d_register;
+        self.cpu.pc = undo.response.pc;
+        self.prev_cpu_response = undo.response;
+        self.clock = Clock::at(self.clock.cycle() - 1);
+
+        Ok(true)
+    }
+
+    /// Rewinds to just before the most recent cycle that wrote `address`, so
+    /// the next `execute` performs that write again. Returns the write, or
+    /// `None` after rewinding the whole journal without finding one.
+    pub fn run_back_to_write(&mut self, address: u16) -> Result<Option<MemoryWrite>, Error> {
+        loop {
+            let last = self
+                .history
+                .as_ref()
+                .and_then(|history| history.entries.back())
+                .map(|undo| undo.write);
+
+            match last {
+                None => return Ok(None),
+                Some(write) => {
+                    self.step_back()?;
+                    if let Some(write) = write.filter(|write| write.address == address) {
+                        return Ok(Some(write));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves to the state right after `cycle` instructions, rewinding through
+    /// the journal for earlier cycles and executing forward for later ones.
+    pub fn goto_cycle(&mut self, cycle: u64) -> Result<(), Error> {
+        self.finish_cycle()?;
+        let oldest = self.cycles() - self.history_len() as u64;
+        if cycle < oldest {
+            return Err(Error::HistoryUnavailable { cycle, oldest });
+        }
+
+        while self.cycles() > cycle {
+            self.step_back()?;
+        }
+        while self.cycles() < cycle {
+            self.execute()?;
+        }
+        Ok(())
+    }
+
+    /// Completes a cycle left between `tick` and `tock` so the journal
+    /// covers it.
+    fn finish_cycle(&mut self) -> Result<(), Error> {
+        if self.clock.is_high() {
+            self.tock()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    const SUM: &str = "
+        @i
+        M=1
+        @sum
+        M=0
+    (LOOP)
+        @i
+        D=M
+        @5
+        D=D-A
+        @END
+        D;JGT
+        @i
+        D=M
+        @sum
+        M=D+M
+        @i
+        M=M+1
+        @LOOP
+        0;JMP
+    (END)
+        @END
+        0;JMP
+    ";
+
+    fn computer() -> Computer {
+        let mut computer = Computer::new();
+        computer.load_program(assemble(SUM).unwrap()).unwrap();
+        computer.enable_history(1000);
+        computer
+    }
+
+    #[test]
+    fn test_step_back() -> Result<(), Error> {
+        let mut computer = computer();
+        computer.run(1000);
+        assert_eq!(computer.ram.read(17)?, 15);
+
+        let (a, d, pc, cycles) = (
+            computer.cpu.a_register,
+            computer.cpu.d_register,
+            computer.pc(),
+            computer.cycles(),
+        );
+        computer.execute()?;
+        assert!(computer.step_back()?);
+        assert_eq!(computer.cpu.a_register, a);
+        assert_eq!(computer.cpu.d_register, d);
+        assert_eq!(computer.pc(), pc);
+        assert_eq!(computer.cycles(), cycles);
+
+        while computer.step_back()? {}
+        assert_eq!(computer.cycles(), 0);
+        assert_eq!(computer.pc(), 0);
+        assert_eq!(computer.ram.read(16)?, 0);
+        assert_eq!(computer.ram.read(17)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_back_to_write() -> Result<(), Error> {
+        let mut computer = computer();
+        computer.run(1000);
+
+        // The last write to `sum` is `M=D+M` adding 5 to 10.
+        let write = computer.run_back_to_write(17)?;
+        assert_eq!(
+            write,
+            Some(MemoryWrite {
+                address: 17,
+                old: 10,
+                new: 15
+            })
+        );
+        assert_eq!(computer.pc(), 13);
+        assert_eq!(computer.ram.read(17)?, 10);
+
+        computer.execute()?;
+        assert_eq!(computer.ram.read(17)?, 15);
+
+        assert_eq!(computer.run_back_to_write(100)?, None);
+        assert_eq!(computer.cycles(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_goto_cycle() -> Result<(), Error> {
+        let mut computer = computer();
+        computer.run(1000);
+        let end = computer.cycles();
+
+        computer.goto_cycle(4)?;
+        assert_eq!(computer.pc(), 4);
+        assert_eq!(computer.ram.read(16)?, 1);
+
+        computer.goto_cycle(end)?;
+        assert_eq!(computer.ram.read(17)?, 15);
+        Ok(())
+    }
+
+    #[test]
+    fn test_history_limit() -> Result<(), Error> {
+        let mut computer = computer();
+        computer.enable_history(3);
+        computer.run(10);
+
+        assert_eq!(computer.history_len(), 3);
+        assert!(matches!(
+            computer.goto_cycle(6),
+            Err(Error::HistoryUnavailable {
+                cycle: 6,
+                oldest: 7
+            })
+        ));
+        computer.goto_cycle(7)?;
+        assert!(!computer.step_back()?);
+        Ok(())
+    }
+}
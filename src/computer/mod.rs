@@ -1,20 +1,115 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::Path;
+
+use self::chip::clock::{Clock, ClockError};
 use self::chip::cpu::instructions::CpuInstructions;
 use self::chip::cpu::{CPUResponse, Cpu};
 use self::chip::memory::{MemoryError, Ram, Rom};
+use self::history::{History, Undo};
+use crate::program::{self, Endian, ProgramError};
+
+pub use self::debug::{WatchHit, WatchKind, Watchpoint};
+pub use self::differential::{CpuState, DifferentialRunner, Divergence};
+pub use self::golden::{read_screen_image, Bounds, GoldenError, ScreenDiff};
+pub use self::run::StopReason;
+pub use self::screen::Screen;
+pub use self::snapshot::{Difference, Snapshot, SnapshotError};
+pub use self::terminal::{Glyphs, TerminalView};
+pub use self::trace::{read_binary_trace, TraceError, TraceFormat, TraceRecord, Tracer};
 
-mod chip;
-mod gate;
+pub mod chip;
+mod debug;
+mod differential;
+pub mod gate;
+mod golden;
+mod history;
+mod run;
+mod screen;
+mod snapshot;
+mod terminal;
+mod trace;
 
-struct Computer {
+pub struct Computer {
     rom: Rom,
     ram: Ram,
     cpu: Cpu,
     prev_cpu_response: CPUResponse,
+    decoding: Decoding,
+    clock: Clock,
+    pending: Option<Pending>,
+    breakpoints: BTreeSet<u16>,
+    watchpoints: Vec<Watchpoint>,
+    tracer: Option<Tracer>,
+    history: Option<History>,
+}
+
+/// What a single executed instruction did.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cycle {
+    pub pc: u16,
+    pub instruction: CpuInstructions,
+    pub response: CPUResponse,
+    pub read: Option<MemoryRead>,
+    pub write: Option<MemoryWrite>,
+}
+
+/// A cycle between its `tick` and `tock`.
+struct Pending {
+    cycle: Cycle,
+    undo: Undo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryRead {
+    pub address: u16,
+    pub value: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryWrite {
+    pub address: u16,
+    pub old: u16,
+    pub new: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decoding {
+    /// Stop with `Error::IllegalInstruction` on comp bits outside the Hack
+    /// specification.
+    Strict,
+    /// Execute every comp pattern the way the ALU hardware would.
+    Permissive,
 }
 
 #[derive(Debug)]
 pub enum Error {
     Memory(MemoryError),
+    Program(ProgramError),
+    IllegalInstruction { pc: u16, word: u16 },
+    Trace(TraceError),
+    HistoryUnavailable { cycle: u64, oldest: u64 },
+    Snapshot(SnapshotError),
+    Clock(ClockError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Memory(error) => write!(f, "{error}"),
+            Error::Program(error) => write!(f, "{error}"),
+            Error::IllegalInstruction { pc, word } => {
+                write!(f, "illegal instruction {word:#018b} at {pc}")
+            }
+            Error::Trace(error) => write!(f, "trace: {error}"),
+            Error::HistoryUnavailable { cycle, oldest } => write!(
+                f,
+                "cycle {cycle} is no longer in the history, the oldest is {oldest}"
+            ),
+            Error::Snapshot(error) => write!(f, "snapshot: {error}"),
+            Error::Clock(error) => write!(f, "{error}"),
+        }
+    }
 }
 
 impl From<MemoryError> for Error {
@@ -23,6 +118,30 @@ impl From<MemoryError> for Error {
     }
 }
 
+impl From<ProgramError> for Error {
+    fn from(value: ProgramError) -> Self {
+        Error::Program(value)
+    }
+}
+
+impl From<TraceError> for Error {
+    fn from(value: TraceError) -> Self {
+        Error::Trace(value)
+    }
+}
+
+impl From<SnapshotError> for Error {
+    fn from(value: SnapshotError) -> Self {
+        Error::Snapshot(value)
+    }
+}
+
+impl From<ClockError> for Error {
+    fn from(value: ClockError) -> Self {
+        Error::Clock(value)
+    }
+}
+
 impl Computer {
     pub fn new() -> Self {
         Computer {
@@ -35,9 +154,20 @@ impl Computer {
             ram: Ram::new(),
             rom: Rom::new(),
             cpu: Cpu::new(),
+            decoding: Decoding::Strict,
+            clock: Clock::new(),
+            pending: None,
+            breakpoints: BTreeSet::new(),
+            watchpoints: Vec::new(),
+            tracer: None,
+            history: None,
         }
     }
 
+    pub fn set_decoding(&mut self, decoding: Decoding) {
+        self.decoding = decoding;
+    }
+
     pub fn reset(&mut self) {
         self.prev_cpu_response = CPUResponse {
             out_m: 0,
@@ -47,24 +177,173 @@ impl Computer {
         };
         self.ram.reset();
         self.cpu.reset();
+        self.clock = Clock::new();
+        self.pending = None;
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.prev_cpu_response.pc
+    }
+
+    /// Jumps to `pc` before the next instruction.
+    pub fn set_pc(&mut self, pc: u16) {
+        self.prev_cpu_response.pc = pc;
+        self.cpu.pc = pc;
+    }
+
+    /// Number of instructions executed since the last reset.
+    pub fn cycles(&self) -> u64 {
+        self.clock.cycle()
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut Cpu {
+        &mut self.cpu
+    }
+
+    pub fn ram(&self) -> &Ram {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut Ram {
+        &mut self.ram
+    }
+
+    pub fn rom(&self) -> &Rom {
+        &self.rom
     }
 
     pub fn execute(&mut self) -> Result<(), Error> {
-        let instruction = self.rom.read(self.prev_cpu_response.pc)?;
+        self.step().map(|_| ())
+    }
+
+    /// Executes one instruction and reports the memory it touched: a `tick`
+    /// followed by a `tock`.
+    pub fn step(&mut self) -> Result<Cycle, Error> {
+        self.tick()?;
+        self.tock()
+    }
+
+    /// The rising edge of the clock: fetches and decodes the instruction at
+    /// the program counter and computes the CPU outputs. Registers, RAM and
+    /// `pc()` keep their old values until `tock`.
+    pub fn tick(&mut self) -> Result<CPUResponse, Error> {
+        if self.clock.is_high() {
+            return Err(ClockError::AlreadyHigh.into());
+        }
 
-        let input_m = self.ram.read(self.prev_cpu_response.address_m)?;
+        let pc = self.prev_cpu_response.pc;
+        let word = self.rom.read(pc)?;
+        let instruction = match self.decoding {
+            Decoding::Strict => CpuInstructions::try_from(word)
+                .map_err(|_| Error::IllegalInstruction { pc, word })?,
+            Decoding::Permissive => CpuInstructions::from_bits(word),
+        };
+
+        // Like the hardware, M is RAM[A] for the A register as it stands now,
+        // and only instructions whose `a` bit selects M touch memory, so A
+        // may hold any address while M goes unused.
+        let read = match &instruction {
+            CpuInstructions::CInstruction { comp, .. } if comp.reads_m() => Some(MemoryRead {
+                address: self.cpu.a_register,
+                value: self.ram.read(self.cpu.a_register)?,
+            }),
+            _ => None,
+        };
 
-        let cpu_response = self
-            .cpu
-            .execute(CpuInstructions::from(instruction), input_m);
+        let mut cpu = self.cpu.clone();
+        let cpu_response = cpu.tick(instruction, read.map_or(0, |read| read.value));
 
-        if cpu_response.write_m {
-            self.ram.load(cpu_response.out_m, cpu_response.address_m)?;
+        let write = if cpu_response.write_m {
+            Some(MemoryWrite {
+                address: cpu_response.address_m,
+                old: self.ram.read(cpu_response.address_m)?,
+                new: cpu_response.out_m,
+            })
+        } else {
+            None
+        };
+
+        self.clock.tick()?;
+        self.ram.tick(
+            cpu_response.out_m,
+            cpu_response.write_m,
+            cpu_response.address_m,
+        )?;
+        self.pending = Some(Pending {
+            cycle: Cycle {
+                pc,
+                instruction,
+                response: cpu_response,
+                read,
+                write,
+            },
+            undo: Undo {
+                a_register: self.cpu.a_register,
+                d_register: self.cpu.d_register,
+                response: self.prev_cpu_response,
+                write,
+            },
+        });
+        self.cpu = cpu;
+
+        Ok(cpu_response)
+    }
+
+    /// The falling edge of the clock: the registers, the program counter and
+    /// the written RAM word take the values computed by `tick`.
+    ///
+    /// The cycle is traced before it is committed, so if the tracer fails
+    /// the error is returned with the computer left as `tick` left it: the
+    /// clock stays high and `tock` may be called again. The tracer writes
+    /// each record whole, so the retried cycle is traced exactly once.
+    pub fn tock(&mut self) -> Result<Cycle, Error> {
+        let mut clock = self.clock;
+        clock.tock()?;
+        let cycle = self
+            .pending
+            .as_ref()
+            .expect("tick stores the pending cycle")
+            .cycle;
+
+        let mut cpu = self.cpu.clone();
+        cpu.tock();
+
+        if let Some(tracer) = &mut self.tracer {
+            tracer.record(&TraceRecord {
+                cycle: clock.cycle(),
+                pc: cycle.pc,
+                instruction: cycle.instruction,
+                a_register: cpu.a_register,
+                d_register: cpu.d_register,
+                response: cycle.response,
+                write: cycle.write,
+            })?;
         }
 
-        self.prev_cpu_response = cpu_response;
+        let Pending { undo, .. } = self.pending.take().expect("tick stores the pending cycle");
+        self.clock = clock;
+        self.cpu = cpu;
+        // `tick` already checked the address, so the write cannot fail here.
+        self.ram.tock()?;
 
-        Ok(())
+        if let Some(history) = &mut self.history {
+            history.push(undo);
+        }
+
+        self.prev_cpu_response = cycle.response;
+
+        Ok(cycle)
     }
 
     pub fn load_program(&mut self, program: Vec<u16>) -> Result<(), Error> {
@@ -75,273 +354,253 @@ impl Computer {
 
         Ok(())
     }
+
+    /// Replaces the ROM contents with the program stored in a `.hack` file.
+    pub fn load_hack(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.rom.load_program(&program::read_hack(path)?)?;
+        Ok(())
+    }
+
+    /// Replaces the ROM contents with a raw binary image of 16-bit words.
+    pub fn load_binary(&mut self, path: impl AsRef<Path>, endian: Endian) -> Result<(), Error> {
+        self.rom
+            .load_program(&program::read_binary(path, endian)?)?;
+        Ok(())
+    }
+
+    pub fn save_hack(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        program::write_hack(path, self.rom.program())?;
+        Ok(())
+    }
+
+    pub fn save_binary(&self, path: impl AsRef<Path>, endian: Endian) -> Result<(), Error> {
+        program::write_binary(path, self.rom.program(), endian)?;
+        Ok(())
+    }
+}
+
+impl Default for Computer {
+    fn default() -> Self {
+        Computer::new()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Computer, Error};
+    use super::{ClockError, Computer, Decoding, Error, MemoryRead};
+    use crate::script::{Script, ScriptError};
+    use crate::test_util::TempDir;
+
+    /// Runs `Name.tst` from the checked in fixtures.
+    fn run_fixture(name: &str) -> Result<(), ScriptError> {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        Script::run_file(dir.join(format!("{name}.tst"))).map(|_| ())
+    }
 
     #[test]
-    fn test_add_program() -> Result<(), Error> {
-        #[derive(Debug, Clone)]
-        struct State {
-            a_register: u16,
-            d_register: u16,
-            pc: u16,
-            ram_0: u16,
-        }
-        let add_program: Vec<u16> = vec![
+    fn test_add_program() -> Result<(), ScriptError> {
+        run_fixture("Add")
+    }
+
+    #[test]
+    fn test_add_program_after_reset() -> Result<(), Error> {
+        let mut computer = Computer::new();
+        computer.load_program(vec![
             0b0000000000000010,
             0b1110110000010000,
             0b0000000000000011,
             0b1110000010010000,
             0b0000000000000000,
             0b1110001100001000,
-        ];
+        ])?;
+        for _ in 0..6 {
+            computer.execute()?;
+        }
+        assert_eq!(computer.ram.read(0)?, 5);
 
-        let expected_state = vec![
-            State {
-                a_register: 2,
-                d_register: 0,
-                pc: 1,
-                ram_0: 0,
-            },
-            State {
-                a_register: 2,
-                d_register: 2,
-                pc: 2,
-                ram_0: 0,
-            },
-            State {
-                a_register: 3,
-                d_register: 2,
-                pc: 3,
-                ram_0: 0,
-            },
-            State {
-                a_register: 3,
-                d_register: 5,
-                pc: 4,
-                ram_0: 0,
-            },
-            State {
-                a_register: 0,
-                d_register: 5,
-                pc: 5,
-                ram_0: 0,
-            },
-            State {
-                a_register: 0,
-                d_register: 5,
-                pc: 6,
-                ram_0: 5,
-            },
-        ];
+        // Reset clears RAM and the PC but keeps A and D, as on the hardware.
+        computer.reset();
+        assert_eq!(computer.pc(), 0);
+        assert_eq!(computer.cycles(), 0);
+        assert_eq!(computer.ram.read(0)?, 0);
+        assert_eq!(computer.cpu.d_register, 5);
 
-        let mut computer = Computer::new();
+        computer.execute()?;
+        assert_eq!((computer.pc(), computer.cpu.a_register), (1, 2));
+        assert_eq!(computer.cpu.d_register, 5);
+        for _ in 1..6 {
+            computer.execute()?;
+        }
+        assert_eq!(computer.ram.read(0)?, 5);
+        Ok(())
+    }
 
-        computer.load_program(add_program)?;
+    #[test]
+    fn test_max_program() -> Result<(), ScriptError> {
+        run_fixture("Max")
+    }
+
+    #[test]
+    fn test_load_hack() -> Result<(), Error> {
+        let dir = TempDir::new("load-hack");
+        let path = dir.join("Add.hack");
+        std::fs::write(
+            &path,
+            "0000000000000010\n1110110000010000\n0000000000000011\n1110000010010000\n0000000000000000\n1110001100001000\n",
+        )
+        .unwrap();
+
+        let mut computer = Computer::new();
+        computer.load_hack(&path)?;
 
-        for State {
-            a_register,
-            d_register,
-            pc,
-            ram_0,
-        } in expected_state
-        {
+        for _ in 0..6 {
             computer.execute()?;
-            assert_eq!(computer.cpu.a_register, a_register);
-            assert_eq!(computer.cpu.d_register, d_register);
-            assert_eq!(computer.prev_cpu_response.pc, pc);
-            assert_eq!(computer.ram.read(0b0)?, ram_0);
         }
+        assert_eq!(computer.ram.read(0)?, 5);
+        assert_eq!(computer.rom.program().len(), 6);
 
-        computer.reset();
+        // Trailing zero words, such as a final `@0`, stay in the program.
+        let mut computer = Computer::new();
+        computer.load_program(vec![0b1110110000010000, 0, 0])?;
+        assert_eq!(computer.rom.program(), [0b1110110000010000, 0, 0]);
+
+        Ok(())
+    }
 
-        let expected_state = vec![
-            State {
-                a_register: 2,
-                d_register: 5,
+    #[test]
+    fn test_illegal_instruction() -> Result<(), Error> {
+        let mut computer = Computer::new();
+        computer.load_program(vec![0b0000000000000111, 0b1110111110010000])?;
+
+        computer.execute()?;
+        assert!(matches!(
+            computer.execute(),
+            Err(Error::IllegalInstruction {
                 pc: 1,
-                ram_0: 0,
-            },
-            State {
-                a_register: 2,
-                d_register: 2,
-                pc: 2,
-                ram_0: 0,
-            },
-            State {
-                a_register: 3,
-                d_register: 2,
-                pc: 3,
-                ram_0: 0,
-            },
-            State {
-                a_register: 3,
-                d_register: 5,
-                pc: 4,
-                ram_0: 0,
-            },
-            State {
-                a_register: 0,
-                d_register: 5,
-                pc: 5,
-                ram_0: 0,
-            },
-            State {
-                a_register: 0,
-                d_register: 5,
-                pc: 6,
-                ram_0: 5,
-            },
-        ];
-
-        for State {
-            a_register,
-            d_register,
-            pc,
-            ram_0,
-        } in expected_state
-        {
-            computer.execute()?;
-            assert_eq!(computer.cpu.a_register, a_register);
-            assert_eq!(computer.cpu.d_register, d_register);
-            assert_eq!(computer.prev_cpu_response.pc, pc);
-            assert_eq!(computer.ram.read(0b0)?, ram_0);
-        }
+                word: 0b1110111110010000
+            })
+        ));
+        assert_eq!(computer.prev_cpu_response.pc, 1);
+        assert_eq!(computer.cpu.d_register, 0);
+
         Ok(())
     }
 
     #[test]
-    fn test_max_program() -> Result<(), Error> {
-        #[derive(Debug, Clone)]
-        struct State {
-            a_register: u16,
-            d_register: u16,
-            ram: (u16, u16, u16),
-            pc: u16,
-        }
-        let max_program: Vec<u16> = vec![
+    fn test_permissive_decoding() -> Result<(), Error> {
+        let mut computer = Computer::new();
+        computer.set_decoding(Decoding::Permissive);
+        // D=-1 encoded with a=1
+        computer.load_program(vec![0b1111111010010000])?;
+
+        computer.execute()?;
+        assert_eq!(computer.cpu.d_register, u16::MAX);
+        assert_eq!(computer.prev_cpu_response.pc, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pointer_read() -> Result<(), Error> {
+        let mut computer = Computer::new();
+        // @0, A=M, D=M: D must come from RAM[RAM[0]], not RAM[0].
+        computer.load_program(vec![
             0b0000000000000000,
+            0b1111110000100000,
             0b1111110000010000,
-            0b0000000000000001,
-            0b1111010011010000,
-            0b0000000000001010,
-            0b1110001100000001,
-            0b0000000000000001,
+        ])?;
+        computer.ram.load(300, 0)?;
+        computer.ram.load(42, 300)?;
+
+        for _ in 0..3 {
+            computer.execute()?;
+        }
+        assert_eq!(computer.cpu.d_register, 42);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_address_beyond_memory() -> Result<(), Error> {
+        let mut computer = Computer::new();
+        // @30000, D=A: A points past the keyboard but M is never used.
+        computer.load_program(vec![0b0111010100110000, 0b1110110000010000])?;
+
+        computer.execute()?;
+        computer.execute()?;
+        assert_eq!(computer.cpu.d_register, 30000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reads_m_only_when_used() -> Result<(), Error> {
+        let mut computer = Computer::new();
+        // @30000, D=A, @5, D=M, @30000, D=M
+        computer.load_program(vec![
+            0b0111010100110000,
+            0b1110110000010000,
+            0b0000000000000101,
             0b1111110000010000,
-            0b0000000000001100,
-            0b1110101010000111,
-            0b0000000000000000,
+            0b0111010100110000,
             0b1111110000010000,
-            0b0000000000000010,
-            0b1110001100001000,
-            0b0000000000001110,
-            0b1110101010000111,
-        ];
-
-        let expected_state = vec![
-            State {
-                a_register: 0,
-                d_register: 0,
-                pc: 1,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 0,
-                d_register: 3,
-                pc: 2,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 1,
-                d_register: 3,
-                pc: 3,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 1,
-                d_register: 65534,
-                pc: 4,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 10,
-                d_register: 65534,
-                pc: 5,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 10,
-                d_register: 65534,
-                pc: 6,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 1,
-                d_register: 65534,
-                pc: 7,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 1,
-                d_register: 5,
-                pc: 8,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 12,
-                d_register: 5,
-                pc: 9,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 12,
-                d_register: 5,
-                pc: 12,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 2,
-                d_register: 5,
-                pc: 13,
-                ram: (3, 5, 0),
-            },
-            State {
-                a_register: 2,
-                d_register: 5,
-                pc: 14,
-                ram: (3, 5, 5),
-            },
-        ];
+        ])?;
+        computer.ram.load(9, 5)?;
 
-        let mut computer = Computer::new();
+        let reads: Vec<Option<MemoryRead>> = (0..4)
+            .map(|_| {
+                computer.tick()?;
+                Ok(computer.tock()?.read)
+            })
+            .collect::<Result<_, Error>>()?;
+        assert_eq!(
+            reads,
+            [
+                None,
+                None,
+                None,
+                Some(MemoryRead {
+                    address: 5,
+                    value: 9
+                })
+            ]
+        );
+        assert_eq!(computer.cpu.d_register, 9);
 
-        computer.load_program(max_program)?;
+        // Reading M with A past the keyboard fails.
+        computer.execute()?;
+        assert!(matches!(computer.execute(), Err(Error::Memory(_))));
 
-        computer.ram.load(3, 0)?;
-        computer.ram.load(5, 1)?;
+        Ok(())
+    }
 
-        for State {
-            a_register,
-            d_register,
-            ram: (ram_0, ram_1, ram_2),
-            pc,
-        } in expected_state
-        {
-            computer.execute()?;
-            assert_eq!(computer.cpu.a_register, a_register);
-            assert_eq!(computer.cpu.d_register, d_register);
-            assert_eq!(computer.prev_cpu_response.pc, pc);
-            assert_eq!(computer.ram.read(0)?, ram_0);
-            assert_eq!(computer.ram.read(1)?, ram_1);
-            assert_eq!(computer.ram.read(2)?, ram_2);
-            assert_eq!(computer.ram.read(0b0)?, ram_0);
-        }
+    #[test]
+    fn test_tick_tock() -> Result<(), Error> {
+        let mut computer = Computer::new();
+        computer.load_program(vec![0b0000000000000111, 0b1110111111001000])?;
+        computer.step()?;
 
-        computer.reset();
+        // M=1: the write is visible on the outputs after the tick but only
+        // lands in RAM on the tock.
+        let response = computer.tick()?;
+        assert_eq!(computer.clock().to_string(), "1+");
+        assert!(response.write_m);
+        assert_eq!((response.out_m, response.address_m), (1, 7));
+        assert_eq!(computer.ram().read(7)?, 0);
+        assert_eq!(computer.pc(), 1);
+        assert!(matches!(
+            computer.tick(),
+            Err(Error::Clock(ClockError::AlreadyHigh))
+        ));
 
+        computer.tock()?;
+        assert_eq!(computer.clock().to_string(), "2");
+        assert_eq!(computer.ram().read(7)?, 1);
+        assert_eq!(computer.pc(), 2);
+        assert!(matches!(
+            computer.tock(),
+            Err(Error::Clock(ClockError::AlreadyLow))
+        ));
         Ok(())
     }
 }
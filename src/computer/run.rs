@@ -0,0 +1,219 @@
+use super::chip::cpu::instructions::{CpuInstructions, Jump};
+use super::{Computer, Error, WatchHit};
+
+#[derive(Debug)]
+pub enum StopReason {
+    /// The program reached the canonical `@N` / `0;JMP` loop at address `N`.
+    Halted {
+        pc: u16,
+    },
+    /// `max_cycles` instructions ran without any other stop condition.
+    CycleLimit,
+    /// A breakpoint was reached before executing the instruction at `pc`.
+    Breakpoint {
+        pc: u16,
+    },
+    /// The `run_until` predicate returned true after the cycle that left the
+    /// program counter at `pc`.
+    Condition {
+        pc: u16,
+    },
+    Watchpoint(WatchHit),
+    Error(Error),
+}
+
+impl Computer {
+    /// Runs until the program halts, fails, hits a breakpoint or watchpoint,
+    /// or `max_cycles` instructions have been executed. A breakpoint on the
+    /// current instruction is stepped over so a stopped run can be resumed.
+    pub fn run(&mut self, max_cycles: u64) -> StopReason {
+        self.run_with(Some(max_cycles), |_| false)
+    }
+
+    /// Runs until `predicate` returns true, checking it after every cycle.
+    /// Halting and errors still stop the run.
+    pub fn run_until(&mut self, predicate: impl FnMut(&Computer) -> bool) -> StopReason {
+        self.run_with(None, predicate)
+    }
+
+    /// Whether the next two instructions are `@pc` followed by an
+    /// unconditional jump that writes nothing, so the machine can never leave
+    /// this address again.
+    pub fn is_halted(&self) -> bool {
+        let pc = self.pc();
+        let (Ok(first), Ok(second)) = (self.rom.read(pc), self.rom.read(pc.wrapping_add(1))) else {
+            return false;
+        };
+
+        match (
+            CpuInstructions::from_bits(first),
+            CpuInstructions::from_bits(second),
+        ) {
+            (
+                CpuInstructions::Ainstruction(target),
+                CpuInstructions::CInstruction {
+                    dest,
+                    jump: Jump::Jmp,
+                    ..
+                },
+            ) => target == pc && u16::from(dest) == 0,
+            _ => false,
+        }
+    }
+
+    pub(super) fn run_with(
+        &mut self,
+        max_cycles: Option<u64>,
+        mut predicate: impl FnMut(&Computer) -> bool,
+    ) -> StopReason {
+        let mut cycles = 0;
+
+        loop {
+            // Breakpoints come first so one set on a halt loop is reported.
+            if cycles > 0 && self.at_breakpoint() {
+                return StopReason::Breakpoint { pc: self.pc() };
+            }
+            if self.is_halted() {
+                return StopReason::Halted { pc: self.pc() };
+            }
+            if max_cycles.is_some_and(|max| cycles >= max) {
+                return StopReason::CycleLimit;
+            }
+            let cycle = match self.step() {
+                Ok(cycle) => cycle,
+                Err(err) => return StopReason::Error(err),
+            };
+            cycles += 1;
+
+            if let Some(hit) = self.check_watchpoints(&cycle) {
+                return StopReason::Watchpoint(hit);
+            }
+            if predicate(self) {
+                return StopReason::Condition { pc: self.pc() };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    const MAX: &str = "
+        @R0
+        D=M
+        @R1
+        D=D-M
+        @OUTPUT_FIRST
+        D;JGT
+        @R1
+        D=M
+        @OUTPUT_D
+        0;JMP
+    (OUTPUT_FIRST)
+        @R0
+        D=M
+    (OUTPUT_D)
+        @R2
+        M=D
+    (END)
+        @END
+        0;JMP
+    ";
+
+    fn computer(source: &str) -> Computer {
+        let mut computer = Computer::new();
+        computer.load_program(assemble(source).unwrap()).unwrap();
+        computer
+    }
+
+    #[test]
+    fn test_run_halts() -> Result<(), Error> {
+        let mut computer = computer(MAX);
+        computer.ram_mut().load(3, 0)?;
+        computer.ram_mut().load(5, 1)?;
+
+        assert!(matches!(computer.run(1000), StopReason::Halted { pc: 14 }));
+        assert_eq!(computer.ram().read(2)?, 5);
+        assert_eq!(computer.cycles(), 12);
+
+        // Already halted: nothing else runs.
+        assert!(matches!(computer.run(1000), StopReason::Halted { pc: 14 }));
+        assert_eq!(computer.cycles(), 12);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_cycle_limit() {
+        let mut computer = computer("(LOOP)\n@LOOP\nD=D+1;JMP");
+
+        assert!(matches!(computer.run(100), StopReason::CycleLimit));
+        assert_eq!(computer.cycles(), 100);
+        assert_eq!(computer.cpu().d_register, 50);
+
+        assert!(matches!(computer.run(10), StopReason::CycleLimit));
+        assert_eq!(computer.cycles(), 110);
+    }
+
+    #[test]
+    fn test_run_until() {
+        let mut computer = computer("(LOOP)\n@LOOP\nD=D+1;JMP");
+
+        let reason = computer.run_until(|computer| computer.cpu().d_register == 7);
+
+        assert!(matches!(reason, StopReason::Condition { pc: 0 }));
+        assert_eq!(computer.cycles(), 14);
+    }
+
+    #[test]
+    fn test_breakpoint_on_halt_loop() -> Result<(), Error> {
+        let mut computer = computer(MAX);
+        computer.ram_mut().load(3, 0)?;
+        computer.ram_mut().load(5, 1)?;
+        computer.add_breakpoint(14);
+
+        assert!(matches!(
+            computer.run(1000),
+            StopReason::Breakpoint { pc: 14 }
+        ));
+        assert_eq!(computer.cycles(), 12);
+
+        // Resuming steps over the breakpoint and then sees the halt.
+        assert!(matches!(computer.run(1000), StopReason::Halted { pc: 14 }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_error() {
+        let mut computer = computer("@5\nD=A");
+        computer.rom.load(0b1111111111111111, 2).unwrap();
+
+        assert!(matches!(
+            computer.run(10),
+            StopReason::Error(Error::IllegalInstruction { pc: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_run_past_rom() -> Result<(), Error> {
+        let mut computer = computer("@16384\n0;JMP");
+        assert!(matches!(
+            computer.run(10),
+            StopReason::Error(Error::Memory(_))
+        ));
+        assert_eq!(computer.pc(), 16384);
+
+        // The last ROM word can be checked for a halt loop without reading
+        // past the end.
+        let mut computer = Computer::new();
+        computer.rom.load(0b0111111111111111, 16383)?;
+        computer.set_pc(16383);
+        assert!(!computer.is_halted());
+        assert!(matches!(
+            computer.run(10),
+            StopReason::Error(Error::Memory(_))
+        ));
+        Ok(())
+    }
+}
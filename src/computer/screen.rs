@@ -0,0 +1,235 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub(super) const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// Words per row of the screen memory map.
+pub(super) const ROW_WORDS: usize = Screen::WIDTH / 16;
+
+/// The 512x256 screen memory map: row `y` starts at word `32 * y`, and bit
+/// `x % 16` of each word is pixel `x`, least significant bit leftmost. A set
+/// bit is a black pixel.
+#[derive(Clone, Copy)]
+pub struct Screen<'a> {
+    words: &'a [u16; 8192],
+}
+
+impl<'a> Screen<'a> {
+    pub const WIDTH: usize = 512;
+    pub const HEIGHT: usize = 256;
+
+    pub fn new(words: &'a [u16; 8192]) -> Self {
+        Screen { words }
+    }
+
+    /// Whether the pixel at column `x`, row `y` is black.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        assert!(
+            x < Screen::WIDTH && y < Screen::HEIGHT,
+            "pixel ({x}, {y}) is off screen"
+        );
+        (self.words[y * ROW_WORDS + x / 16] >> (x % 16)) & 1 == 1
+    }
+
+    /// The words of each row, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u16]> {
+        self.words.chunks(ROW_WORDS)
+    }
+
+    /// Each row packed eight pixels to a byte, leftmost pixel in the most
+    /// significant bit and 1 for black.
+    fn packed_rows(&self) -> impl Iterator<Item = [u8; Screen::WIDTH / 8]> + 'a {
+        self.rows().map(|row| {
+            let mut bytes = [0; Screen::WIDTH / 8];
+            for (idx, word) in row.iter().enumerate() {
+                let word = word.reverse_bits();
+                bytes[2 * idx] = (word >> 8) as u8;
+                bytes[2 * idx + 1] = word as u8;
+            }
+            bytes
+        })
+    }
+
+    /// A binary (`P4`) portable bitmap.
+    pub fn to_pbm(&self) -> Vec<u8> {
+        let mut pbm = format!("P4\n{} {}\n", Screen::WIDTH, Screen::HEIGHT).into_bytes();
+        for row in self.packed_rows() {
+            pbm.extend_from_slice(&row);
+        }
+        pbm
+    }
+
+    /// A 1-bit grayscale PNG. The image data is stored uncompressed.
+    pub fn to_png(&self) -> Vec<u8> {
+        // Each row is a filter type byte (none) and the pixels, where a
+        // grayscale 0 is black.
+        let mut raw = Vec::with_capacity(Screen::HEIGHT * (1 + Screen::WIDTH / 8));
+        for row in self.packed_rows() {
+            raw.push(0);
+            raw.extend(row.iter().map(|byte| !byte));
+        }
+
+        // Bit depth 1, grayscale.
+        png(Screen::WIDTH, Screen::HEIGHT, 1, 0, &raw)
+    }
+
+    pub fn save_pbm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_pbm())
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_png())
+    }
+}
+
+/// A PNG of `raw`, rows already prefixed with their filter type byte.
+pub(super) fn png(width: usize, height: usize, depth: u8, color: u8, raw: &[u8]) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // Deflate, adaptive filtering, no interlace.
+    header.extend_from_slice(&[depth, color, 0, 0, 0]);
+
+    let mut png = PNG_SIGNATURE.to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &zlib_stored(raw));
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// A zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        zlib.push(blocks.peek().is_none() as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+pub(super) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+pub(super) fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen_words() -> Box<[u16; 8192]> {
+        let mut words = Box::new([0; 8192]);
+        // Pixel (0, 0), pixel (17, 1) and the whole of the last row.
+        words[0] = 0b1;
+        words[ROW_WORDS + 1] = 0b10;
+        words[255 * ROW_WORDS..].fill(0xFFFF);
+        words
+    }
+
+    #[test]
+    fn test_pixels() {
+        let words = screen_words();
+        let screen = Screen::new(&words);
+
+        assert!(screen.pixel(0, 0));
+        assert!(!screen.pixel(1, 0));
+        assert!(screen.pixel(17, 1));
+        assert!(!screen.pixel(16, 1));
+        assert!(screen.pixel(511, 255));
+        assert_eq!(screen.rows().count(), Screen::HEIGHT);
+        assert_eq!(screen.rows().nth(1).unwrap()[1], 0b10);
+
+        let pbm = screen.to_pbm();
+        let header = b"P4\n512 256\n";
+        assert_eq!(&pbm[..header.len()], header);
+        let bitmap = &pbm[header.len()..];
+        assert_eq!(bitmap.len(), Screen::WIDTH * Screen::HEIGHT / 8);
+        assert_eq!(bitmap[0], 0b1000_0000);
+        assert_eq!(bitmap[64 + 2], 0b0100_0000);
+        assert!(bitmap[255 * 64..].iter().all(|byte| *byte == 0xFF));
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn test_png() {
+        let words = screen_words();
+        let png = Screen::new(&words).to_png();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        // Walk the chunks, checking each CRC.
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (body, crc) = rest[4..].split_at(4 + len);
+            assert_eq!(
+                crc32(body),
+                u32::from_be_bytes(crc[..4].try_into().unwrap())
+            );
+            chunks.push((&body[..4], &body[4..]));
+            rest = &crc[4..];
+        }
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1, [0, 0, 2, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0]);
+
+        // Inflate the stored blocks.
+        let zlib = chunks[1].1;
+        assert_eq!(&zlib[..2], [0x78, 0x01]);
+        let mut raw = Vec::new();
+        let mut pos = 2;
+        loop {
+            let last = zlib[pos] & 1 == 1;
+            let len = u16::from_le_bytes([zlib[pos + 1], zlib[pos + 2]]) as usize;
+            let nlen = u16::from_le_bytes([zlib[pos + 3], zlib[pos + 4]]) as usize;
+            assert_eq!(len, !nlen & 0xFFFF);
+            raw.extend_from_slice(&zlib[pos + 5..pos + 5 + len]);
+            pos += 5 + len;
+            if last {
+                break;
+            }
+        }
+        assert_eq!(zlib[pos..], adler32(&raw).to_be_bytes());
+
+        let rows: Vec<&[u8]> = raw.chunks(1 + Screen::WIDTH / 8).collect();
+        assert_eq!(rows.len(), Screen::HEIGHT);
+        assert!(rows.iter().all(|row| row[0] == 0));
+        assert_eq!(rows[0][1], 0b0111_1111);
+        assert_eq!(rows[1][3], 0b1011_1111);
+        assert!(rows[255][1..].iter().all(|byte| *byte == 0));
+    }
+}
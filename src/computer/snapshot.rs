@@ -0,0 +1,453 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use super::chip::clock::Clock;
+use super::chip::cpu::{CPUResponse, Cpu};
+use super::chip::memory::{Ram, Rom};
+use super::{Computer, Error};
+
+const MAGIC: &[u8; 8] = b"HACKSNAP";
+const VERSION: u16 = 2;
+
+const ROM_WORDS: usize = 16384;
+const RAM_WORDS: usize = 16384 + 8192 + 1;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    ProgramTooLong(u16),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "{error}"),
+            SnapshotError::InvalidMagic => write!(f, "not a snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {version}")
+            }
+            SnapshotError::ProgramTooLong(len) => {
+                write!(f, "program of {len} words does not fit in ROM")
+            }
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(value: io::Error) -> Self {
+        SnapshotError::Io(value)
+    }
+}
+
+/// A copy of everything that determines how a `Computer` continues: ROM,
+/// RAM (including the screen and keyboard maps), the CPU registers, the last
+/// CPU response and the cycle count.
+#[derive(Clone)]
+pub struct Snapshot {
+    rom: Rom,
+    ram: Ram,
+    cpu: Cpu,
+    response: CPUResponse,
+    cycles: u64,
+}
+
+/// One field that differs between two snapshots, `left` being `self` in
+/// `Snapshot::diff`. RAM addresses use the Hack memory map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Difference {
+    ARegister {
+        left: u16,
+        right: u16,
+    },
+    DRegister {
+        left: u16,
+        right: u16,
+    },
+    Response {
+        left: CPUResponse,
+        right: CPUResponse,
+    },
+    Cycles {
+        left: u64,
+        right: u64,
+    },
+    Rom {
+        address: u16,
+        left: u16,
+        right: u16,
+    },
+    Ram {
+        address: u16,
+        left: u16,
+        right: u16,
+    },
+}
+
+impl Snapshot {
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.response.pc
+    }
+
+    pub fn ram(&self) -> &Ram {
+        &self.ram
+    }
+
+    pub fn rom(&self) -> &Rom {
+        &self.rom
+    }
+
+    pub fn diff(&self, other: &Snapshot) -> Vec<Difference> {
+        let mut differences = Vec::new();
+
+        if self.cpu.a_register != other.cpu.a_register {
+            differences.push(Difference::ARegister {
+                left: self.cpu.a_register,
+                right: other.cpu.a_register,
+            });
+        }
+        if self.cpu.d_register != other.cpu.d_register {
+            differences.push(Difference::DRegister {
+                left: self.cpu.d_register,
+                right: other.cpu.d_register,
+            });
+        }
+        if self.response != other.response {
+            differences.push(Difference::Response {
+                left: self.response,
+                right: other.response,
+            });
+        }
+        if self.cycles != other.cycles {
+            differences.push(Difference::Cycles {
+                left: self.cycles,
+                right: other.cycles,
+            });
+        }
+
+        let rom = self.rom.words().iter().zip(other.rom.words());
+        for (address, (left, right)) in rom.enumerate() {
+            if left != right {
+                differences.push(Difference::Rom {
+                    address: address as u16,
+                    left: *left,
+                    right: *right,
+                });
+            }
+        }
+
+        for (address, (left, right)) in self.ram.words().zip(other.ram.words()).enumerate() {
+            if left != right {
+                differences.push(Difference::Ram {
+                    address: address as u16,
+                    left,
+                    right,
+                });
+            }
+        }
+
+        differences
+    }
+
+    /// Writes the versioned format: the `HACKSNAP` magic, a `u16` version,
+    /// then the cycle count, registers, last response, program length, ROM
+    /// and RAM, all little-endian.
+    pub fn write_to(&self, out: impl Write) -> Result<(), SnapshotError> {
+        let mut out = BufWriter::new(out);
+
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&self.cycles.to_le_bytes())?;
+        for word in [
+            self.cpu.a_register,
+            self.cpu.d_register,
+            self.response.out_m,
+            self.response.write_m as u16,
+            self.response.address_m,
+            self.response.pc,
+            self.rom.program().len() as u16,
+        ] {
+            out.write_all(&word.to_le_bytes())?;
+        }
+        for word in self.rom.words().iter().copied().chain(self.ram.words()) {
+            out.write_all(&word.to_le_bytes())?;
+        }
+
+        Ok(out.flush()?)
+    }
+
+    pub fn read_from(input: impl Read) -> Result<Self, SnapshotError> {
+        let mut input = BufReader::new(input);
+
+        let mut magic = [0; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+        let version = read_word(&mut input)?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let mut cycles = [0; 8];
+        input.read_exact(&mut cycles)?;
+
+        let mut registers = [0; 7];
+        for word in registers.iter_mut() {
+            *word = read_word(&mut input)?;
+        }
+        let [a_register, d_register, out_m, write_m, address_m, pc, len] = registers;
+        if len as usize > ROM_WORDS {
+            return Err(SnapshotError::ProgramTooLong(len));
+        }
+
+        let mut words = vec![0; ROM_WORDS];
+        for word in words.iter_mut() {
+            *word = read_word(&mut input)?;
+        }
+        let mut rom = Rom::new();
+        rom.load_program(&words[..len as usize])
+            .expect("program fits in ROM");
+        let mut ram = Ram::new();
+        for address in 0..RAM_WORDS {
+            ram.load(read_word(&mut input)?, address as u16)
+                .expect("address inside RAM");
+        }
+
+        let mut cpu = Cpu::new();
+        cpu.a_register = a_register;
+        cpu.d_register = d_register;
+        cpu.pc = pc;
+
+        Ok(Snapshot {
+            rom,
+            ram,
+            cpu,
+            response: CPUResponse {
+                out_m,
+                write_m: write_m != 0,
+                address_m,
+                pc,
+            },
+            cycles: u64::from_le_bytes(cycles),
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        self.write_to(File::create(path)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Snapshot::read_from(File::open(path)?)
+    }
+}
+
+fn read_word(input: &mut impl Read) -> Result<u16, SnapshotError> {
+    let mut bytes = [0; 2];
+    input.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+impl Computer {
+    /// Captures the machine between cycles. Between a `tick` and its `tock`
+    /// this is the state before the tick.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            rom: self.rom.clone(),
+            ram: self.ram.clone(),
+            cpu: self.cpu.clone(),
+            response: self.prev_cpu_response,
+            cycles: self.clock.cycle(),
+        }
+    }
+
+    /// Puts the machine back in the state captured by `snapshot`. Breakpoints,
+    /// watchpoints and the tracer are kept; the undo journal is cleared since
+    /// it no longer matches.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.rom = snapshot.rom.clone();
+        self.ram = snapshot.ram.clone();
+        self.cpu = snapshot.cpu.clone();
+        self.prev_cpu_response = snapshot.response;
+        self.clock = Clock::at(snapshot.cycles);
+        self.pending = None;
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+    }
+
+    pub fn save_snapshot(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        Ok(self.snapshot().save(path)?)
+    }
+
+    pub fn load_snapshot(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.restore(&Snapshot::load(path)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::test_util::TempDir;
+
+    fn computer() -> Computer {
+        let mut computer = Computer::new();
+        let program = assemble(
+            "
+            @SCREEN
+            M=-1
+            @i
+            M=M+1
+            D=M
+            @KBD
+            M=D
+            @2
+            0;JMP
+            ",
+        )
+        .unwrap();
+        computer.load_program(program).unwrap();
+        computer
+    }
+
+    #[test]
+    fn test_restore() -> Result<(), Error> {
+        let mut computer = computer();
+        computer.run(20);
+        let snapshot = computer.snapshot();
+
+        computer.run(50);
+        assert_ne!(computer.ram.read(16)?, snapshot.ram().read(16)?);
+
+        computer.restore(&snapshot);
+        assert_eq!(computer.cycles(), 20);
+        assert!(computer.snapshot().diff(&snapshot).is_empty());
+
+        // Resuming from the snapshot behaves like never having left it.
+        let mut fresh = self::computer();
+        fresh.run(70);
+        computer.run(50);
+        assert!(computer.snapshot().diff(&fresh.snapshot()).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut computer = computer();
+        let before = computer.snapshot();
+        computer.run(4);
+        let after = computer.snapshot();
+
+        assert_eq!(
+            before.diff(&after),
+            vec![
+                Difference::ARegister { left: 0, right: 16 },
+                Difference::Response {
+                    left: CPUResponse {
+                        out_m: 0,
+                        write_m: false,
+                        address_m: 0,
+                        pc: 0
+                    },
+                    right: CPUResponse {
+                        out_m: 1,
+                        write_m: true,
+                        address_m: 16,
+                        pc: 4
+                    }
+                },
+                Difference::Cycles { left: 0, right: 4 },
+                Difference::Ram {
+                    address: 16,
+                    left: 0,
+                    right: 1
+                },
+                Difference::Ram {
+                    address: 16384,
+                    left: 0,
+                    right: 0xFFFF
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_round_trip() -> Result<(), SnapshotError> {
+        let mut computer = computer();
+        computer.run(37);
+        let snapshot = computer.snapshot();
+
+        let mut bytes = Vec::new();
+        snapshot.write_to(&mut bytes)?;
+        assert_eq!(&bytes[..10], b"HACKSNAP\x02\x00");
+
+        let loaded = Snapshot::read_from(bytes.as_slice())?;
+        assert!(loaded.diff(&snapshot).is_empty());
+        assert_eq!(
+            loaded.ram().read(24576).unwrap(),
+            computer.ram.read(24576).unwrap()
+        );
+
+        let dir = TempDir::new("snapshot");
+        let path = dir.join("Add.snap");
+        snapshot.save(&path)?;
+        let mut restored = Computer::new();
+        restored.load_snapshot(&path).unwrap();
+        assert!(restored.snapshot().diff(&snapshot).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trailing_zero_word_round_trip() -> Result<(), Error> {
+        let mut computer = Computer::new();
+        computer.load_program(assemble("@5\nD=A\n@0").unwrap())?;
+        let dir = TempDir::new("snapshot-zero");
+        computer.save_snapshot(dir.join("Zero.snap"))?;
+
+        let mut restored = Computer::new();
+        restored.load_snapshot(dir.join("Zero.snap"))?;
+        assert_eq!(restored.rom().program(), computer.rom().program());
+        assert_eq!(restored.rom().program().len(), 3);
+
+        restored.save_hack(dir.join("Zero.hack"))?;
+        assert_eq!(
+            crate::program::read_hack(dir.join("Zero.hack"))?,
+            computer.rom().program()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert!(matches!(
+            Snapshot::read_from(&b"NOTASNAP\x01\x00"[..]),
+            Err(SnapshotError::InvalidMagic)
+        ));
+        assert!(matches!(
+            Snapshot::read_from(&b"HACKSNAP\x01\x00"[..]),
+            Err(SnapshotError::UnsupportedVersion(1))
+        ));
+        assert!(matches!(
+            Snapshot::read_from(&b"HACKSNAP\x02\x00\x05"[..]),
+            Err(SnapshotError::Io(_))
+        ));
+
+        let mut header = b"HACKSNAP\x02\x00".to_vec();
+        header.extend_from_slice(&[0; 8 + 12]);
+        header.extend_from_slice(&16385u16.to_le_bytes());
+        assert!(matches!(
+            Snapshot::read_from(header.as_slice()),
+            Err(SnapshotError::ProgramTooLong(16385))
+        ));
+    }
+}
@@ -0,0 +1,294 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use super::{Computer, Screen, StopReason};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Glyphs {
+    /// Braille patterns, 2x4 pixels per character.
+    Braille,
+    /// `▀`, `▄` and `█`, 1x2 pixels per character.
+    HalfBlocks,
+}
+
+impl Glyphs {
+    /// Pixels per character across and down.
+    fn cell(&self) -> (usize, usize) {
+        match self {
+            Glyphs::Braille => (2, 4),
+            Glyphs::HalfBlocks => (1, 2),
+        }
+    }
+
+    /// The character for a cell, `pixel(dx, dy)` being whether the pixel at
+    /// that offset within it is black.
+    fn glyph(&self, pixel: impl Fn(usize, usize) -> bool) -> char {
+        match self {
+            Glyphs::Braille => {
+                const DOTS: [(usize, usize); 8] = [
+                    (0, 0),
+                    (0, 1),
+                    (0, 2),
+                    (1, 0),
+                    (1, 1),
+                    (1, 2),
+                    (0, 3),
+                    (1, 3),
+                ];
+                let bits = DOTS
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (dx, dy))| pixel(*dx, *dy))
+                    .fold(0, |bits, (bit, _)| bits | 1 << bit);
+                char::from_u32(0x2800 + bits).expect("braille patterns are characters")
+            }
+            Glyphs::HalfBlocks => match (pixel(0, 0), pixel(0, 1)) {
+                (false, false) => ' ',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (true, true) => '█',
+            },
+        }
+    }
+}
+
+/// Draws the screen in a terminal with ANSI escapes, rewriting only the
+/// lines that changed since the last frame.
+pub struct TerminalView {
+    glyphs: Glyphs,
+    scale: usize,
+    previous: Vec<String>,
+}
+
+impl TerminalView {
+    pub fn new() -> Self {
+        TerminalView {
+            glyphs: Glyphs::Braille,
+            scale: 1,
+            previous: Vec::new(),
+        }
+    }
+
+    pub fn set_glyphs(&mut self, glyphs: Glyphs) {
+        self.glyphs = glyphs;
+        self.previous.clear();
+    }
+
+    /// Shrinks the screen by `scale` in both directions: a pixel is black
+    /// if any pixel of the `scale` x `scale` block it stands for is.
+    pub fn set_scale(&mut self, scale: usize) {
+        assert!(scale > 0, "scale must be at least 1");
+        self.scale = scale;
+        self.previous.clear();
+    }
+
+    /// The screen as lines of text.
+    pub fn lines(&self, screen: &Screen) -> Vec<String> {
+        let scale = self.scale;
+        let (width, height) = (
+            Screen::WIDTH.div_ceil(scale),
+            Screen::HEIGHT.div_ceil(scale),
+        );
+        let pixel = |x: usize, y: usize| {
+            x < width
+                && y < height
+                && (y * scale..((y + 1) * scale).min(Screen::HEIGHT)).any(|y| {
+                    (x * scale..((x + 1) * scale).min(Screen::WIDTH)).any(|x| screen.pixel(x, y))
+                })
+        };
+
+        let (cell_width, cell_height) = self.glyphs.cell();
+        (0..height.div_ceil(cell_height))
+            .map(|row| {
+                (0..width.div_ceil(cell_width))
+                    .map(|column| {
+                        self.glyphs
+                            .glyph(|dx, dy| pixel(column * cell_width + dx, row * cell_height + dy))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The escapes that bring the terminal up to date with `screen`: the
+    /// whole picture on the first frame, then only the changed lines.
+    pub fn render(&mut self, screen: &Screen) -> String {
+        let lines = self.lines(screen);
+        let mut output = String::new();
+        if self.previous.is_empty() {
+            output.push_str("\x1b[2J");
+        }
+        for (row, line) in lines.iter().enumerate() {
+            if self.previous.get(row) != Some(line) {
+                output.push_str(&format!("\x1b[{};1H{line}", row + 1));
+            }
+        }
+        self.previous = lines;
+        output
+    }
+
+    pub fn draw(&mut self, screen: &Screen, out: &mut impl Write) -> io::Result<()> {
+        let frame = self.render(screen);
+        if !frame.is_empty() {
+            out.write_all(frame.as_bytes())?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Runs `computer` as `Computer::run` does, redrawing at most once per
+    /// `refresh` and once more when it stops.
+    pub fn run(
+        &mut self,
+        computer: &mut Computer,
+        out: &mut impl Write,
+        refresh: Duration,
+        max_cycles: Option<u64>,
+    ) -> io::Result<StopReason> {
+        self.draw(&computer.ram().screen(), out)?;
+
+        let mut last = Instant::now();
+        let mut error = None;
+        let stop = computer.run_with(max_cycles, |computer| {
+            if last.elapsed() < refresh {
+                return false;
+            }
+            last = Instant::now();
+            error = self.draw(&computer.ram().screen(), out).err();
+            error.is_some()
+        });
+        if let Some(error) = error {
+            return Err(error);
+        }
+
+        self.draw(&computer.ram().screen(), out)?;
+        Ok(stop)
+    }
+}
+
+impl Default for TerminalView {
+    fn default() -> Self {
+        TerminalView::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    /// Pixels (0, 0), (1, 3) and (3, 4), and the bottom right corner.
+    fn screen_words() -> Box<[u16; 8192]> {
+        let mut words = Box::new([0; 8192]);
+        words[0] = 0b1;
+        words[3 * 32] = 0b10;
+        words[4 * 32] = 0b1000;
+        words[8191] = 0x8000;
+        words
+    }
+
+    #[test]
+    fn test_lines() {
+        struct Test {
+            glyphs: Glyphs,
+            scale: usize,
+            size: (usize, usize),
+            top_left: &'static [&'static str],
+            bottom_right: char,
+        }
+        let tests = vec![
+            Test {
+                glyphs: Glyphs::Braille,
+                scale: 1,
+                size: (256, 64),
+                top_left: &["⢁ ", " ⠈"],
+                bottom_right: '⢀',
+            },
+            Test {
+                glyphs: Glyphs::HalfBlocks,
+                scale: 1,
+                size: (512, 128),
+                top_left: &["▀   ", " ▄  ", "   ▀"],
+                bottom_right: '▄',
+            },
+            Test {
+                glyphs: Glyphs::HalfBlocks,
+                scale: 2,
+                size: (256, 64),
+                top_left: &["█ ", " ▀"],
+                bottom_right: '▄',
+            },
+            Test {
+                glyphs: Glyphs::Braille,
+                scale: 3,
+                size: (86, 22),
+                top_left: &["⠓"],
+                bottom_right: '⠂',
+            },
+        ];
+
+        let words = screen_words();
+        let screen = Screen::new(&words);
+        for test in tests {
+            let mut view = TerminalView::new();
+            view.set_glyphs(test.glyphs);
+            view.set_scale(test.scale);
+            let lines = view.lines(&screen);
+
+            let name = format!("{:?} x{}", test.glyphs, test.scale);
+            assert_eq!(lines.len(), test.size.1, "{name}");
+            assert!(
+                lines.iter().all(|line| line.chars().count() == test.size.0),
+                "{name}"
+            );
+            for (line, expected) in lines.iter().zip(test.top_left) {
+                let start: String = line.chars().take(expected.chars().count()).collect();
+                let start = start.replace('⠀', " ");
+                assert_eq!(start, expected.replace('⠀', " "), "{name}");
+            }
+            let last = lines.last().unwrap().chars().last().unwrap();
+            assert_eq!(last, test.bottom_right, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_render_changes() {
+        let mut words = screen_words();
+        let mut view = TerminalView::new();
+        view.set_glyphs(Glyphs::HalfBlocks);
+
+        let first = view.render(&Screen::new(&words));
+        assert!(first.starts_with("\x1b[2J\x1b[1;1H▀"));
+        assert_eq!(first.matches("\x1b[").count(), 1 + 128);
+        assert_eq!(view.render(&Screen::new(&words)), "");
+
+        words[10 * 32] = 0xFFFF;
+        let frame = view.render(&Screen::new(&words));
+        let line = format!("\x1b[6;1H{}{}", "▀".repeat(16), " ".repeat(496));
+        assert_eq!(frame, line);
+    }
+
+    #[test]
+    fn test_run() {
+        let program =
+            assemble("@SCREEN\nM=-1\n@SCREEN\nD=A\n@32\nA=D+A\nM=-1\n(END)\n@END\n0;JMP\n")
+                .unwrap();
+        let mut computer = Computer::new();
+        computer.load_program(program).unwrap();
+
+        let mut out = Vec::new();
+        let mut view = TerminalView::new();
+        view.set_glyphs(Glyphs::HalfBlocks);
+        let stop = view
+            .run(&mut computer, &mut out, Duration::ZERO, Some(100))
+            .unwrap();
+        assert!(matches!(stop, StopReason::Halted { pc: 7 }));
+
+        // A blank first frame, then line 1 as the two rows are drawn.
+        let out = String::from_utf8(out).unwrap();
+        let frames: Vec<&str> = out.split("\x1b[1;1H").skip(1).collect();
+        assert_eq!(frames.len(), 3);
+        assert!(frames[1].starts_with(&"▀".repeat(16)));
+        assert!(frames[2].starts_with(&format!("{} ", "█".repeat(16))));
+    }
+}
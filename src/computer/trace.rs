@@ -0,0 +1,416 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+use super::chip::cpu::instructions::CpuInstructions;
+use super::chip::cpu::CPUResponse;
+use super::{Computer, MemoryWrite};
+
+const MAGIC: &[u8; 4] = b"HTRC";
+const VERSION: u8 = 1;
+
+const WRITE_M: u8 = 0b01;
+const HAS_WRITE: u8 = 0b10;
+
+#[derive(Debug)]
+pub enum TraceError {
+    Io(io::Error),
+    InvalidHeader,
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::Io(error) => write!(f, "{error}"),
+            TraceError::InvalidHeader => write!(f, "not a binary trace"),
+            TraceError::UnsupportedVersion(version) => {
+                write!(f, "unsupported trace version {version}")
+            }
+        }
+    }
+}
+
+impl From<io::Error> for TraceError {
+    fn from(value: io::Error) -> Self {
+        TraceError::Io(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    /// One human readable line per cycle, e.g.
+    /// `3 2 @16 A=16 D=0 outM=0 writeM=0 addressM=16`.
+    Text,
+    /// A `HTRC` header followed by fixed little-endian records, with the
+    /// memory write appended only when the cycle performed one.
+    Binary,
+}
+
+/// Everything observable about one executed instruction. The registers are
+/// the values after the cycle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceRecord {
+    pub cycle: u64,
+    pub pc: u16,
+    pub instruction: CpuInstructions,
+    pub a_register: u16,
+    pub d_register: u16,
+    pub response: CPUResponse,
+    pub write: Option<MemoryWrite>,
+}
+
+pub struct Tracer {
+    out: BufWriter<Box<dyn Write>>,
+    format: TraceFormat,
+    started: bool,
+}
+
+impl Tracer {
+    pub fn new(out: impl Write + 'static, format: TraceFormat) -> Self {
+        Tracer {
+            out: BufWriter::new(Box::new(out)),
+            format,
+            started: false,
+        }
+    }
+
+    pub fn create(path: impl AsRef<Path>, format: TraceFormat) -> Result<Self, TraceError> {
+        Ok(Tracer::new(File::create(path)?, format))
+    }
+
+    /// Writes one record. The record is sent to the output in a single
+    /// write, so a failed write never leaves part of it behind and the same
+    /// record may be recorded again.
+    pub fn record(&mut self, record: &TraceRecord) -> Result<(), TraceError> {
+        let mut bytes = Vec::new();
+        match self.format {
+            TraceFormat::Text => record_text(&mut bytes, record),
+            TraceFormat::Binary => {
+                if !self.started {
+                    bytes.extend_from_slice(MAGIC);
+                    bytes.push(VERSION);
+                }
+                record_binary(&mut bytes, record)
+            }
+        }
+        self.out.write_all(&bytes)?;
+        self.started = true;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), TraceError> {
+        Ok(self.out.flush()?)
+    }
+}
+
+fn record_text(out: &mut Vec<u8>, record: &TraceRecord) {
+    let TraceRecord {
+        cycle,
+        pc,
+        instruction,
+        a_register,
+        d_register,
+        response,
+        write,
+    } = record;
+
+    let mut line = format!(
+        "{cycle} {pc} {instruction} A={a_register} D={d_register} outM={} writeM={} addressM={}",
+        response.out_m, response.write_m as u8, response.address_m
+    );
+    if let Some(MemoryWrite { address, old, new }) = write {
+        line += &format!(" RAM[{address}]={old}->{new}");
+    }
+    line.push('\n');
+    out.extend_from_slice(line.as_bytes());
+}
+
+fn record_binary(out: &mut Vec<u8>, record: &TraceRecord) {
+    let mut flags = 0;
+    if record.response.write_m {
+        flags |= WRITE_M;
+    }
+    if record.write.is_some() {
+        flags |= HAS_WRITE;
+    }
+
+    out.extend_from_slice(&record.cycle.to_le_bytes());
+    for word in [
+        record.pc,
+        u16::from(record.instruction),
+        record.a_register,
+        record.d_register,
+        record.response.out_m,
+        record.response.address_m,
+        record.response.pc,
+    ] {
+        out.extend_from_slice(&word.to_le_bytes());
+    }
+    out.push(flags);
+
+    if let Some(write) = record.write {
+        for word in [write.address, write.old, write.new] {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+    }
+}
+
+/// Reads back every record written by a `TraceFormat::Binary` tracer.
+pub fn read_binary_trace(mut input: impl Read) -> Result<Vec<TraceRecord>, TraceError> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes)?;
+
+    if bytes.is_empty() {
+        return Ok(Vec::new());
+    }
+    if bytes.len() < 5 || &bytes[..4] != MAGIC {
+        return Err(TraceError::InvalidHeader);
+    }
+    if bytes[4] != VERSION {
+        return Err(TraceError::UnsupportedVersion(bytes[4]));
+    }
+
+    let mut reader = &bytes[5..];
+    let mut records = Vec::new();
+
+    while !reader.is_empty() {
+        let mut cycle = [0; 8];
+        reader.read_exact(&mut cycle)?;
+        let [pc, word, a_register, d_register, out_m, address_m, next_pc] =
+            read_words(&mut reader)?;
+        let mut flags = [0];
+        reader.read_exact(&mut flags)?;
+
+        let write = if flags[0] & HAS_WRITE != 0 {
+            let [address, old, new] = read_words(&mut reader)?;
+            Some(MemoryWrite { address, old, new })
+        } else {
+            None
+        };
+
+        records.push(TraceRecord {
+            cycle: u64::from_le_bytes(cycle),
+            pc,
+            instruction: CpuInstructions::from_bits(word),
+            a_register,
+            d_register,
+            response: CPUResponse {
+                out_m,
+                write_m: flags[0] & WRITE_M != 0,
+                address_m,
+                pc: next_pc,
+            },
+            write,
+        });
+    }
+
+    Ok(records)
+}
+
+fn read_words<const N: usize>(reader: &mut &[u8]) -> Result<[u16; N], TraceError> {
+    let mut words = [0; N];
+    for word in words.iter_mut() {
+        let mut bytes = [0; 2];
+        reader.read_exact(&mut bytes)?;
+        *word = u16::from_le_bytes(bytes);
+    }
+    Ok(words)
+}
+
+impl Computer {
+    /// Records every following cycle to `tracer` until `stop_trace`.
+    pub fn start_trace(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    /// Detaches the tracer, flushing anything it buffered.
+    pub fn stop_trace(&mut self) -> Result<Option<Tracer>, TraceError> {
+        if let Some(tracer) = &mut self.tracer {
+            tracer.flush()?;
+        }
+        Ok(self.tracer.take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::computer::Error;
+
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn add_computer() -> Computer {
+        let mut computer = Computer::new();
+        computer
+            .load_program(assemble("@2\nD=A\n@3\nD=D+A\n@0\nM=D").unwrap())
+            .unwrap();
+        computer
+    }
+
+    #[test]
+    fn test_text_trace() -> Result<(), Error> {
+        let out = Shared::default();
+        let mut computer = add_computer();
+
+        computer.start_trace(Tracer::new(out.clone(), TraceFormat::Text));
+        for _ in 0..6 {
+            computer.execute()?;
+        }
+        computer.stop_trace()?;
+        computer.execute()?;
+
+        let text = String::from_utf8(out.0.take()).unwrap();
+        assert_eq!(
+            text,
+            [
+                "1 0 @2 A=2 D=0 outM=0 writeM=0 addressM=2",
+                "2 1 D=A A=2 D=2 outM=2 writeM=0 addressM=2",
+                "3 2 @3 A=3 D=2 outM=0 writeM=0 addressM=3",
+                "4 3 D=D+A A=3 D=5 outM=5 writeM=0 addressM=3",
+                "5 4 @0 A=0 D=5 outM=0 writeM=0 addressM=0",
+                "6 5 M=D A=0 D=5 outM=5 writeM=1 addressM=0 RAM[0]=0->5",
+                "",
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+
+    /// Writes to `out`, but fails every write while `broken` is set.
+    #[derive(Clone, Default)]
+    struct Flaky {
+        out: Shared,
+        broken: Rc<Cell<bool>>,
+    }
+
+    impl Write for Flaky {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.broken.get() {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            self.out.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_trace_error_leaves_cycle_uncommitted() {
+        for format in [TraceFormat::Text, TraceFormat::Binary] {
+            let out = Flaky::default();
+            out.broken.set(true);
+            let mut computer = add_computer();
+            computer.start_trace(Tracer::new(out.clone(), format));
+
+            // The tracer buffers its output, so the error shows up once the
+            // buffer fills.
+            let error = loop {
+                computer.tick().unwrap();
+                let (pc, cycles) = (computer.pc(), computer.cycles());
+                if let Err(error) = computer.tock() {
+                    assert_eq!(computer.pc(), pc);
+                    assert_eq!(computer.cycles(), cycles);
+                    assert!(computer.clock().is_high());
+                    break error;
+                }
+            };
+            assert!(matches!(error, Error::Trace(TraceError::Io(_))));
+
+            out.broken.set(false);
+            let cycles = computer.cycles();
+            let cycle = computer.tock().unwrap();
+            assert_eq!(computer.pc(), cycle.response.pc);
+            assert_eq!(computer.cycles(), cycles + 1);
+            computer.stop_trace().unwrap();
+
+            // The retried cycle is traced once, after the records buffered
+            // before the failure.
+            let expected = Shared::default();
+            let mut reference = add_computer();
+            reference.start_trace(Tracer::new(expected.clone(), format));
+            while reference.cycles() < computer.cycles() {
+                reference.execute().unwrap();
+            }
+            reference.stop_trace().unwrap();
+            assert!(out.out.0.take() == expected.0.take(), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_binary_trace_round_trip() -> Result<(), Error> {
+        let out = Shared::default();
+        let mut computer = add_computer();
+
+        computer.start_trace(Tracer::new(out.clone(), TraceFormat::Binary));
+        for _ in 0..6 {
+            computer.execute()?;
+        }
+        computer.stop_trace()?;
+
+        let bytes = out.0.take();
+        assert_eq!(&bytes[..5], b"HTRC\x01");
+
+        let records = read_binary_trace(bytes.as_slice())?;
+        assert_eq!(records.len(), 6);
+        assert_eq!(
+            records[5],
+            TraceRecord {
+                cycle: 6,
+                pc: 5,
+                instruction: "M=D".parse().unwrap(),
+                a_register: 0,
+                d_register: 5,
+                response: CPUResponse {
+                    out_m: 5,
+                    write_m: true,
+                    address_m: 0,
+                    pc: 6,
+                },
+                write: Some(MemoryWrite {
+                    address: 0,
+                    old: 0,
+                    new: 5,
+                }),
+            }
+        );
+        assert_eq!(records[3].instruction.to_string(), "D=D+A");
+        assert_eq!(records[3].write, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_trace_errors() {
+        assert!(matches!(
+            read_binary_trace(&b"NOPE\x01"[..]),
+            Err(TraceError::InvalidHeader)
+        ));
+        assert!(matches!(
+            read_binary_trace(&b"HTRC\x02"[..]),
+            Err(TraceError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            read_binary_trace(&b"HTRC\x01\x01\x00"[..]),
+            Err(TraceError::Io(_))
+        ));
+    }
+}
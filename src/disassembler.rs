@@ -0,0 +1,183 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Write};
+
+use crate::computer::chip::cpu::instructions::{CpuInstructions, Jump};
+use crate::computer::chip::memory::Rom;
+
+#[derive(Debug, PartialEq)]
+pub enum DisassemblerError {
+    IllegalInstruction { address: u16, word: u16 },
+}
+
+impl fmt::Display for DisassemblerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisassemblerError::IllegalInstruction { address, word } => {
+                write!(f, "address {address}: illegal instruction {word:#018b}")
+            }
+        }
+    }
+}
+
+/// Turns machine words back into Hack assembly.
+///
+/// Every `@n` that feeds a jumping C-instruction is treated as a jump target:
+/// the address gets a synthesized `(Ln)` label and the A-instruction is
+/// rewritten to `@Ln`, so the output reassembles to the same words.
+pub fn disassemble(program: &[u16]) -> Result<String, DisassemblerError> {
+    let instructions = program
+        .iter()
+        .enumerate()
+        .map(|(address, word)| {
+            CpuInstructions::try_from(*word).map_err(|_| DisassemblerError::IllegalInstruction {
+                address: address as u16,
+                word: *word,
+            })
+        })
+        .collect::<Result<Vec<CpuInstructions>, DisassemblerError>>()?;
+    let targets = jump_targets(&instructions);
+
+    let mut out = String::new();
+    for (address, instruction) in instructions.iter().enumerate() {
+        if targets.contains(&(address as u16)) {
+            writeln!(out, "(L{address})").unwrap();
+        }
+        match instruction {
+            CpuInstructions::Ainstruction(value) if targets.contains(value) => {
+                writeln!(out, "    @L{value}").unwrap()
+            }
+            instruction => writeln!(out, "    {instruction}").unwrap(),
+        }
+    }
+
+    let end = instructions.len() as u16;
+    if targets.contains(&end) {
+        writeln!(out, "(L{end})").unwrap();
+    }
+
+    Ok(out)
+}
+
+/// Disassembles the contents of `rom`, ignoring the zeroed words after the
+/// last loaded instruction.
+pub fn disassemble_rom(rom: &Rom) -> Result<String, DisassemblerError> {
+    disassemble(rom.program())
+}
+
+fn jump_targets(instructions: &[CpuInstructions]) -> BTreeSet<u16> {
+    instructions
+        .windows(2)
+        .filter_map(|pair| match pair {
+            [CpuInstructions::Ainstruction(target), CpuInstructions::CInstruction { jump, .. }]
+                if *jump != Jump::Null && *target as usize <= instructions.len() =>
+            {
+                Some(*target)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::{assemble, AssemblerError};
+
+    #[test]
+    fn test_disassemble_max() -> Result<(), DisassemblerError> {
+        let max_program: Vec<u16> = vec![
+            0b0000000000000000,
+            0b1111110000010000,
+            0b0000000000000001,
+            0b1111010011010000,
+            0b0000000000001010,
+            0b1110001100000001,
+            0b0000000000000001,
+            0b1111110000010000,
+            0b0000000000001100,
+            0b1110101010000111,
+            0b0000000000000000,
+            0b1111110000010000,
+            0b0000000000000010,
+            0b1110001100001000,
+            0b0000000000001110,
+            0b1110101010000111,
+        ];
+
+        let expected = [
+            "    @0",
+            "    D=M",
+            "    @1",
+            "    D=D-M",
+            "    @L10",
+            "    D;JGT",
+            "    @1",
+            "    D=M",
+            "    @L12",
+            "    0;JMP",
+            "(L10)",
+            "    @0",
+            "    D=M",
+            "(L12)",
+            "    @2",
+            "    M=D",
+            "(L14)",
+            "    @L14",
+            "    0;JMP",
+            "",
+        ]
+        .join("\n");
+
+        assert_eq!(disassemble(&max_program)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), AssemblerError> {
+        let program = assemble(
+            "
+            @i
+            M=1
+            (LOOP)
+            @i
+            D=M
+            @100
+            D=D-A
+            @END
+            D;JGT
+            @i
+            AMD=M+1
+            @LOOP
+            0;JMP
+            (END)
+            ",
+        )?;
+
+        assert_eq!(assemble(&disassemble(&program).unwrap())?, program);
+        Ok(())
+    }
+
+    #[test]
+    fn test_disassemble_rom() {
+        let mut rom = Rom::new();
+        rom.load(0b0000000000000111, 0).unwrap();
+        rom.load(0b1110110000010000, 1).unwrap();
+
+        assert_eq!(disassemble_rom(&rom), Ok("    @7\n    D=A\n".to_string()));
+    }
+
+    #[test]
+    fn test_illegal_instruction() {
+        assert_eq!(
+            disassemble(&[0b0000000000000111, 0b1111111111111111]),
+            Err(DisassemblerError::IllegalInstruction {
+                address: 1,
+                word: 0b1111111111111111
+            })
+        );
+        assert_eq!(
+            disassemble(&[0b1111111111111111]).unwrap_err().to_string(),
+            "address 0: illegal instruction 0b1111111111111111"
+        );
+    }
+}
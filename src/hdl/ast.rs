@@ -0,0 +1,92 @@
+use super::Span;
+
+/// A name as written in the source, e.g. a chip, pin or part name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+/// One `.hdl` chip definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chip {
+    pub name: Ident,
+    pub inputs: Vec<PinDecl>,
+    pub outputs: Vec<PinDecl>,
+    pub body: Body,
+    /// Pins listed by `CLOCKED`, whose effect waits for the clock.
+    pub clocked: Vec<Ident>,
+    pub span: Span,
+}
+
+/// A pin in `IN` or `OUT`, `a` or `a[16]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinDecl {
+    pub name: Ident,
+    pub width: usize,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Body {
+    /// `PARTS:` followed by the parts wiring the chip together.
+    Parts(Vec<Part>),
+    /// `BUILTIN Name;`, implemented by the simulator.
+    Builtin(Ident),
+}
+
+/// `Chip(a=x, b=y, out=z);` inside `PARTS:`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub chip: Ident,
+    pub connections: Vec<Connection>,
+    pub span: Span,
+}
+
+/// `pin=value`, the part's pin on the left and the signal it is wired to on
+/// the right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Connection {
+    pub pin: PinRef,
+    pub value: Value,
+    pub span: Span,
+}
+
+/// A pin, or some of its bits: `a`, `a[3]` or `a[0..7]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinRef {
+    pub name: Ident,
+    pub bits: Option<BitRange>,
+    pub span: Span,
+}
+
+/// Bits `start` to `end` inclusive, least significant first; `a[3]` is
+/// `3..3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitRange {
+    pub start: usize,
+    pub end: usize,
+    pub span: Span,
+}
+
+impl BitRange {
+    pub fn width(&self) -> usize {
+        self.end - self.start + 1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Pin(PinRef),
+    /// `true` or `false`, filling every bit it is connected to.
+    Constant(bool, Span),
+}
+
+impl Value {
+    pub fn span(&self) -> Span {
+        match self {
+            Value::Pin(pin) => pin.span,
+            Value::Constant(_, span) => *span,
+        }
+    }
+}
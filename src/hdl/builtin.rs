@@ -0,0 +1,384 @@
+use crate::computer::chip::arithmetic::{add_16, alu_gates, full_adder, half_adder, inc_16};
+use crate::computer::chip::cpu::gate_cpu::GateCpu;
+use crate::computer::chip::sequential::{
+    self, Dff, Pc, Ram16K, Ram4K, Ram512, Ram64, Ram8, Register,
+};
+use crate::computer::gate::{
+    and, and_16, dmux, dmux_4_way, dmux_8_way, from_bits, mux, mux_16, mux_4_way_16, mux_8_way_16,
+    nand, not, not_16, or, or_16, or_8_way, to_bits, xor,
+};
+
+/// A chip the simulator runs in Rust rather than from an `.hdl` file. Pin
+/// values are passed as words, bit `i` of a word being bit `i` of the bus.
+pub struct Builtin {
+    pub name: &'static str,
+    pub inputs: &'static [(&'static str, usize)],
+    pub outputs: &'static [(&'static str, usize)],
+    /// Inputs that only matter on the clock, so no combinational path runs
+    /// from them to an output.
+    pub clocked: &'static [&'static str],
+    pub kind: Kind,
+}
+
+pub enum Kind {
+    Combinational(fn(&[u16]) -> Vec<u16>),
+    Sequential(fn() -> Sequential),
+}
+
+/// The state of a clocked builtin, one of the chips of `chip::sequential`.
+#[derive(Clone)]
+pub enum Sequential {
+    Dff(Dff),
+    Bit(sequential::Bit),
+    Register(Register),
+    Ram8(Box<Ram8>),
+    Ram64(Ram64),
+    Ram512(Ram512),
+    Ram4K(Ram4K),
+    Ram16K(Ram16K),
+    Pc(Pc),
+    Cpu(GateCpu),
+}
+
+impl Sequential {
+    /// The outputs given the inputs, of which memories only read `address`
+    /// and the CPU reads `inM` and `instruction`.
+    pub fn out(&self, inputs: &[u16]) -> Vec<u16> {
+        let out = match self {
+            Sequential::Dff(dff) => dff.out() as u16,
+            Sequential::Bit(bit) => bit.out() as u16,
+            Sequential::Register(register) => from_bits(register.out()),
+            Sequential::Ram8(ram) => from_bits(ram.out(to_bits(inputs[2]))),
+            Sequential::Ram64(ram) => from_bits(ram.out(to_bits(inputs[2]))),
+            Sequential::Ram512(ram) => from_bits(ram.out(to_bits(inputs[2]))),
+            Sequential::Ram4K(ram) => from_bits(ram.out(to_bits(inputs[2]))),
+            Sequential::Ram16K(ram) => from_bits(ram.out(to_bits(inputs[2]))),
+            Sequential::Pc(pc) => from_bits(pc.out()),
+            Sequential::Cpu(cpu) => {
+                let out = cpu.outputs(inputs[1], inputs[0]);
+                return vec![
+                    out.out_m,
+                    out.write_m as u16,
+                    out.address_m & 0x7FFF,
+                    out.pc & 0x7FFF,
+                ];
+            }
+        };
+        vec![out]
+    }
+
+    pub fn tick(&mut self, inputs: &[u16]) {
+        let input = to_bits::<16>(inputs[0]);
+        let flag = |idx: usize| bit(inputs[idx]);
+        match self {
+            Sequential::Dff(dff) => dff.tick(flag(0)),
+            Sequential::Bit(bit) => bit.tick(flag(0), flag(1)),
+            Sequential::Register(register) => register.tick(input, flag(1)),
+            Sequential::Ram8(ram) => ram.tick(input, flag(1), to_bits(inputs[2])),
+            Sequential::Ram64(ram) => ram.tick(input, flag(1), to_bits(inputs[2])),
+            Sequential::Ram512(ram) => ram.tick(input, flag(1), to_bits(inputs[2])),
+            Sequential::Ram4K(ram) => ram.tick(input, flag(1), to_bits(inputs[2])),
+            Sequential::Ram16K(ram) => ram.tick(input, flag(1), to_bits(inputs[2])),
+            Sequential::Pc(pc) => pc.tick(input, flag(1), flag(2), flag(3)),
+            Sequential::Cpu(cpu) => cpu.tick(inputs[1], inputs[0], flag(2)),
+        }
+    }
+
+    pub fn tock(&mut self) {
+        match self {
+            Sequential::Dff(dff) => dff.tock(),
+            Sequential::Bit(bit) => bit.tock(),
+            Sequential::Register(register) => register.tock(),
+            Sequential::Ram8(ram) => ram.tock(),
+            Sequential::Ram64(ram) => ram.tock(),
+            Sequential::Ram512(ram) => ram.tock(),
+            Sequential::Ram4K(ram) => ram.tock(),
+            Sequential::Ram16K(ram) => ram.tock(),
+            Sequential::Pc(pc) => pc.tock(),
+            Sequential::Cpu(cpu) => cpu.tock(),
+        }
+    }
+}
+
+fn bit(word: u16) -> bool {
+    word & 1 == 1
+}
+
+fn bus(word: u16) -> [bool; 16] {
+    to_bits(word)
+}
+
+fn words(bits: &[bool]) -> Vec<u16> {
+    bits.iter().map(|bit| *bit as u16).collect()
+}
+
+const A_B: &[(&str, usize)] = &[("a", 1), ("b", 1)];
+const A_B_16: &[(&str, usize)] = &[("a", 16), ("b", 16)];
+const OUT: &[(&str, usize)] = &[("out", 1)];
+const OUT_16: &[(&str, usize)] = &[("out", 16)];
+const IN_16: &[(&str, usize)] = &[("in", 16)];
+const SUM_CARRY: &[(&str, usize)] = &[("sum", 1), ("carry", 1)];
+const MEMORY_OUT: &[&str] = &["in", "load"];
+
+macro_rules! ram {
+    ($name:literal, $chip:ident, $bits:literal, $new:expr) => {
+        Builtin {
+            name: $name,
+            inputs: &[("in", 16), ("load", 1), ("address", $bits)],
+            outputs: OUT_16,
+            clocked: MEMORY_OUT,
+            kind: Kind::Sequential(|| Sequential::$chip($new)),
+        }
+    };
+}
+
+/// The builtin chips, under their course names: the gate library, the
+/// arithmetic chips, the sequential chips and the CPU.
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "Nand",
+        inputs: A_B,
+        outputs: OUT,
+        clocked: &[],
+        kind: Kind::Combinational(|i| words(&[nand(bit(i[0]), bit(i[1]))])),
+    },
+    Builtin {
+        name: "Not",
+        inputs: &[("in", 1)],
+        outputs: OUT,
+        clocked: &[],
+        kind: Kind::Combinational(|i| words(&[not(bit(i[0]))])),
+    },
+    Builtin {
+        name: "And",
+        inputs: A_B,
+        outputs: OUT,
+        clocked: &[],
+        kind: Kind::Combinational(|i| words(&[and(bit(i[0]), bit(i[1]))])),
+    },
+    Builtin {
+        name: "Or",
+        inputs: A_B,
+        outputs: OUT,
+        clocked: &[],
+        kind: Kind::Combinational(|i| words(&[or(bit(i[0]), bit(i[1]))])),
+    },
+    Builtin {
+        name: "Xor",
+        inputs: A_B,
+        outputs: OUT,
+        clocked: &[],
+        kind: Kind::Combinational(|i| words(&[xor(bit(i[0]), bit(i[1]))])),
+    },
+    Builtin {
+        name: "Mux",
+        inputs: &[("a", 1), ("b", 1), ("sel", 1)],
+        outputs: OUT,
+        clocked: &[],
+        kind: Kind::Combinational(|i| words(&[mux(bit(i[0]), bit(i[1]), bit(i[2]))])),
+    },
+    Builtin {
+        name: "DMux",
+        inputs: &[("in", 1), ("sel", 1)],
+        outputs: &[("a", 1), ("b", 1)],
+        clocked: &[],
+        kind: Kind::Combinational(|i| words(&dmux(bit(i[0]), bit(i[1])))),
+    },
+    Builtin {
+        name: "Not16",
+        inputs: IN_16,
+        outputs: OUT_16,
+        clocked: &[],
+        kind: Kind::Combinational(|i| vec![from_bits(not_16(bus(i[0])))]),
+    },
+    Builtin {
+        name: "And16",
+        inputs: A_B_16,
+        outputs: OUT_16,
+        clocked: &[],
+        kind: Kind::Combinational(|i| vec![from_bits(and_16(bus(i[0]), bus(i[1])))]),
+    },
+    Builtin {
+        name: "Or16",
+        inputs: A_B_16,
+        outputs: OUT_16,
+        clocked: &[],
+        kind: Kind::Combinational(|i| vec![from_bits(or_16(bus(i[0]), bus(i[1])))]),
+    },
+    Builtin {
+        name: "Mux16",
+        inputs: &[("a", 16), ("b", 16), ("sel", 1)],
+        outputs: OUT_16,
+        clocked: &[],
+        kind: Kind::Combinational(|i| vec![from_bits(mux_16(bus(i[0]), bus(i[1]), bit(i[2])))]),
+    },
+    Builtin {
+        name: "Or8Way",
+        inputs: &[("in", 8)],
+        outputs: OUT,
+        clocked: &[],
+        kind: Kind::Combinational(|i| words(&[or_8_way(to_bits(i[0]))])),
+    },
+    Builtin {
+        name: "Mux4Way16",
+        inputs: &[("a", 16), ("b", 16), ("c", 16), ("d", 16), ("sel", 2)],
+        outputs: OUT_16,
+        clocked: &[],
+        kind: Kind::Combinational(|i| {
+            let out = mux_4_way_16(bus(i[0]), bus(i[1]), bus(i[2]), bus(i[3]), to_bits(i[4]));
+            vec![from_bits(out)]
+        }),
+    },
+    Builtin {
+        name: "Mux8Way16",
+        inputs: &[
+            ("a", 16),
+            ("b", 16),
+            ("c", 16),
+            ("d", 16),
+            ("e", 16),
+            ("f", 16),
+            ("g", 16),
+            ("h", 16),
+            ("sel", 3),
+        ],
+        outputs: OUT_16,
+        clocked: &[],
+        kind: Kind::Combinational(|i| {
+            let low = (bus(i[0]), bus(i[1]), bus(i[2]), bus(i[3]));
+            let high = (bus(i[4]), bus(i[5]), bus(i[6]), bus(i[7]));
+            vec![from_bits(mux_8_way_16(low, high, to_bits(i[8])))]
+        }),
+    },
+    Builtin {
+        name: "DMux4Way",
+        inputs: &[("in", 1), ("sel", 2)],
+        outputs: &[("a", 1), ("b", 1), ("c", 1), ("d", 1)],
+        clocked: &[],
+        kind: Kind::Combinational(|i| words(&dmux_4_way(bit(i[0]), to_bits(i[1])))),
+    },
+    Builtin {
+        name: "DMux8Way",
+        inputs: &[("in", 1), ("sel", 3)],
+        outputs: &[
+            ("a", 1),
+            ("b", 1),
+            ("c", 1),
+            ("d", 1),
+            ("e", 1),
+            ("f", 1),
+            ("g", 1),
+            ("h", 1),
+        ],
+        clocked: &[],
+        kind: Kind::Combinational(|i| words(&dmux_8_way(bit(i[0]), to_bits(i[1])))),
+    },
+    Builtin {
+        name: "HalfAdder",
+        inputs: A_B,
+        outputs: SUM_CARRY,
+        clocked: &[],
+        kind: Kind::Combinational(|i| {
+            let (sum, carry) = half_adder(bit(i[0]), bit(i[1]));
+            words(&[sum, carry])
+        }),
+    },
+    Builtin {
+        name: "FullAdder",
+        inputs: &[("a", 1), ("b", 1), ("c", 1)],
+        outputs: SUM_CARRY,
+        clocked: &[],
+        kind: Kind::Combinational(|i| {
+            let (sum, carry) = full_adder(bit(i[0]), bit(i[1]), bit(i[2]));
+            words(&[sum, carry])
+        }),
+    },
+    Builtin {
+        name: "Add16",
+        inputs: A_B_16,
+        outputs: OUT_16,
+        clocked: &[],
+        kind: Kind::Combinational(|i| vec![from_bits(add_16(bus(i[0]), bus(i[1])))]),
+    },
+    Builtin {
+        name: "Inc16",
+        inputs: IN_16,
+        outputs: OUT_16,
+        clocked: &[],
+        kind: Kind::Combinational(|i| vec![from_bits(inc_16(bus(i[0])))]),
+    },
+    Builtin {
+        name: "ALU",
+        inputs: &[
+            ("x", 16),
+            ("y", 16),
+            ("zx", 1),
+            ("nx", 1),
+            ("zy", 1),
+            ("ny", 1),
+            ("f", 1),
+            ("no", 1),
+        ],
+        outputs: &[("out", 16), ("zr", 1), ("ng", 1)],
+        clocked: &[],
+        kind: Kind::Combinational(|i| {
+            let control = [i[7], i[6], i[5], i[4], i[3], i[2]].map(bit);
+            let (out, zr, ng) = alu_gates(bus(i[0]), bus(i[1]), control);
+            vec![from_bits(out), zr as u16, ng as u16]
+        }),
+    },
+    Builtin {
+        name: "DFF",
+        inputs: &[("in", 1)],
+        outputs: OUT,
+        clocked: &["in"],
+        kind: Kind::Sequential(|| Sequential::Dff(Dff::new())),
+    },
+    Builtin {
+        name: "Bit",
+        inputs: &[("in", 1), ("load", 1)],
+        outputs: OUT,
+        clocked: &["in", "load"],
+        kind: Kind::Sequential(|| Sequential::Bit(sequential::Bit::new())),
+    },
+    Builtin {
+        name: "Register",
+        inputs: &[("in", 16), ("load", 1)],
+        outputs: OUT_16,
+        clocked: &["in", "load"],
+        kind: Kind::Sequential(|| Sequential::Register(Register::new())),
+    },
+    ram!("RAM8", Ram8, 3, Box::default()),
+    ram!("RAM64", Ram64, 6, Ram64::new()),
+    ram!("RAM512", Ram512, 9, Ram512::new()),
+    ram!("RAM4K", Ram4K, 12, Ram4K::new()),
+    ram!("RAM16K", Ram16K, 14, Ram16K::new()),
+    Builtin {
+        name: "PC",
+        inputs: &[("in", 16), ("load", 1), ("inc", 1), ("reset", 1)],
+        outputs: OUT_16,
+        clocked: &["in", "load", "inc", "reset"],
+        kind: Kind::Sequential(|| Sequential::Pc(Pc::new())),
+    },
+    Builtin {
+        name: "CPU",
+        inputs: &[("inM", 16), ("instruction", 16), ("reset", 1)],
+        outputs: &[("outM", 16), ("writeM", 1), ("addressM", 15), ("pc", 15)],
+        clocked: &["reset"],
+        kind: Kind::Sequential(|| Sequential::Cpu(GateCpu::new())),
+    },
+];
+
+/// Copies one bit; the simulator adds these where one part output drives
+/// two of the chip's output pins.
+pub const BUFFER: Builtin = Builtin {
+    name: "Buffer",
+    inputs: &[("in", 1)],
+    outputs: OUT,
+    clocked: &[],
+    kind: Kind::Combinational(|i| vec![i[0]]),
+};
+
+pub fn builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
@@ -0,0 +1,179 @@
+use std::fmt;
+
+use super::{ParseError, Span};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Ident(String),
+    Number(String),
+    /// One of `{ } ( ) [ ] , ; : = ..`.
+    Symbol(&'static str),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) | Token::Number(name) => write!(f, "`{name}`"),
+            Token::Symbol(symbol) => write!(f, "`{symbol}`"),
+            Token::End => write!(f, "end of file"),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 10] = ["..", "{", "}", "(", ")", "[", "]", ",", ";", ":"];
+
+/// Splits `source` into tokens, skipping whitespace and `//`, `/* */` and
+/// `/** */` comments. The last token is always `End`.
+pub fn tokenize(source: &str) -> Result<Vec<(Token, Span)>, ParseError> {
+    let mut lexer = Lexer {
+        source,
+        pos: 0,
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+
+    loop {
+        lexer.skip_trivia()?;
+        let start = lexer.here();
+        let rest = &source[lexer.pos..];
+
+        let Some(c) = rest.chars().next() else {
+            tokens.push((Token::End, start));
+            return Ok(tokens);
+        };
+
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            let text = lexer.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            Token::Ident(text.to_string())
+        } else if c.is_ascii_digit() {
+            Token::Number(lexer.take_while(|c| c.is_ascii_digit()).to_string())
+        } else if c == '=' {
+            lexer.advance(1);
+            Token::Symbol("=")
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            lexer.advance(symbol.len());
+            Token::Symbol(symbol)
+        } else {
+            return Err(ParseError::UnexpectedCharacter {
+                span: lexer.span_from(start, c.len_utf8()),
+                found: c,
+            });
+        };
+        tokens.push((token, lexer.span_to(start)));
+    }
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn here(&self) -> Span {
+        Span {
+            start: self.pos,
+            end: self.pos,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn span_to(&self, start: Span) -> Span {
+        Span {
+            end: self.pos,
+            ..start
+        }
+    }
+
+    fn span_from(&self, start: Span, len: usize) -> Span {
+        Span {
+            end: start.start + len,
+            ..start
+        }
+    }
+
+    fn advance(&mut self, len: usize) {
+        for c in self.source[self.pos..self.pos + len].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.pos += len;
+    }
+
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> &'a str {
+        let rest = &self.source[self.pos..];
+        let len = rest.find(|c| !accept(c)).unwrap_or(rest.len());
+        self.advance(len);
+        &rest[..len]
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), ParseError> {
+        loop {
+            let rest = &self.source[self.pos..];
+            if rest.starts_with("//") {
+                self.take_while(|c| c != '\n');
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                let start = self.here();
+                match comment.find("*/") {
+                    Some(len) => self.advance(len + 4),
+                    None => {
+                        return Err(ParseError::UnterminatedComment {
+                            span: self.span_from(start, 2),
+                        })
+                    }
+                }
+            } else if rest.starts_with(char::is_whitespace) {
+                self.take_while(char::is_whitespace);
+            } else {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let source = "/** Or */\nOr(a=x[0..7], // low\n b=true);";
+        let tokens: Vec<(Token, usize, usize)> = tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|(token, span)| (token, span.line, span.column))
+            .collect();
+
+        let ident = |name: &str| Token::Ident(name.to_string());
+        assert_eq!(
+            tokens,
+            [
+                (ident("Or"), 2, 1),
+                (Token::Symbol("("), 2, 3),
+                (ident("a"), 2, 4),
+                (Token::Symbol("="), 2, 5),
+                (ident("x"), 2, 6),
+                (Token::Symbol("["), 2, 7),
+                (Token::Number("0".to_string()), 2, 8),
+                (Token::Symbol(".."), 2, 9),
+                (Token::Number("7".to_string()), 2, 11),
+                (Token::Symbol("]"), 2, 12),
+                (Token::Symbol(","), 2, 13),
+                (ident("b"), 3, 2),
+                (Token::Symbol("="), 3, 3),
+                (ident("true"), 3, 4),
+                (Token::Symbol(")"), 3, 8),
+                (Token::Symbol(";"), 3, 9),
+                (Token::End, 3, 10),
+            ]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt;
+
+pub use self::parser::parse;
+pub use self::simulator::{Chip, ChipError, Library};
+
+pub mod ast;
+mod builtin;
+mod lexer;
+mod parser;
+mod simulator;
+
+/// Where something is in the source: byte offsets `start..end`, and the line
+/// and column of `start`, both counted from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// From the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    UnexpectedCharacter {
+        span: Span,
+        found: char,
+    },
+    UnterminatedComment {
+        span: Span,
+    },
+    Expected {
+        span: Span,
+        expected: &'static str,
+        found: String,
+    },
+    /// A bus width or bit index that is not a usable number.
+    InvalidNumber {
+        span: Span,
+        number: String,
+    },
+    /// A sub-bus whose first bit comes after its last, e.g. `a[7..0]`.
+    InvalidRange {
+        span: Span,
+        start: usize,
+        end: usize,
+    },
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedCharacter { span, .. }
+            | ParseError::UnterminatedComment { span }
+            | ParseError::Expected { span, .. }
+            | ParseError::InvalidNumber { span, .. }
+            | ParseError::InvalidRange { span, .. } => *span,
+        }
+    }
+
+    /// The message followed by the offending source line with the span
+    /// underlined, e.g.
+    ///
+    /// ```text
+    /// 3:18: expected `;`, found `}`
+    ///     OUT out }
+    ///             ^
+    /// ```
+    pub fn annotate(&self, source: &str) -> String {
+        let span = self.span();
+        let text = source.lines().nth(span.line - 1).unwrap_or("");
+        let start = span.column - 1;
+        let width = text
+            .chars()
+            .skip(start)
+            .take(span.end - span.start)
+            .count()
+            .max(1);
+        format!(
+            "{self}\n    {text}\n    {}{}",
+            " ".repeat(start),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let span = self.span();
+        write!(f, "{}:{}: ", span.line, span.column)?;
+        match self {
+            ParseError::UnexpectedCharacter { found, .. } => {
+                write!(f, "unexpected character `{found}`")
+            }
+            ParseError::UnterminatedComment { .. } => write!(f, "unterminated comment"),
+            ParseError::Expected {
+                expected, found, ..
+            } => write!(f, "expected {expected}, found {found}"),
+            ParseError::InvalidNumber { number, .. } => write!(f, "invalid number {number}"),
+            ParseError::InvalidRange { start, end, .. } => {
+                write!(f, "invalid bit range {start}..{end}")
+            }
+        }
+    }
+}
@@ -0,0 +1,458 @@
+use super::ast::{BitRange, Body, Chip, Connection, Ident, Part, PinDecl, PinRef, Value};
+use super::lexer::{tokenize, Token};
+use super::{ParseError, Span};
+
+/// Parses the single chip definition in an `.hdl` file:
+///
+/// ```text
+/// CHIP Name {
+///     IN a, b[16];
+///     OUT out[16];
+///     PARTS:
+///     Part(pin=signal, pin[0..7]=signal[8..15], pin=true);
+/// }
+/// ```
+///
+/// `PARTS:` may instead be `BUILTIN Name;`, and either may be followed by
+/// `CLOCKED pins;`.
+pub fn parse(source: &str) -> Result<Chip, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+    };
+    let chip = parser.chip()?;
+    parser.expect_end()?;
+    Ok(chip)
+}
+
+struct Parser {
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn span(&self) -> Span {
+        self.tokens[self.pos].1
+    }
+
+    /// The span of the token just consumed.
+    fn last(&self) -> Span {
+        self.tokens[self.pos - 1].1
+    }
+
+    fn next(&mut self) -> (Token, Span) {
+        let token = self.tokens[self.pos].clone();
+        if token.0 != Token::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, expected: &'static str) -> ParseError {
+        ParseError::Expected {
+            span: self.span(),
+            expected,
+            found: self.peek().to_string(),
+        }
+    }
+
+    fn at_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Token::Symbol(found) if *found == symbol)
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(found) if found == keyword)
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = self.at_symbol(symbol);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    fn symbol(&mut self, symbol: &str, expected: &'static str) -> Result<Span, ParseError> {
+        match self.eat_symbol(symbol) {
+            true => Ok(self.last()),
+            false => Err(self.error(expected)),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, expected: &'static str) -> Result<Span, ParseError> {
+        match self.at_keyword(keyword) {
+            true => Ok(self.next().1),
+            false => Err(self.error(expected)),
+        }
+    }
+
+    fn ident(&mut self, expected: &'static str) -> Result<Ident, ParseError> {
+        match self.peek() {
+            Token::Ident(name) => {
+                let name = name.clone();
+                let span = self.next().1;
+                Ok(Ident { name, span })
+            }
+            _ => Err(self.error(expected)),
+        }
+    }
+
+    fn number(&mut self, expected: &'static str) -> Result<(usize, Span), ParseError> {
+        match self.peek() {
+            Token::Number(number) => {
+                let number = number.clone();
+                let span = self.next().1;
+                number
+                    .parse()
+                    .map(|value| (value, span))
+                    .map_err(|_| ParseError::InvalidNumber { span, number })
+            }
+            _ => Err(self.error(expected)),
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<(), ParseError> {
+        match self.peek() {
+            Token::End => Ok(()),
+            _ => Err(self.error("end of file")),
+        }
+    }
+
+    fn chip(&mut self) -> Result<Chip, ParseError> {
+        let start = self.keyword("CHIP", "`CHIP`")?;
+        let name = self.ident("a chip name")?;
+        self.symbol("{", "`{`")?;
+
+        let inputs = match self.at_keyword("IN") {
+            true => self.pin_decls()?,
+            false => Vec::new(),
+        };
+        let outputs = match self.at_keyword("OUT") {
+            true => self.pin_decls()?,
+            false => Vec::new(),
+        };
+
+        let body = if self.at_keyword("BUILTIN") {
+            self.next();
+            let builtin = self.ident("a builtin chip name")?;
+            self.symbol(";", "`;`")?;
+            Body::Builtin(builtin)
+        } else {
+            self.keyword("PARTS", "`PARTS:` or `BUILTIN`")?;
+            self.symbol(":", "`:`")?;
+            let mut parts = Vec::new();
+            while matches!(self.peek(), Token::Ident(name) if name != "CLOCKED") {
+                parts.push(self.part()?);
+            }
+            Body::Parts(parts)
+        };
+
+        let mut clocked = Vec::new();
+        if self.at_keyword("CLOCKED") {
+            self.next();
+            loop {
+                clocked.push(self.ident("a pin name")?);
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+            self.symbol(";", "`,` or `;`")?;
+        }
+
+        let end = self.symbol("}", "`}`")?;
+        Ok(Chip {
+            name,
+            inputs,
+            outputs,
+            body,
+            clocked,
+            span: start.to(end),
+        })
+    }
+
+    /// `IN a, b[16];` or the same after `OUT`, starting at the keyword.
+    fn pin_decls(&mut self) -> Result<Vec<PinDecl>, ParseError> {
+        self.next();
+        let mut pins = Vec::new();
+        loop {
+            let name = self.ident("a pin name")?;
+            let mut width = 1;
+            if self.eat_symbol("[") {
+                let (number, span) = self.number("a bus width")?;
+                if number == 0 {
+                    return Err(ParseError::InvalidNumber {
+                        span,
+                        number: number.to_string(),
+                    });
+                }
+                width = number;
+                self.symbol("]", "`]`")?;
+            }
+            pins.push(PinDecl {
+                span: name.span.to(self.last()),
+                name,
+                width,
+            });
+
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+        self.symbol(";", "`,` or `;`")?;
+        Ok(pins)
+    }
+
+    fn part(&mut self) -> Result<Part, ParseError> {
+        let chip = self.ident("a part name")?;
+        self.symbol("(", "`(`")?;
+
+        let mut connections = Vec::new();
+        loop {
+            let pin = self.pin_ref("a pin name")?;
+            self.symbol("=", "`=`")?;
+            let value = match self.peek() {
+                Token::Ident(name) if name == "true" || name == "false" => {
+                    let value = name == "true";
+                    Value::Constant(value, self.next().1)
+                }
+                _ => Value::Pin(self.pin_ref("a pin name, `true` or `false`")?),
+            };
+            connections.push(Connection {
+                span: pin.span.to(value.span()),
+                pin,
+                value,
+            });
+
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+
+        self.symbol(")", "`,` or `)`")?;
+        let end = self.symbol(";", "`;`")?;
+        Ok(Part {
+            span: chip.span.to(end),
+            chip,
+            connections,
+        })
+    }
+
+    /// `a`, `a[3]` or `a[0..7]`.
+    fn pin_ref(&mut self, expected: &'static str) -> Result<PinRef, ParseError> {
+        let name = self.ident(expected)?;
+        let mut bits = None;
+
+        if self.at_symbol("[") {
+            let open = self.next().1;
+            let (start, _) = self.number("a bit index")?;
+            let end = match self.eat_symbol("..") {
+                true => self.number("a bit index")?.0,
+                false => start,
+            };
+            let close = self.symbol("]", "`..` or `]`")?;
+            let span = open.to(close);
+            if start > end {
+                return Err(ParseError::InvalidRange { span, start, end });
+            }
+            bits = Some(BitRange { start, end, span });
+        }
+
+        Ok(PinRef {
+            span: name.span.to(self.last()),
+            name,
+            bits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(pins: &[PinDecl]) -> Vec<(&str, usize)> {
+        pins.iter()
+            .map(|pin| (pin.name.name.as_str(), pin.width))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_parts() {
+        let source = "
+/** Exclusive or. */
+CHIP Xor {
+    IN a, b;
+    OUT out;
+
+    PARTS:
+    Not(in=a, out=nota);
+    Not(in=b, out=notb);
+    And(a=a, b=notb, out=w1);
+    And(a=nota, b=b, out=w2);
+    Or(a=w1, b=w2, out=out);
+}
+";
+        let chip = parse(source).unwrap();
+
+        assert_eq!(chip.name.name, "Xor");
+        assert_eq!(names(&chip.inputs), [("a", 1), ("b", 1)]);
+        assert_eq!(names(&chip.outputs), [("out", 1)]);
+        assert!(chip.clocked.is_empty());
+        assert_eq!((chip.span.line, chip.span.column), (3, 1));
+        assert_eq!(&source[chip.span.start..chip.span.end], source[22..].trim());
+
+        let Body::Parts(parts) = &chip.body else {
+            panic!("expected parts");
+        };
+        let chips: Vec<&str> = parts.iter().map(|part| part.chip.name.as_str()).collect();
+        assert_eq!(chips, ["Not", "Not", "And", "And", "Or"]);
+        let last = &parts[4];
+        assert_eq!(
+            &source[last.span.start..last.span.end],
+            "Or(a=w1, b=w2, out=out);"
+        );
+        let Value::Pin(value) = &last.connections[2].value else {
+            panic!("expected a pin");
+        };
+        assert_eq!(last.connections[2].pin.name.name, "out");
+        assert_eq!(value.name.name, "out");
+    }
+
+    #[test]
+    fn test_parse_buses() {
+        let chip = parse(
+            "CHIP Split {
+                IN in[16];
+                OUT low[8], high[8], zero, first;
+                PARTS:
+                Or8Way(in=in[0..7], out=low0);
+                Mux16(a[0..7]=in[8..15], b=false, sel=true, out[3]=first);
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(names(&chip.inputs), [("in", 16)]);
+        assert_eq!(
+            names(&chip.outputs),
+            [("low", 8), ("high", 8), ("zero", 1), ("first", 1)]
+        );
+
+        let Body::Parts(parts) = &chip.body else {
+            panic!("expected parts");
+        };
+        let connections: Vec<_> = parts[1]
+            .connections
+            .iter()
+            .map(|connection| {
+                let bits = |pin: &PinRef| pin.bits.map(|bits| (bits.start, bits.end));
+                let value = match &connection.value {
+                    Value::Pin(pin) => format!("{}{:?}", pin.name.name, bits(pin)),
+                    Value::Constant(value, _) => value.to_string(),
+                };
+                (
+                    connection.pin.name.name.as_str(),
+                    bits(&connection.pin),
+                    value,
+                )
+            })
+            .collect();
+        assert_eq!(
+            connections,
+            [
+                ("a", Some((0, 7)), "inSome((8, 15))".to_string()),
+                ("b", None, "false".to_string()),
+                ("sel", None, "true".to_string()),
+                ("out", Some((3, 3)), "firstNone".to_string()),
+            ]
+        );
+        assert_eq!(parts[1].connections[0].pin.bits.unwrap().width(), 8);
+    }
+
+    #[test]
+    fn test_parse_builtin() {
+        let chip = parse(
+            "// Data flip-flop.
+            CHIP DFF {
+                IN in;
+                OUT out;
+                BUILTIN DFF;
+                CLOCKED in;
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(
+            chip.body,
+            Body::Builtin(Ident {
+                name: "DFF".to_string(),
+                span: Span {
+                    start: 114,
+                    end: 117,
+                    line: 5,
+                    column: 25,
+                },
+            })
+        );
+        let clocked: Vec<&str> = chip.clocked.iter().map(|pin| pin.name.as_str()).collect();
+        assert_eq!(clocked, ["in"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        struct Test {
+            source: &'static str,
+            expected: &'static str,
+        }
+        let tests = vec![
+            Test {
+                source: "CHIP Not {\n    IN in;\n    OUT out\n    PARTS:\n}",
+                expected: "4:5: expected `,` or `;`, found `PARTS`\n        PARTS:\n        ^^^^^",
+            },
+            Test {
+                source: "CHIP And { IN a, b; OUT out; PARTS: Nand(a=a, b=b out=x); }",
+                expected: "1:51: expected `,` or `)`, found `out`",
+            },
+            Test {
+                source: "CHIP And { IN a[0]; }",
+                expected: "1:17: invalid number 0",
+            },
+            Test {
+                source: "CHIP A { PARTS: B(a=x[7..0]); }",
+                expected: "1:22: invalid bit range 7..0",
+            },
+            Test {
+                source: "CHIP A { PARTS: B(a=x); } /* open",
+                expected: "1:27: unterminated comment",
+            },
+            Test {
+                source: "CHIP A { PARTS: B(a=x) & }",
+                expected: "1:24: unexpected character `&`",
+            },
+            Test {
+                source: "CHIP A { PARTS: }",
+                expected: "",
+            },
+            Test {
+                source: "CHIP A { PARTS: } CHIP",
+                expected: "1:19: expected end of file, found `CHIP`",
+            },
+            Test {
+                source: "CHIP A { IN a;",
+                expected: "1:15: expected `PARTS:` or `BUILTIN`, found end of file",
+            },
+        ];
+
+        for Test { source, expected } in tests {
+            match parse(source) {
+                Ok(_) => assert_eq!(expected, "", "{source}"),
+                Err(err) if expected.contains('\n') => {
+                    assert_eq!(err.annotate(source), expected, "{source}")
+                }
+                Err(err) => assert_eq!(err.to_string(), expected, "{source}"),
+            }
+        }
+    }
+}
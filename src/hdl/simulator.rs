@@ -0,0 +1,1144 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::{fmt, fs, io};
+
+use super::ast::{self, Body, PinRef, Value};
+use super::builtin::{builtin, Builtin, Kind, Sequential, BUFFER};
+use super::{parse, ParseError, Span};
+
+#[derive(Debug)]
+pub enum ChipError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Parse {
+        chip: String,
+        error: ParseError,
+    },
+    /// `Name.hdl` defines a chip with a different name.
+    WrongName {
+        chip: String,
+        span: Span,
+        found: String,
+    },
+    /// No `.hdl` file or builtin for the chip asked for.
+    NotFound {
+        chip: String,
+    },
+    /// A part that is neither an `.hdl` file nor a builtin.
+    UnknownChip {
+        chip: String,
+        span: Span,
+        name: String,
+    },
+    UnknownPin {
+        chip: String,
+        span: Span,
+        part: String,
+        pin: String,
+    },
+    /// `Chip::set` or `Chip::get` with a pin the chip does not have.
+    NoSuchPin {
+        chip: String,
+        pin: String,
+    },
+    DuplicatePin {
+        chip: String,
+        span: Span,
+        pin: String,
+    },
+    /// A part input, chip output or internal pin that nothing drives.
+    UnconnectedPin {
+        chip: String,
+        span: Span,
+        pin: String,
+    },
+    DrivenTwice {
+        chip: String,
+        span: Span,
+        pin: String,
+    },
+    /// A part output wired to a constant, a chip input or a sub-bus of an
+    /// internal pin.
+    CannotDrive {
+        chip: String,
+        span: Span,
+        pin: String,
+    },
+    WidthMismatch {
+        chip: String,
+        span: Span,
+        pin: String,
+        expected: usize,
+        found: usize,
+    },
+    BitsOutOfRange {
+        chip: String,
+        span: Span,
+        pin: String,
+        width: usize,
+    },
+    /// A pin declared wider than the 16-bit words `Chip` reads and writes.
+    PinTooWide {
+        chip: String,
+        span: Span,
+        pin: String,
+        width: usize,
+    },
+    RecursiveChip {
+        chip: String,
+        span: Span,
+    },
+    /// Parts whose outputs feed back into their own inputs without a clocked
+    /// chip in between, each given by its path from the top chip.
+    CombinationalLoop {
+        parts: Vec<String>,
+    },
+}
+
+impl fmt::Display for ChipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = |f: &mut fmt::Formatter<'_>, chip: &str, span: &Span| {
+            write!(f, "{chip}.hdl:{}:{}: ", span.line, span.column)
+        };
+        match self {
+            ChipError::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            ChipError::Parse { chip, error } => write!(f, "{chip}.hdl:{error}"),
+            ChipError::WrongName { chip, span, found } => {
+                at(f, chip, span)?;
+                write!(f, "file defines chip {found}")
+            }
+            ChipError::NotFound { chip } => write!(f, "no chip named {chip}"),
+            ChipError::UnknownChip { chip, span, name } => {
+                at(f, chip, span)?;
+                write!(f, "unknown chip {name}")
+            }
+            ChipError::UnknownPin {
+                chip,
+                span,
+                part,
+                pin,
+            } => {
+                at(f, chip, span)?;
+                write!(f, "{part} has no pin {pin}")
+            }
+            ChipError::NoSuchPin { chip, pin } => write!(f, "{chip} has no pin {pin}"),
+            ChipError::DuplicatePin { chip, span, pin } => {
+                at(f, chip, span)?;
+                write!(f, "pin {pin} is declared twice")
+            }
+            ChipError::UnconnectedPin { chip, span, pin } => {
+                at(f, chip, span)?;
+                write!(f, "pin {pin} is not connected")
+            }
+            ChipError::DrivenTwice { chip, span, pin } => {
+                at(f, chip, span)?;
+                write!(f, "pin {pin} is driven more than once")
+            }
+            ChipError::CannotDrive { chip, span, pin } => {
+                at(f, chip, span)?;
+                write!(f, "a part output cannot drive {pin}")
+            }
+            ChipError::WidthMismatch {
+                chip,
+                span,
+                pin,
+                expected,
+                found,
+            } => {
+                at(f, chip, span)?;
+                write!(f, "{pin} needs {expected} bits but is connected to {found}")
+            }
+            ChipError::BitsOutOfRange {
+                chip,
+                span,
+                pin,
+                width,
+            } => {
+                at(f, chip, span)?;
+                write!(f, "{pin} has only {width} bits")
+            }
+            ChipError::PinTooWide {
+                chip,
+                span,
+                pin,
+                width,
+            } => {
+                at(f, chip, span)?;
+                write!(
+                    f,
+                    "pin {pin} is {width} bits wide, at most 16 are supported"
+                )
+            }
+            ChipError::RecursiveChip { chip, span } => {
+                at(f, chip, span)?;
+                write!(f, "{chip} contains itself")
+            }
+            ChipError::CombinationalLoop { parts } => {
+                write!(f, "combinational loop through {}", parts.join(" -> "))
+            }
+        }
+    }
+}
+
+/// Where chips come from: `.hdl` files added directly or found in a
+/// directory as `Name.hdl`, falling back to the builtin chips.
+#[derive(Debug, Default)]
+pub struct Library {
+    dir: Option<PathBuf>,
+    chips: HashMap<String, Rc<ast::Chip>>,
+}
+
+#[derive(Clone)]
+enum Definition {
+    Builtin(&'static Builtin),
+    Hdl(Rc<ast::Chip>),
+}
+
+/// Pin names and widths, inputs then outputs.
+type Pins = (Vec<(String, usize)>, Vec<(String, usize)>);
+
+impl Definition {
+    fn pins(&self) -> Pins {
+        match self {
+            Definition::Builtin(builtin) => {
+                let pins = |pins: &[(&str, usize)]| {
+                    pins.iter()
+                        .map(|(name, width)| (name.to_string(), *width))
+                        .collect()
+                };
+                (pins(builtin.inputs), pins(builtin.outputs))
+            }
+            Definition::Hdl(chip) => {
+                let pins = |pins: &[ast::PinDecl]| {
+                    pins.iter()
+                        .map(|pin| (pin.name.name.clone(), pin.width))
+                        .collect()
+                };
+                (pins(&chip.inputs), pins(&chip.outputs))
+            }
+        }
+    }
+}
+
+impl Library {
+    /// Only the builtin chips.
+    pub fn new() -> Library {
+        Library::default()
+    }
+
+    /// Looks for parts in `dir` before the builtins.
+    pub fn open(dir: impl AsRef<Path>) -> Library {
+        Library {
+            dir: Some(dir.as_ref().to_path_buf()),
+            chips: HashMap::new(),
+        }
+    }
+
+    /// Adds the chip defined in `source`, ahead of any file or builtin of
+    /// the same name, and returns its name.
+    pub fn add(&mut self, source: &str) -> Result<String, ChipError> {
+        let chip = parse(source).map_err(|error| ChipError::Parse {
+            chip: "<source>".to_string(),
+            error,
+        })?;
+        let name = chip.name.name.clone();
+        self.chips.insert(name.clone(), Rc::new(chip));
+        Ok(name)
+    }
+
+    fn definition(&mut self, name: &str) -> Result<Option<Definition>, ChipError> {
+        if let Some(chip) = self.chips.get(name) {
+            return Ok(Some(Definition::Hdl(chip.clone())));
+        }
+
+        if let Some(path) = self.dir.as_ref().map(|dir| dir.join(format!("{name}.hdl"))) {
+            if path.is_file() {
+                let source =
+                    fs::read_to_string(&path).map_err(|error| ChipError::Io { path, error })?;
+                let chip = parse(&source).map_err(|error| ChipError::Parse {
+                    chip: name.to_string(),
+                    error,
+                })?;
+                if chip.name.name != name {
+                    return Err(ChipError::WrongName {
+                        chip: name.to_string(),
+                        span: chip.name.span,
+                        found: chip.name.name,
+                    });
+                }
+                let chip = Rc::new(chip);
+                self.chips.insert(name.to_string(), chip.clone());
+                return Ok(Some(Definition::Hdl(chip)));
+            }
+        }
+
+        Ok(builtin(name).map(Definition::Builtin))
+    }
+
+    /// Instantiates `name` and every part inside it, down to builtins.
+    pub fn build(&mut self, name: &str) -> Result<Chip, ChipError> {
+        let definition = self.definition(name)?.ok_or_else(|| ChipError::NotFound {
+            chip: name.to_string(),
+        })?;
+        let (inputs, outputs) = definition.pins();
+
+        let mut builder = Builder {
+            library: self,
+            nets: 2,
+            parts: Vec::new(),
+            stack: Vec::new(),
+        };
+        let mut pins = |pins: Vec<(String, usize)>| -> Vec<(String, Vec<usize>)> {
+            pins.into_iter()
+                .map(|(name, width)| (name, (0..width).map(|_| builder.net()).collect()))
+                .collect()
+        };
+        let inputs = pins(inputs);
+        let outputs = pins(outputs);
+        let nets =
+            |pins: &[(String, Vec<usize>)]| pins.iter().map(|(_, nets)| nets.clone()).collect();
+        builder.instantiate(&definition, nets(&inputs), nets(&outputs), name.to_string())?;
+
+        let mut values = vec![false; builder.nets];
+        values[1] = true;
+        let mut chip = Chip {
+            name: name.to_string(),
+            inputs,
+            outputs,
+            values,
+            parts: order(builder.parts, builder.nets)?,
+        };
+        chip.eval();
+        Ok(chip)
+    }
+}
+
+/// A builtin inside the chip being built, wired to nets by index. Net 0 is
+/// always false and net 1 always true.
+struct Part {
+    builtin: &'static Builtin,
+    logic: Logic,
+    inputs: Vec<Vec<usize>>,
+    outputs: Vec<Vec<usize>>,
+    path: String,
+}
+
+enum Logic {
+    Combinational(fn(&[u16]) -> Vec<u16>),
+    Sequential(Sequential),
+}
+
+/// What a name means inside a chip's `PARTS:`.
+enum Local {
+    Input(Vec<usize>),
+    /// The index of an output pin.
+    Output(usize),
+    Internal(Vec<usize>),
+}
+
+struct Builder<'a> {
+    library: &'a mut Library,
+    nets: usize,
+    parts: Vec<Part>,
+    /// The `.hdl` chips being expanded, to catch a chip containing itself.
+    stack: Vec<String>,
+}
+
+impl Builder<'_> {
+    fn net(&mut self) -> usize {
+        self.nets += 1;
+        self.nets - 1
+    }
+
+    fn push(
+        &mut self,
+        builtin: &'static Builtin,
+        inputs: Vec<Vec<usize>>,
+        outputs: Vec<Vec<usize>>,
+        path: String,
+    ) {
+        let logic = match builtin.kind {
+            Kind::Combinational(eval) => Logic::Combinational(eval),
+            Kind::Sequential(new) => Logic::Sequential(new()),
+        };
+        self.parts.push(Part {
+            builtin,
+            logic,
+            inputs,
+            outputs,
+            path,
+        });
+    }
+
+    /// Adds `definition` with its pins, in declaration order, on the given
+    /// nets.
+    fn instantiate(
+        &mut self,
+        definition: &Definition,
+        inputs: Vec<Vec<usize>>,
+        outputs: Vec<Vec<usize>>,
+        path: String,
+    ) -> Result<(), ChipError> {
+        let chip = match definition {
+            Definition::Builtin(builtin) => {
+                self.push(builtin, inputs, outputs, path);
+                return Ok(());
+            }
+            Definition::Hdl(chip) => chip.clone(),
+        };
+        let name = &chip.name.name;
+
+        let mut locals: HashMap<String, Local> = HashMap::new();
+        let declared = chip
+            .inputs
+            .iter()
+            .zip(inputs.iter().cloned().map(Local::Input));
+        let declared = declared.chain(
+            chip.outputs
+                .iter()
+                .zip((0..outputs.len()).map(Local::Output)),
+        );
+        for (pin, local) in declared {
+            if pin.width > 16 {
+                return Err(ChipError::PinTooWide {
+                    chip: name.clone(),
+                    span: pin.span,
+                    pin: pin.name.name.clone(),
+                    width: pin.width,
+                });
+            }
+            if locals.insert(pin.name.name.clone(), local).is_some() {
+                return Err(ChipError::DuplicatePin {
+                    chip: name.clone(),
+                    span: pin.span,
+                    pin: pin.name.name.clone(),
+                });
+            }
+        }
+
+        let parts = match &chip.body {
+            Body::Builtin(builtin) => {
+                return self.builtin_body(&chip, builtin, inputs, outputs, path)
+            }
+            Body::Parts(parts) => parts,
+        };
+        if self.stack.contains(name) {
+            return Err(ChipError::RecursiveChip {
+                chip: name.clone(),
+                span: chip.name.span,
+            });
+        }
+        self.stack.push(name.clone());
+
+        let bits = |pin: &PinRef, width: usize| -> Result<Range<usize>, ChipError> {
+            match pin.bits {
+                Some(bits) if bits.end >= width => Err(ChipError::BitsOutOfRange {
+                    chip: name.clone(),
+                    span: bits.span,
+                    pin: pin.name.name.clone(),
+                    width,
+                }),
+                Some(bits) => Ok(bits.start..bits.end + 1),
+                None => Ok(0..width),
+            }
+        };
+
+        // First every part output, so internal pins get their nets before
+        // anything reads them.
+        let mut driven: Vec<Vec<bool>> =
+            outputs.iter().map(|nets| vec![false; nets.len()]).collect();
+        let mut resolved = Vec::new();
+        for part in parts {
+            let definition = self.library.definition(&part.chip.name)?.ok_or_else(|| {
+                ChipError::UnknownChip {
+                    chip: name.clone(),
+                    span: part.chip.span,
+                    name: part.chip.name.clone(),
+                }
+            })?;
+            let (part_inputs, part_outputs) = definition.pins();
+            let mut out_nets: Vec<Vec<Option<usize>>> = part_outputs
+                .iter()
+                .map(|(_, width)| vec![None; *width])
+                .collect();
+
+            for connection in &part.connections {
+                let Some(idx) = part_outputs
+                    .iter()
+                    .position(|(pin, _)| *pin == connection.pin.name.name)
+                else {
+                    continue;
+                };
+                let range = bits(&connection.pin, part_outputs[idx].1)?;
+                let target = match &connection.value {
+                    Value::Pin(target) => target,
+                    Value::Constant(value, span) => {
+                        return Err(ChipError::CannotDrive {
+                            chip: name.clone(),
+                            span: *span,
+                            pin: value.to_string(),
+                        })
+                    }
+                };
+                let cannot_drive = || ChipError::CannotDrive {
+                    chip: name.clone(),
+                    span: target.span,
+                    pin: target.name.name.clone(),
+                };
+
+                match locals.get(&target.name.name) {
+                    Some(Local::Input(_)) => return Err(cannot_drive()),
+                    Some(Local::Internal(_)) => {
+                        return Err(ChipError::DrivenTwice {
+                            chip: name.clone(),
+                            span: target.span,
+                            pin: target.name.name.clone(),
+                        })
+                    }
+                    Some(Local::Output(output)) => {
+                        let output = *output;
+                        let target_range = bits(target, outputs[output].len())?;
+                        if target_range.len() != range.len() {
+                            return Err(ChipError::WidthMismatch {
+                                chip: name.clone(),
+                                span: connection.span,
+                                pin: target.name.name.clone(),
+                                expected: target_range.len(),
+                                found: range.len(),
+                            });
+                        }
+                        for (bit, target_bit) in range.zip(target_range) {
+                            if std::mem::replace(&mut driven[output][target_bit], true) {
+                                return Err(ChipError::DrivenTwice {
+                                    chip: name.clone(),
+                                    span: target.span,
+                                    pin: target.name.name.clone(),
+                                });
+                            }
+                            let net = outputs[output][target_bit];
+                            match out_nets[idx][bit] {
+                                // The part bit already drives another pin.
+                                Some(source) => self.push(
+                                    &BUFFER,
+                                    vec![vec![source]],
+                                    vec![vec![net]],
+                                    path.clone(),
+                                ),
+                                None => out_nets[idx][bit] = Some(net),
+                            }
+                        }
+                    }
+                    None if target.bits.is_some() => return Err(cannot_drive()),
+                    None => {
+                        let nets = range
+                            .map(|bit| *out_nets[idx][bit].get_or_insert_with(|| self.net()))
+                            .collect();
+                        locals.insert(target.name.name.clone(), Local::Internal(nets));
+                    }
+                }
+            }
+            resolved.push((definition, part_inputs, part_outputs, out_nets));
+        }
+
+        // Then every part input, and the parts themselves.
+        for (part, (definition, part_inputs, part_outputs, out_nets)) in parts.iter().zip(resolved)
+        {
+            let mut in_nets: Vec<Vec<Option<usize>>> = part_inputs
+                .iter()
+                .map(|(_, width)| vec![None; *width])
+                .collect();
+
+            for connection in &part.connections {
+                let pin = &connection.pin;
+                let Some(idx) = part_inputs
+                    .iter()
+                    .position(|(name, _)| *name == pin.name.name)
+                else {
+                    if part_outputs.iter().any(|(name, _)| *name == pin.name.name) {
+                        continue;
+                    }
+                    return Err(ChipError::UnknownPin {
+                        chip: name.clone(),
+                        span: pin.name.span,
+                        part: part.chip.name.clone(),
+                        pin: pin.name.name.clone(),
+                    });
+                };
+                let range = bits(pin, part_inputs[idx].1)?;
+
+                let nets = match &connection.value {
+                    Value::Constant(value, _) => vec![*value as usize; range.len()],
+                    Value::Pin(source) => {
+                        let nets = match locals.get(&source.name.name) {
+                            Some(Local::Input(nets) | Local::Internal(nets)) => nets,
+                            Some(Local::Output(output)) => &outputs[*output],
+                            None => {
+                                return Err(ChipError::UnconnectedPin {
+                                    chip: name.clone(),
+                                    span: source.span,
+                                    pin: source.name.name.clone(),
+                                })
+                            }
+                        };
+                        let nets = nets[bits(source, nets.len())?].to_vec();
+                        if nets.len() != range.len() {
+                            return Err(ChipError::WidthMismatch {
+                                chip: name.clone(),
+                                span: connection.span,
+                                pin: pin.name.name.clone(),
+                                expected: range.len(),
+                                found: nets.len(),
+                            });
+                        }
+                        nets
+                    }
+                };
+
+                for (bit, net) in range.zip(nets) {
+                    if in_nets[idx][bit].replace(net).is_some() {
+                        return Err(ChipError::DrivenTwice {
+                            chip: name.clone(),
+                            span: pin.span,
+                            pin: pin.name.name.clone(),
+                        });
+                    }
+                }
+            }
+
+            let mut part_in = Vec::new();
+            for (nets, (pin, _)) in in_nets.into_iter().zip(&part_inputs) {
+                let nets = nets.into_iter().collect::<Option<Vec<usize>>>();
+                part_in.push(nets.ok_or_else(|| ChipError::UnconnectedPin {
+                    chip: name.clone(),
+                    span: part.span,
+                    pin: format!("{}.{pin}", part.chip.name),
+                })?);
+            }
+            let part_out = out_nets
+                .into_iter()
+                .map(|nets| {
+                    nets.into_iter()
+                        .map(|net| net.unwrap_or_else(|| self.net()))
+                        .collect()
+                })
+                .collect();
+            let part_path = format!(
+                "{path}/{}@{}:{}",
+                part.chip.name, part.span.line, part.span.column
+            );
+            self.instantiate(&definition, part_in, part_out, part_path)?;
+        }
+
+        for (pin, driven) in chip.outputs.iter().zip(driven) {
+            if driven.contains(&false) {
+                return Err(ChipError::UnconnectedPin {
+                    chip: name.clone(),
+                    span: pin.span,
+                    pin: pin.name.name.clone(),
+                });
+            }
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// An `.hdl` chip whose body is `BUILTIN name;`, its pins matched to the
+    /// builtin's by name.
+    fn builtin_body(
+        &mut self,
+        chip: &ast::Chip,
+        builtin_name: &ast::Ident,
+        inputs: Vec<Vec<usize>>,
+        outputs: Vec<Vec<usize>>,
+        path: String,
+    ) -> Result<(), ChipError> {
+        let name = &chip.name.name;
+        let builtin = builtin(&builtin_name.name).ok_or_else(|| ChipError::UnknownChip {
+            chip: name.clone(),
+            span: builtin_name.span,
+            name: builtin_name.name.clone(),
+        })?;
+
+        let arrange = |declared: &[ast::PinDecl], nets: Vec<Vec<usize>>, pins: &[(&str, usize)]| {
+            if let Some(pin) = declared
+                .iter()
+                .find(|pin| !pins.iter().any(|(name, _)| *name == pin.name.name))
+            {
+                return Err(ChipError::UnknownPin {
+                    chip: name.clone(),
+                    span: pin.span,
+                    part: builtin.name.to_string(),
+                    pin: pin.name.name.clone(),
+                });
+            }
+            pins.iter()
+                .map(|(pin, width)| {
+                    let idx = declared
+                        .iter()
+                        .position(|declared| declared.name.name == *pin)
+                        .ok_or_else(|| ChipError::UnconnectedPin {
+                            chip: name.clone(),
+                            span: builtin_name.span,
+                            pin: pin.to_string(),
+                        })?;
+                    if declared[idx].width != *width {
+                        return Err(ChipError::WidthMismatch {
+                            chip: name.clone(),
+                            span: declared[idx].span,
+                            pin: pin.to_string(),
+                            expected: *width,
+                            found: declared[idx].width,
+                        });
+                    }
+                    Ok(nets[idx].clone())
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let inputs = arrange(&chip.inputs, inputs, builtin.inputs)?;
+        let outputs = arrange(&chip.outputs, outputs, builtin.outputs)?;
+        self.push(builtin, inputs, outputs, path);
+        Ok(())
+    }
+}
+
+/// Sorts the parts so each comes after those driving its combinational
+/// inputs, failing if they form a loop.
+fn order(parts: Vec<Part>, nets: usize) -> Result<Vec<Part>, ChipError> {
+    let mut driver = vec![None; nets];
+    for (idx, part) in parts.iter().enumerate() {
+        for net in part.outputs.iter().flatten() {
+            driver[*net] = Some(idx);
+        }
+    }
+    let dependencies: Vec<Vec<usize>> = parts
+        .iter()
+        .map(|part| {
+            part.builtin
+                .inputs
+                .iter()
+                .zip(&part.inputs)
+                .filter(|((pin, _), _)| !part.builtin.clocked.contains(pin))
+                .flat_map(|(_, nets)| nets)
+                .filter_map(|net| driver[*net])
+                .collect()
+        })
+        .collect();
+
+    let mut waiting: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+    let mut users = vec![Vec::new(); parts.len()];
+    for (idx, dependencies) in dependencies.iter().enumerate() {
+        for dependency in dependencies {
+            users[*dependency].push(idx);
+        }
+    }
+
+    let mut sorted: Vec<usize> = (0..parts.len()).filter(|idx| waiting[*idx] == 0).collect();
+    let mut next = 0;
+    while next < sorted.len() {
+        for user in &users[sorted[next]] {
+            waiting[*user] -= 1;
+            if waiting[*user] == 0 {
+                sorted.push(*user);
+            }
+        }
+        next += 1;
+    }
+
+    if sorted.len() < parts.len() {
+        // Walk back through unsorted dependencies until a part repeats.
+        let mut walk = vec![waiting
+            .iter()
+            .position(|waiting| *waiting > 0)
+            .expect("some part is unsorted")];
+        loop {
+            let last = *walk.last().expect("walk is never empty");
+            let previous = *dependencies[last]
+                .iter()
+                .find(|dependency| waiting[**dependency] > 0)
+                .expect("an unsorted part waits on another unsorted part");
+            if let Some(start) = walk.iter().position(|idx| *idx == previous) {
+                let parts = walk[start..]
+                    .iter()
+                    .rev()
+                    .map(|idx| parts[*idx].path.clone())
+                    .collect();
+                return Err(ChipError::CombinationalLoop { parts });
+            }
+            walk.push(previous);
+        }
+    }
+
+    let mut parts: Vec<Option<Part>> = parts.into_iter().map(Some).collect();
+    Ok(sorted
+        .into_iter()
+        .map(|idx| parts[idx].take().expect("each part is sorted once"))
+        .collect())
+}
+
+/// A chip built from `.hdl` definitions and builtins, ready to simulate.
+/// Pin values are words holding the pin's bits, least significant first.
+pub struct Chip {
+    name: String,
+    inputs: Vec<(String, Vec<usize>)>,
+    outputs: Vec<(String, Vec<usize>)>,
+    values: Vec<bool>,
+    parts: Vec<Part>,
+}
+
+fn read(values: &[bool], nets: &[usize]) -> u16 {
+    nets.iter()
+        .enumerate()
+        .fold(0, |acc, (idx, net)| acc | (values[*net] as u16) << idx)
+}
+
+impl Chip {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Input pin names and widths in declaration order.
+    pub fn inputs(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.inputs
+            .iter()
+            .map(|(name, nets)| (name.as_str(), nets.len()))
+    }
+
+    pub fn outputs(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.outputs
+            .iter()
+            .map(|(name, nets)| (name.as_str(), nets.len()))
+    }
+
+    /// Whether any part holds state, so the chip needs the clock.
+    pub fn is_clocked(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part.logic, Logic::Sequential(_)))
+    }
+
+    fn pin(&self, pin: &str, pins: &[(String, Vec<usize>)]) -> Result<Vec<usize>, ChipError> {
+        pins.iter()
+            .find(|(name, _)| name == pin)
+            .map(|(_, nets)| nets.clone())
+            .ok_or_else(|| ChipError::NoSuchPin {
+                chip: self.name.clone(),
+                pin: pin.to_string(),
+            })
+    }
+
+    /// Sets an input pin. Outputs follow on the next `eval`, `tick` or
+    /// `tock`.
+    pub fn set(&mut self, pin: &str, value: u16) -> Result<(), ChipError> {
+        for (idx, net) in self.pin(pin, &self.inputs)?.into_iter().enumerate() {
+            self.values[net] = (value >> idx) & 1 == 1;
+        }
+        Ok(())
+    }
+
+    /// The value of an input or output pin.
+    pub fn get(&self, pin: &str) -> Result<u16, ChipError> {
+        let nets = self
+            .pin(pin, &self.outputs)
+            .or_else(|_| self.pin(pin, &self.inputs))?;
+        Ok(read(&self.values, &nets))
+    }
+
+    /// Settles every combinational part given the inputs and the state of
+    /// the clocked parts.
+    pub fn eval(&mut self) {
+        for part in &self.parts {
+            let inputs: Vec<u16> = part
+                .inputs
+                .iter()
+                .map(|nets| read(&self.values, nets))
+                .collect();
+            let outputs = match &part.logic {
+                Logic::Combinational(eval) => eval(&inputs),
+                Logic::Sequential(state) => state.out(&inputs),
+            };
+            for (nets, value) in part.outputs.iter().zip(outputs) {
+                for (idx, net) in nets.iter().enumerate() {
+                    self.values[*net] = (value >> idx) & 1 == 1;
+                }
+            }
+        }
+    }
+
+    /// The rising edge: clocked parts sample their inputs, outputs stay.
+    pub fn tick(&mut self) {
+        self.eval();
+        for part in &mut self.parts {
+            if let Logic::Sequential(state) = &mut part.logic {
+                let inputs: Vec<u16> = part
+                    .inputs
+                    .iter()
+                    .map(|nets| read(&self.values, nets))
+                    .collect();
+                state.tick(&inputs);
+            }
+        }
+    }
+
+    /// The falling edge: clocked parts commit and the outputs follow.
+    pub fn tock(&mut self) {
+        for part in &mut self.parts {
+            if let Logic::Sequential(state) = &mut part.logic {
+                state.tock();
+            }
+        }
+        self.eval();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::chip::arithmetic::alu_control;
+    use crate::test_util::TempDir;
+
+    const XOR: &str = "
+CHIP Xor {
+    IN a, b;
+    OUT out;
+
+    PARTS:
+    Not(in=a, out=nota);
+    Not(in=b, out=notb);
+    And(a=a, b=notb, out=w1);
+    And(a=nota, b=b, out=w2);
+    Or(a=w1, b=w2, out=out);
+}";
+
+    fn build(sources: &[&str]) -> Result<Chip, ChipError> {
+        let mut library = Library::new();
+        let mut name = String::new();
+        for source in sources {
+            name = library.add(source)?;
+        }
+        library.build(&name)
+    }
+
+    #[test]
+    fn test_xor() -> Result<(), ChipError> {
+        let mut chip = build(&[XOR])?;
+
+        for (a, b) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            chip.set("a", a)?;
+            chip.set("b", b)?;
+            chip.eval();
+            assert_eq!(chip.get("out")?, a ^ b, "{a} {b}");
+        }
+        assert!(!chip.is_clocked());
+        Ok(())
+    }
+
+    #[test]
+    fn test_buses() -> Result<(), ChipError> {
+        // Swaps the bytes, also giving the new low byte on its own and
+        // whether the input's low byte was zero.
+        let mut chip = build(&["
+            CHIP Swap {
+                IN in[16];
+                OUT out[16], low[8], zero;
+                PARTS:
+                Or16(a[0..7]=in[8..15], a[8..15]=in[0..7], b=false, out=out, out[0..7]=low);
+                Or8Way(in=in[0..7], out=any);
+                Not(in=any, out=zero);
+            }"])?;
+
+        assert_eq!(
+            chip.outputs().collect::<Vec<_>>(),
+            [("out", 16), ("low", 8), ("zero", 1)]
+        );
+        chip.set("in", 0x12AB)?;
+        chip.eval();
+        assert_eq!(
+            (chip.get("out")?, chip.get("low")?, chip.get("zero")?),
+            (0xAB12, 0x12, 0)
+        );
+        chip.set("in", 0x3400)?;
+        chip.eval();
+        assert_eq!(chip.get("zero")?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_clocked() -> Result<(), ChipError> {
+        let mut chip = build(&["
+            CHIP Bit {
+                IN in, load;
+                OUT out;
+                PARTS:
+                Mux(a=dffout, b=in, sel=load, out=next);
+                DFF(in=next, out=dffout, out=out);
+            }"])?;
+        assert!(chip.is_clocked());
+
+        chip.set("in", 1)?;
+        chip.set("load", 1)?;
+        chip.tick();
+        assert_eq!(chip.get("out")?, 0);
+        chip.tock();
+        assert_eq!(chip.get("out")?, 1);
+
+        chip.set("in", 0)?;
+        chip.set("load", 0)?;
+        chip.tick();
+        chip.tock();
+        assert_eq!(chip.get("out")?, 1);
+
+        // RAM8's output follows the address without waiting for the clock.
+        let mut ram = Library::new().build("RAM8")?;
+        ram.set("in", 77)?;
+        ram.set("load", 1)?;
+        ram.set("address", 5)?;
+        ram.tick();
+        ram.tock();
+        ram.set("address", 4)?;
+        ram.eval();
+        assert_eq!(ram.get("out")?, 0);
+        ram.set("address", 5)?;
+        ram.eval();
+        assert_eq!(ram.get("out")?, 77);
+        Ok(())
+    }
+
+    #[test]
+    fn test_builtin_alu() -> Result<(), ChipError> {
+        let mut chip = Library::new().build("ALU")?;
+
+        for control in 0..64u16 {
+            chip.set("x", 1234)?;
+            chip.set("y", 0xFF00)?;
+            for (idx, pin) in ["no", "f", "ny", "zy", "nx", "zx"].into_iter().enumerate() {
+                chip.set(pin, (control >> idx) & 1)?;
+            }
+            chip.eval();
+
+            let (out, zr, ng) = alu_control(1234, 0xFF00, control as u8);
+            assert_eq!(chip.get("out")?, out, "{control:06b}");
+            assert_eq!((chip.get("zr")?, chip.get("ng")?), (zr as u16, ng as u16));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_directory() -> Result<(), ChipError> {
+        let dir = TempDir::new("hdl");
+        let write = |name: &str, source: &str| {
+            fs::write(dir.join(name), source).map_err(|error| ChipError::Io {
+                path: dir.join(name),
+                error,
+            })
+        };
+        write("Xor.hdl", XOR)?;
+        // And from Nand, overriding the builtin.
+        write(
+            "And.hdl",
+            "CHIP And { IN a, b; OUT out; PARTS: Nand(a=a, b=b, out=n); Not(in=n, out=out); }",
+        )?;
+        write("Not.hdl", "CHIP Not { IN in; OUT out; BUILTIN Not; }")?;
+        write("Or.hdl", "CHIP Nor { IN a, b; OUT out; BUILTIN Or; }")?;
+
+        let mut library = Library::open(dir.path());
+        let result = library.build("Xor");
+        assert!(
+            matches!(&result, Err(ChipError::WrongName { chip, found, .. }) if chip == "Or" && found == "Nor"),
+            "{:?}",
+            result.err()
+        );
+
+        write("Or.hdl", "CHIP Or { IN b, a; OUT out; BUILTIN Or; }")?;
+        let mut chip = Library::open(dir.path()).build("Xor")?;
+        chip.set("a", 1)?;
+        chip.eval();
+        assert_eq!(chip.get("out")?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        struct Test {
+            sources: Vec<&'static str>,
+            expected: &'static str,
+        }
+        let tests = vec![
+            Test {
+                sources: vec!["CHIP Loop { IN a; OUT out; PARTS: And(a=a, b=y, out=x); Not(in=x, out=y, out=out); }"],
+                expected: "combinational loop through Loop/And@1:35 -> Loop/Not@1:57",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a; OUT out; PARTS: And(a=a, out=out); }"],
+                expected: "A.hdl:1:32: pin And.b is not connected",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a; OUT out, other; PARTS: Not(in=a, out=out); }"],
+                expected: "A.hdl:1:25: pin other is not connected",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a; OUT out; PARTS: Not(in=w, out=out); }"],
+                expected: "A.hdl:1:39: pin w is not connected",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a[8]; OUT out[16]; PARTS: Not16(in=a, out=out); }"],
+                expected: "A.hdl:1:45: in needs 16 bits but is connected to 8",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a[16]; OUT out[8]; PARTS: Not16(in=a, out=out); }"],
+                expected: "A.hdl:1:51: out needs 8 bits but is connected to 16",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a[8]; OUT out; PARTS: Not(in=a[8], out=out); }"],
+                expected: "A.hdl:1:43: a has only 8 bits",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a[32]; OUT out; PARTS: Not(in=a[0], out=out); }"],
+                expected: "A.hdl:1:13: pin a is 32 bits wide, at most 16 are supported",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a; OUT out; PARTS: Nope(in=a, out=out); }"],
+                expected: "A.hdl:1:32: unknown chip Nope",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a; OUT out; PARTS: Not(x=a, out=out); }"],
+                expected: "A.hdl:1:36: Not has no pin x",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a; OUT out; PARTS: Not(in=a, out=out); Not(in=a, out=out); }"],
+                expected: "A.hdl:1:66: pin out is driven more than once",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a; OUT out; PARTS: Not(in=a, out=a); }"],
+                expected: "A.hdl:1:46: a part output cannot drive a",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a, a; OUT out; PARTS: }"],
+                expected: "A.hdl:1:16: pin a is declared twice",
+            },
+            Test {
+                sources: vec![
+                    "CHIP B { IN a; OUT out; PARTS: A(a=a, out=out); }",
+                    "CHIP A { IN a; OUT out; PARTS: B(a=a, out=out); }",
+                ],
+                expected: "A.hdl:1:6: A contains itself",
+            },
+            Test {
+                sources: vec!["CHIP A { IN a; OUT out; BUILTIN Not; }"],
+                expected: "A.hdl:1:13: Not has no pin a",
+            },
+        ];
+
+        for Test { sources, expected } in tests {
+            match build(&sources) {
+                Ok(_) => panic!("{sources:?} built"),
+                Err(err) => assert_eq!(err.to_string(), expected, "{sources:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,9 @@
+pub mod assembler;
+pub mod computer;
+pub mod disassembler;
+pub mod hdl;
+pub mod program;
+pub mod script;
+
+#[cfg(test)]
+mod test_util;
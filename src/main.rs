@@ -1,5 +1,205 @@
-mod computer;
+use std::path::Path;
+use std::time::Duration;
+use std::{env, fs, process};
+
+use nand2tetris::computer::chip::netlist::{Netlist, Report};
+use nand2tetris::computer::{Computer, Glyphs, TerminalView};
+use nand2tetris::script::Script;
+use nand2tetris::{assembler, disassembler, program};
 
 fn main() {
-    println!("Hello, world!");
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("asm") => assemble(&args[1..]),
+        Some("disasm") => disassemble(&args[1..]),
+        Some("dot") => export(&args[1..], Netlist::to_dot),
+        Some("verilog") => export(&args[1..], Netlist::to_verilog),
+        Some("blif") => export(&args[1..], Netlist::to_blif),
+        Some("report") => report(&args[1..]),
+        Some("test") => test(&args[1..]),
+        Some("screen") => screen(&args[1..]),
+        Some("watch") => watch(&args[1..]),
+        _ => Err(usage()),
+    };
+
+    if let Err(message) = result {
+        eprintln!("{message}");
+        process::exit(1);
+    }
+}
+
+fn usage() -> String {
+    [
+        "usage:",
+        "  nand2tetris asm <input.asm> [output.hack]",
+        "  nand2tetris disasm <input.hack> [output.asm]",
+        "  nand2tetris dot <chip> [output.dot]",
+        "  nand2tetris verilog <chip> [output.v]",
+        "  nand2tetris blif <chip> [output.blif]",
+        "  nand2tetris report [chip]",
+        "  nand2tetris test <script.tst>",
+        "  nand2tetris screen <program.hack> <cycles> <output.png|output.pbm>",
+        "  nand2tetris watch <program.hack> [--blocks] [--scale N] [--fps N] [--cycles N]",
+    ]
+    .join("\n")
+}
+
+fn assemble(args: &[String]) -> Result<(), String> {
+    let (input, output) = match args {
+        [input] => (Path::new(input), Path::new(input).with_extension("hack")),
+        [input, output] => (Path::new(input), Path::new(output).to_path_buf()),
+        _ => return Err(usage()),
+    };
+
+    let source = fs::read_to_string(input).map_err(|err| format!("{}: {err}", input.display()))?;
+    let words =
+        assembler::assemble(&source).map_err(|err| format!("{}: {err}", input.display()))?;
+
+    program::write_hack(&output, &words).map_err(|err| format!("{}: {err}", output.display()))
+}
+
+fn disassemble(args: &[String]) -> Result<(), String> {
+    let (input, output) = match args {
+        [input] => (Path::new(input), Path::new(input).with_extension("asm")),
+        [input, output] => (Path::new(input), Path::new(output).to_path_buf()),
+        _ => return Err(usage()),
+    };
+
+    let words = program::read_hack(input).map_err(|err| format!("{}: {err}", input.display()))?;
+
+    let source =
+        disassembler::disassemble(&words).map_err(|err| format!("{}: {err}", input.display()))?;
+
+    fs::write(&output, source).map_err(|err| format!("{}: {err}", output.display()))
+}
+
+fn report(args: &[String]) -> Result<(), String> {
+    let reports = match args {
+        [] => Report::catalog(),
+        [chip] => vec![find_chip(chip)?.report()],
+        _ => return Err(usage()),
+    };
+
+    println!(
+        "{:<10} {:>6} {:>6} {:>8}",
+        "chip", "nands", "depth", "fan-out"
+    );
+    for report in reports {
+        println!(
+            "{:<10} {:>6} {:>6} {:>8}",
+            report.chip, report.nand_count, report.depth, report.max_fan_out
+        );
+    }
+    Ok(())
+}
+
+fn test(args: &[String]) -> Result<(), String> {
+    let [script] = args else {
+        return Err(usage());
+    };
+
+    let outcome = Script::run_file(script).map_err(|err| format!("{script}: {err}"))?;
+    print!("{}", outcome.output);
+    if outcome.compared > 0 {
+        println!("End of script - Comparison ended successfully");
+    } else {
+        println!("End of script");
+    }
+    Ok(())
+}
+
+fn screen(args: &[String]) -> Result<(), String> {
+    let [program, cycles, output] = args else {
+        return Err(usage());
+    };
+    let cycles: u64 = cycles
+        .parse()
+        .map_err(|_| format!("invalid cycle count {cycles}"))?;
+
+    let mut computer = Computer::new();
+    computer
+        .load_hack(program)
+        .map_err(|err| format!("{program}: {err}"))?;
+    for _ in 0..cycles {
+        computer
+            .execute()
+            .map_err(|err| format!("{program}: cycle {}: {err}", computer.cycles()))?;
+    }
+
+    let screen = computer.ram().screen();
+    let output = Path::new(output);
+    let result = match output.extension().and_then(|extension| extension.to_str()) {
+        Some("pbm") => screen.save_pbm(output),
+        _ => screen.save_png(output),
+    };
+    result.map_err(|err| format!("{}: {err}", output.display()))
+}
+
+fn watch(args: &[String]) -> Result<(), String> {
+    let Some((program, mut flags)) = args.split_first() else {
+        return Err(usage());
+    };
+
+    let mut view = TerminalView::new();
+    let mut fps = 30;
+    let mut max_cycles = None;
+    while let Some((flag, rest)) = flags.split_first() {
+        let mut number = || -> Result<u64, String> {
+            let (value, rest) = rest.split_first().ok_or_else(usage)?;
+            flags = rest;
+            value
+                .parse()
+                .ok()
+                .filter(|value| *value > 0)
+                .ok_or_else(|| format!("invalid value {value} for {flag}"))
+        };
+        match flag.as_str() {
+            "--blocks" => {
+                view.set_glyphs(Glyphs::HalfBlocks);
+                flags = rest;
+            }
+            "--scale" => view.set_scale(number()? as usize),
+            "--fps" => fps = number()?,
+            "--cycles" => max_cycles = Some(number()?),
+            _ => return Err(usage()),
+        }
+    }
+
+    let mut computer = Computer::new();
+    computer
+        .load_hack(program)
+        .map_err(|err| format!("{program}: {err}"))?;
+    let refresh = Duration::from_secs_f64(1.0 / fps as f64);
+    let stop = view
+        .run(&mut computer, &mut std::io::stdout(), refresh, max_cycles)
+        .map_err(|err| err.to_string())?;
+    println!("\n{stop:?} after {} cycles", computer.cycles());
+    Ok(())
+}
+
+fn find_chip(chip: &str) -> Result<Netlist, String> {
+    Netlist::chip(chip).ok_or_else(|| {
+        let chips: Vec<&str> = Netlist::chip_names().collect();
+        format!("unknown chip {chip}, expected one of: {}", chips.join(", "))
+    })
+}
+
+fn export(args: &[String], render: fn(&Netlist) -> String) -> Result<(), String> {
+    let (chip, output) = match args {
+        [chip] => (chip, None),
+        [chip, output] => (chip, Some(Path::new(output))),
+        _ => return Err(usage()),
+    };
+
+    let netlist = find_chip(chip)?;
+
+    match output {
+        Some(output) => fs::write(output, render(&netlist))
+            .map_err(|err| format!("{}: {err}", output.display())),
+        None => {
+            print!("{}", render(&netlist));
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,245 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const WORD_BITS: usize = 16;
+
+#[derive(Debug)]
+pub enum ProgramError {
+    Io(io::Error),
+    InvalidLength {
+        line: usize,
+        length: usize,
+    },
+    InvalidCharacter {
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    OddByteCount(usize),
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgramError::Io(error) => write!(f, "{error}"),
+            ProgramError::InvalidLength { line, length } => {
+                write!(f, "line {line}: expected {WORD_BITS} bits, found {length}")
+            }
+            ProgramError::InvalidCharacter {
+                line,
+                column,
+                character,
+            } => write!(f, "line {line}:{column}: invalid character {character:?}"),
+            ProgramError::OddByteCount(count) => {
+                write!(f, "{count} bytes is not a whole number of words")
+            }
+        }
+    }
+}
+
+impl From<io::Error> for ProgramError {
+    fn from(value: io::Error) -> Self {
+        ProgramError::Io(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+/// Parses `.hack` text: one 16-character binary word per line, most
+/// significant bit first. Surrounding whitespace and blank lines are
+/// skipped.
+pub fn parse_hack(source: &str) -> Result<Vec<u16>, ProgramError> {
+    let mut program = Vec::new();
+
+    for (idx, raw) in source.lines().enumerate() {
+        let line = idx + 1;
+        let text = raw.trim();
+        let indent = raw.len() - raw.trim_start().len();
+
+        if text.is_empty() {
+            continue;
+        }
+
+        if let Some((column, character)) = text
+            .chars()
+            .enumerate()
+            .find(|(_, character)| !matches!(character, '0' | '1'))
+        {
+            return Err(ProgramError::InvalidCharacter {
+                line,
+                column: indent + column + 1,
+                character,
+            });
+        }
+
+        if text.len() != WORD_BITS {
+            return Err(ProgramError::InvalidLength {
+                line,
+                length: text.len(),
+            });
+        }
+
+        let word = text
+            .bytes()
+            .fold(0, |word, bit| word << 1 | u16::from(bit - b'0'));
+        program.push(word);
+    }
+
+    Ok(program)
+}
+
+pub fn format_hack(program: &[u16]) -> String {
+    program
+        .iter()
+        .map(|word| format!("{word:016b}\n"))
+        .collect()
+}
+
+pub fn decode_binary(bytes: &[u8], endian: Endian) -> Result<Vec<u16>, ProgramError> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(ProgramError::OddByteCount(bytes.len()));
+    }
+
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|pair| match endian {
+            Endian::Big => u16::from_be_bytes([pair[0], pair[1]]),
+            Endian::Little => u16::from_le_bytes([pair[0], pair[1]]),
+        })
+        .collect())
+}
+
+pub fn encode_binary(program: &[u16], endian: Endian) -> Vec<u8> {
+    program
+        .iter()
+        .flat_map(|word| match endian {
+            Endian::Big => word.to_be_bytes(),
+            Endian::Little => word.to_le_bytes(),
+        })
+        .collect()
+}
+
+pub fn read_hack(path: impl AsRef<Path>) -> Result<Vec<u16>, ProgramError> {
+    parse_hack(&fs::read_to_string(path)?)
+}
+
+pub fn write_hack(path: impl AsRef<Path>, program: &[u16]) -> Result<(), ProgramError> {
+    Ok(fs::write(path, format_hack(program))?)
+}
+
+pub fn read_binary(path: impl AsRef<Path>, endian: Endian) -> Result<Vec<u16>, ProgramError> {
+    decode_binary(&fs::read(path)?, endian)
+}
+
+pub fn write_binary(
+    path: impl AsRef<Path>,
+    program: &[u16],
+    endian: Endian,
+) -> Result<(), ProgramError> {
+    Ok(fs::write(path, encode_binary(program, endian))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_hack_round_trip() -> Result<(), ProgramError> {
+        let program = vec![0b0000000000000010, 0b1110110000010000, 0xFFFF, 0];
+        let text = format_hack(&program);
+
+        assert_eq!(
+            text,
+            "0000000000000010\n1110110000010000\n1111111111111111\n0000000000000000\n"
+        );
+        assert_eq!(parse_hack(&text)?, program);
+        assert_eq!(
+            parse_hack("0000000000000010\r\n\n1110110000010000")?,
+            program[..2]
+        );
+        assert_eq!(
+            parse_hack("  0000000000000010\n\t1110110000010000  \n")?,
+            program[..2]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hack_errors() {
+        assert!(matches!(
+            parse_hack("0000000000000010\n000000000000001x\n"),
+            Err(ProgramError::InvalidCharacter {
+                line: 2,
+                column: 16,
+                character: 'x'
+            })
+        ));
+        assert!(matches!(
+            parse_hack("  000000000000001x"),
+            Err(ProgramError::InvalidCharacter {
+                line: 1,
+                column: 18,
+                character: 'x'
+            })
+        ));
+        assert!(matches!(
+            parse_hack("0000000000000010\n\n000000000000001"),
+            Err(ProgramError::InvalidLength {
+                line: 3,
+                length: 15
+            })
+        ));
+        assert!(matches!(
+            parse_hack("00000000000000100"),
+            Err(ProgramError::InvalidLength {
+                line: 1,
+                length: 17
+            })
+        ));
+    }
+
+    #[test]
+    fn test_binary_round_trip() -> Result<(), ProgramError> {
+        let program = vec![0x1234, 0xABCD];
+
+        assert_eq!(
+            encode_binary(&program, Endian::Big),
+            vec![0x12, 0x34, 0xAB, 0xCD]
+        );
+        assert_eq!(
+            encode_binary(&program, Endian::Little),
+            vec![0x34, 0x12, 0xCD, 0xAB]
+        );
+        assert_eq!(
+            decode_binary(&encode_binary(&program, Endian::Little), Endian::Little)?,
+            program
+        );
+        assert!(matches!(
+            decode_binary(&[0x12, 0x34, 0x56], Endian::Big),
+            Err(ProgramError::OddByteCount(3))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_files() -> Result<(), ProgramError> {
+        let dir = TempDir::new("program");
+        let hack = dir.join("Add.hack");
+        let bin = dir.join("Add.bin");
+        let program = vec![0b0000000000000010, 0b1110110000010000];
+
+        write_hack(&hack, &program)?;
+        write_binary(&bin, &program, Endian::Big)?;
+
+        assert_eq!(read_hack(&hack)?, program);
+        assert_eq!(read_binary(&bin, Endian::Big)?, program);
+        Ok(())
+    }
+}
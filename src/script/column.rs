@@ -0,0 +1,193 @@
+use super::ScriptError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Binary,
+    Decimal,
+    Hex,
+    String,
+}
+
+/// One `output-list` entry, `name%Fleft.width.right`: the value is printed
+/// in `width` characters with `left` and `right` spaces around it. A bare
+/// `name` is `name%D1.6.1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub format: Format,
+    pub left: usize,
+    pub width: usize,
+    pub right: usize,
+}
+
+/// A value to print: a pin or register, or text such as the time.
+pub enum Cell {
+    Word(u16),
+    Text(String),
+}
+
+impl Column {
+    pub fn parse(line: usize, spec: &str) -> Result<Column, ScriptError> {
+        let invalid = || ScriptError::InvalidColumn {
+            line,
+            column: spec.to_string(),
+        };
+        let Some((name, format)) = spec.split_once('%') else {
+            return Ok(Column {
+                name: spec.to_string(),
+                format: Format::Decimal,
+                left: 1,
+                width: 6,
+                right: 1,
+            });
+        };
+        let mut chars = format.chars();
+        let format = match chars.next() {
+            Some('B') => Format::Binary,
+            Some('D') => Format::Decimal,
+            Some('X') => Format::Hex,
+            Some('S') => Format::String,
+            _ => return Err(invalid()),
+        };
+        let sizes: Vec<usize> = chars
+            .as_str()
+            .split('.')
+            .map(|size| size.parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        let [left, width, right] = sizes[..] else {
+            return Err(invalid());
+        };
+
+        Ok(Column {
+            name: name.to_string(),
+            format,
+            left,
+            width,
+            right,
+        })
+    }
+
+    fn total(&self) -> usize {
+        self.left + self.width + self.right
+    }
+
+    /// The name centred over the column, cut to fit if it is too long.
+    pub fn header(&self) -> String {
+        let name: String = self.name.chars().take(self.total()).collect();
+        let space = self.total() - name.chars().count();
+        format!(
+            "{}{name}{}",
+            " ".repeat(space / 2),
+            " ".repeat(space - space / 2)
+        )
+    }
+
+    pub fn cell(&self, cell: &Cell) -> String {
+        let width = self.width;
+        let value = match (cell, self.format) {
+            (Cell::Text(text), _) => format!("{text:<width$}"),
+            (Cell::Word(word), Format::Binary) => {
+                let bits = width.min(16);
+                let word = *word as u32 & ((1 << bits) - 1);
+                format!("{word:0width$b}")
+            }
+            (Cell::Word(word), Format::Hex) => {
+                let digits = width.min(4);
+                let word = *word as u32 & ((1 << (4 * digits)) - 1);
+                format!("{word:0width$X}")
+            }
+            (Cell::Word(word), Format::Decimal) => format!("{:>width$}", *word as i16),
+            (Cell::Word(word), Format::String) => format!("{word:<width$}"),
+        };
+        format!("{}{value}{}", " ".repeat(self.left), " ".repeat(self.right))
+    }
+}
+
+/// Joins cells into an output line, `|a|b|`.
+pub fn line(cells: impl Iterator<Item = String>) -> String {
+    let mut line = String::from("|");
+    for cell in cells {
+        line.push_str(&cell);
+        line.push('|');
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns() {
+        struct Test {
+            spec: &'static str,
+            cell: Cell,
+            header: &'static str,
+            expected: &'static str,
+        }
+        let tests = vec![
+            Test {
+                spec: "a%B3.1.3",
+                cell: Cell::Word(1),
+                header: "   a   ",
+                expected: "   1   ",
+            },
+            Test {
+                spec: "out%B1.16.1",
+                cell: Cell::Word(0x00F1),
+                header: "       out        ",
+                expected: " 0000000011110001 ",
+            },
+            Test {
+                spec: "sel%B2.3.2",
+                cell: Cell::Word(5),
+                header: "  sel  ",
+                expected: "  101  ",
+            },
+            Test {
+                spec: "out%D1.6.1",
+                cell: Cell::Word(0xFFFF),
+                header: "  out   ",
+                expected: "     -1 ",
+            },
+            Test {
+                spec: "address%D0.5.0",
+                cell: Cell::Word(12),
+                header: "addre",
+                expected: "   12",
+            },
+            Test {
+                spec: "in%X2.4.2",
+                cell: Cell::Word(0xBEEF),
+                header: "   in   ",
+                expected: "  BEEF  ",
+            },
+            Test {
+                spec: "time%S1.4.1",
+                cell: Cell::Text("3+".to_string()),
+                header: " time ",
+                expected: " 3+   ",
+            },
+        ];
+
+        for test in tests {
+            let column = Column::parse(1, test.spec).unwrap();
+            assert_eq!(column.header(), test.header, "{}", test.spec);
+            assert_eq!(column.cell(&test.cell), test.expected, "{}", test.spec);
+        }
+
+        assert!(matches!(
+            Column::parse(4, "a%Q1.1.1"),
+            Err(ScriptError::InvalidColumn { line: 4, .. })
+        ));
+        assert!(Column::parse(1, "a%B1.1").is_err());
+        assert_eq!(
+            Column::parse(1, "D").unwrap(),
+            Column::parse(1, "D%D1.6.1").unwrap()
+        );
+        assert_eq!(
+            line(["a".to_string(), "b".to_string()].into_iter()),
+            "|a|b|"
+        );
+    }
+}
@@ -0,0 +1,514 @@
+//! The course's test scripts: a `.tst` file loads a chip, sets its inputs,
+//! clocks it and prints an `.out` table that is checked line by line
+//! against a `.cmp` file.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use self::column::{Cell, Column};
+use self::parser::{Command, Operand, Step};
+use self::target::Target;
+use crate::assembler::AssemblerError;
+use crate::computer::chip::clock::ClockError;
+use crate::computer::Error;
+use crate::hdl::ChipError;
+
+mod column;
+mod parser;
+mod target;
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Syntax {
+        line: usize,
+        expected: &'static str,
+        found: String,
+    },
+    InvalidValue {
+        line: usize,
+        value: String,
+    },
+    InvalidColumn {
+        line: usize,
+        column: String,
+    },
+    /// A command that needs a chip before any `load`.
+    NoChip {
+        line: usize,
+    },
+    Chip {
+        line: usize,
+        error: ChipError,
+    },
+    Assembler {
+        line: usize,
+        error: AssemblerError,
+    },
+    Computer {
+        line: usize,
+        error: Error,
+    },
+    /// A name other than `A`, `D`, `PC` or `RAM[n]` on the computer.
+    UnknownVariable {
+        line: usize,
+        name: String,
+    },
+    Clock {
+        line: usize,
+        error: ClockError,
+    },
+    /// Output line `output_line` differs from the `.cmp` file.
+    Comparison {
+        line: usize,
+        output_line: usize,
+        expected: String,
+        found: String,
+    },
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            ScriptError::Syntax {
+                line,
+                expected,
+                found,
+            } => write!(f, "line {line}: expected {expected}, found {found}"),
+            ScriptError::InvalidValue { line, value } => {
+                write!(f, "line {line}: invalid value {value}")
+            }
+            ScriptError::InvalidColumn { line, column } => {
+                write!(f, "line {line}: invalid output column {column}")
+            }
+            ScriptError::NoChip { line } => write!(f, "line {line}: no chip loaded"),
+            ScriptError::Chip { line, error } => write!(f, "line {line}: {error}"),
+            ScriptError::Assembler { line, error } => {
+                write!(f, "line {line}: cannot assemble, {error}")
+            }
+            ScriptError::Computer { line, error } => write!(f, "line {line}: {error}"),
+            ScriptError::UnknownVariable { line, name } => {
+                write!(f, "line {line}: unknown variable {name}")
+            }
+            ScriptError::Clock { line, error } => write!(f, "line {line}: {error}"),
+            ScriptError::Comparison {
+                line,
+                output_line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: comparison failure at output line {output_line}\n  expected: {expected}\n  found:    {found}"
+            ),
+        }
+    }
+}
+
+/// What a script that ran to the end produced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    /// The output table.
+    pub output: String,
+    /// Output lines checked against the `compare-to` file.
+    pub compared: usize,
+}
+
+/// A parsed test script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    steps: Vec<Step>,
+}
+
+impl Script {
+    pub fn parse(source: &str) -> Result<Script, ScriptError> {
+        Ok(Script {
+            steps: parser::parse(source)?,
+        })
+    }
+
+    /// Reads and runs `path`, with files named in the script relative to
+    /// its directory.
+    pub fn run_file(path: impl AsRef<Path>) -> Result<Outcome, ScriptError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|error| ScriptError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        Script::parse(&source)?.run(dir)
+    }
+
+    /// Runs the script and returns its output. Chips are loaded from
+    /// `.hdl` files in `dir`, falling back to the builtins, and programs
+    /// from `.hack` or `.asm` files. The output file, if any, is written
+    /// even when a comparison fails.
+    pub fn run(&self, dir: impl AsRef<Path>) -> Result<Outcome, ScriptError> {
+        let mut run = Run {
+            dir: dir.as_ref(),
+            target: None,
+            columns: Vec::new(),
+            output: String::new(),
+            output_lines: 0,
+            output_file: None,
+            compare: None,
+            compared: 0,
+        };
+        let result = run.steps(&self.steps);
+
+        if let Some(path) = &run.output_file {
+            fs::write(path, &run.output).map_err(|error| ScriptError::Io {
+                path: path.clone(),
+                error,
+            })?;
+        }
+        result.map(|()| Outcome {
+            output: run.output,
+            compared: run.compared,
+        })
+    }
+}
+
+struct Run<'a> {
+    dir: &'a Path,
+    target: Option<Target>,
+    columns: Vec<Column>,
+    output: String,
+    output_lines: usize,
+    output_file: Option<PathBuf>,
+    compare: Option<Vec<String>>,
+    compared: usize,
+}
+
+impl Run<'_> {
+    fn steps(&mut self, steps: &[Step]) -> Result<(), ScriptError> {
+        for step in steps {
+            self.step(step)?;
+        }
+        Ok(())
+    }
+
+    fn target(&mut self, line: usize) -> Result<&mut Target, ScriptError> {
+        self.target.as_mut().ok_or(ScriptError::NoChip { line })
+    }
+
+    fn get(&mut self, line: usize, name: &str) -> Result<u16, ScriptError> {
+        self.target(line)?.get(line, name)
+    }
+
+    fn step(&mut self, step: &Step) -> Result<(), ScriptError> {
+        let line = step.line;
+        match &step.command {
+            Command::Load(file) => self.target = Some(Target::load(line, self.dir, file)?),
+            Command::OutputFile(file) => self.output_file = Some(self.dir.join(file)),
+            Command::CompareTo(file) => {
+                let path = self.dir.join(file);
+                let source =
+                    fs::read_to_string(&path).map_err(|error| ScriptError::Io { path, error })?;
+                self.compare = Some(source.lines().map(str::to_string).collect());
+            }
+            Command::OutputList(columns) => {
+                self.columns = columns.clone();
+                let header = column::line(self.columns.iter().map(Column::header));
+                self.emit(line, header)?;
+            }
+            Command::Set(name, value) => self.target(line)?.set(line, name, *value)?,
+            Command::Eval => self.target(line)?.eval(),
+            Command::Tick => self.target(line)?.tick(line)?,
+            Command::Tock => self.target(line)?.tock(line)?,
+            Command::TickTock => {
+                let target = self.target(line)?;
+                target.tick(line)?;
+                target.tock(line)?;
+            }
+            Command::Output => {
+                let mut cells = Vec::new();
+                for column in self.columns.clone() {
+                    let cell = match column.name.as_str() {
+                        "time" => Cell::Text(self.target(line)?.clock().to_string()),
+                        name => Cell::Word(self.get(line, name)?),
+                    };
+                    cells.push(column.cell(&cell));
+                }
+                self.emit(line, column::line(cells.into_iter()))?;
+            }
+            // Messages for the course's GUI; nothing to show here.
+            Command::Echo(_) | Command::ClearEcho => {}
+            Command::Repeat(count, body) => {
+                for _ in 0..*count {
+                    self.steps(body)?;
+                }
+            }
+            Command::While(condition, body) => loop {
+                let mut operand = |operand: &Operand| match operand {
+                    Operand::Name(pin) => self.get(line, pin),
+                    Operand::Value(value) => Ok(*value),
+                };
+                let (left, right) = (operand(&condition.left)?, operand(&condition.right)?);
+                if !condition.comparison.holds(left, right) {
+                    break;
+                }
+                self.steps(body)?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Appends a line to the output and checks it against the `.cmp` file,
+    /// where `*` matches any character.
+    fn emit(&mut self, line: usize, text: String) -> Result<(), ScriptError> {
+        self.output.push_str(&text);
+        self.output.push('\n');
+        self.output_lines += 1;
+
+        let Some(compare) = &self.compare else {
+            return Ok(());
+        };
+        let expected = compare
+            .get(self.output_lines - 1)
+            .map(|expected| expected.trim_end())
+            .unwrap_or("");
+        let found = text.trim_end();
+        let matches = expected.len() == found.len()
+            && expected
+                .chars()
+                .zip(found.chars())
+                .all(|(expected, found)| expected == '*' || expected == found);
+        match matches {
+            true => {
+                self.compared += 1;
+                Ok(())
+            }
+            false => Err(ScriptError::Comparison {
+                line,
+                output_line: self.output_lines,
+                expected: expected.to_string(),
+                found: found.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /// Writes `files` to a fresh directory, runs `Test.tst` from it and
+    /// returns the result along with `Test.out`, if written.
+    fn run(name: &str, files: &[(&str, &str)]) -> (Result<Outcome, ScriptError>, Option<String>) {
+        let dir = TempDir::new(&format!("script-{name}"));
+        for (file, contents) in files {
+            fs::write(dir.join(file), contents).unwrap();
+        }
+        let result = Script::run_file(dir.join("Test.tst"));
+        let out = fs::read_to_string(dir.join("Test.out")).ok();
+        (result, out)
+    }
+
+    const XOR_CMP: &str = "|   a   |   b   |  out  |
+|   0   |   0   |   0   |
+|   0   |   1   |   1   |
+|   1   |   0   |   1   |
+|   1   |   1   |   0   |
+";
+
+    #[test]
+    fn test_builtin() {
+        let tst = "load Xor.hdl, output-file Test.out, compare-to Test.cmp,
+output-list a%B3.1.3 b%B3.1.3 out%B3.1.3;
+set a 0, set b 0, eval, output;
+set a 0, set b 1, eval, output;
+set a 1, set b 0, eval, output;
+set a 1, set b 1, eval, output;";
+        let (result, out) = run("builtin", &[("Test.tst", tst), ("Test.cmp", XOR_CMP)]);
+        assert_eq!(
+            result.unwrap(),
+            Outcome {
+                output: XOR_CMP.to_string(),
+                compared: 5
+            }
+        );
+        assert_eq!(out.as_deref(), Some(XOR_CMP));
+    }
+
+    #[test]
+    fn test_hdl() {
+        let mux = "CHIP Mux4Way16 {
+    IN a[16], b[16], c[16], d[16], sel[2];
+    OUT out[16];
+    PARTS:
+    Mux16(a=a, b=b, sel=sel[0], out=ab);
+    Mux16(a=c, b=d, sel=sel[0], out=cd);
+    Mux16(a=ab, b=cd, sel=sel[1], out=out);
+}";
+        let tst = "load Mux4Way16.hdl, compare-to Test.cmp,
+output-list a%X1.4.1 d%X1.4.1 sel%B2.2.2 out%D1.6.1;
+set a %X1234, set b 2, set c -3, set d %XFFFF,
+set sel 0, eval, output;
+repeat 3 { set sel %B11, eval, output; }";
+        let cmp = "|  a   |  d   | sel  |  out   |
+| 1234 | FFFF |  00  |   4660 |
+| **** | FFFF |  11  |     -1 |
+| 1234 | FFFF |  11  |     -1 |
+| 1234 | FFFF |  11  |     -1 |
+";
+        let (result, out) = run(
+            "hdl",
+            &[("Mux4Way16.hdl", mux), ("Test.tst", tst), ("Test.cmp", cmp)],
+        );
+        assert_eq!(result.unwrap().output, cmp.replace("****", "1234"));
+        assert_eq!(out, None);
+    }
+
+    #[test]
+    fn test_clocked() {
+        let tst = "load PC.hdl, output-file Test.out,
+output-list time%S1.4.1 in%D1.6.1 reset%B2.1.2 load%B2.1.2 inc%B2.1.2 out%D1.6.1;
+set in -32123, set reset 0, set load 0, set inc 1,
+tick, output; tock, output;
+while out < 4 { tick, tock; }
+output;
+set load 1, tick, output, tock, output;";
+        let (result, out) = run("clocked", &[("Test.tst", tst)]);
+        let expected = "| time |   in   |reset|load | inc |  out   |
+| 0+   | -32123 |  0  |  0  |  1  |      0 |
+| 1    | -32123 |  0  |  0  |  1  |      1 |
+| 4    | -32123 |  0  |  0  |  1  |      4 |
+| 4+   | -32123 |  0  |  1  |  1  |      4 |
+| 5    | -32123 |  0  |  1  |  1  | -32123 |
+";
+        assert_eq!(
+            result.unwrap(),
+            Outcome {
+                output: expected.to_string(),
+                compared: 0
+            }
+        );
+        assert_eq!(out.as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn test_cpu() {
+        // @12345, D=A, M=D, then a reset.
+        let tst = "load CPU.hdl,
+output-list time%S1.4.1 inM%D1.6.1 instruction%B0.16.0 reset%B2.1.2 outM%D1.6.1 writeM%B3.1.3 addressM%D1.5.1 pc%D0.5.0;
+set instruction %B0011000000111001, tick, output; tock, output;
+set instruction %B1110110000010000, tick, output; tock, output;
+set instruction %B1110001100001000, tick, output; tock, output;
+set reset 1, tick, tock, output;";
+        let (result, _) = run("cpu", &[("Test.tst", tst)]);
+        let expected = "\
+| time |  inM   |  instruction   |reset|  outM  |writeM |address| pc  |
+| 0+   |      0 |0011000000111001|  0  |      0 |   0   |     0 |    0|
+| 1    |      0 |0011000000111001|  0  |      0 |   0   | 12345 |    1|
+| 1+   |      0 |1110110000010000|  0  |  12345 |   0   | 12345 |    1|
+| 2    |      0 |1110110000010000|  0  |  12345 |   0   | 12345 |    2|
+| 2+   |      0 |1110001100001000|  0  |  12345 |   1   | 12345 |    2|
+| 3    |      0 |1110001100001000|  0  |  12345 |   1   | 12345 |    3|
+| 4    |      0 |1110001100001000|  1  |  12345 |   1   | 12345 |    0|
+";
+        assert_eq!(result.unwrap().output, expected);
+    }
+
+    #[test]
+    fn test_program() {
+        // Counts RAM[0] up to RAM[1].
+        let asm = "(LOOP)\n@0\nM=M+1\nD=M\n@1\nD=D-M\n@LOOP\nD;JLT\n(END)\n@END\n0;JMP\n";
+        let tst = "load Count.asm, output-list RAM[0]%D1.3.1 PC%D1.2.1;
+set RAM[1] 3,
+while RAM[0] < 2 { ticktock; }
+output;
+tick, output, tock, output;
+set PC 0, set RAM[0] -5, set D 7,
+ticktock, output;
+set X 1;";
+        let (result, _) = run("program", &[("Count.asm", asm), ("Test.tst", tst)]);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "line 8: unknown variable X"
+        );
+
+        let (result, _) = run(
+            "program-output",
+            &[
+                ("Count.asm", asm),
+                ("Test.tst", &tst.replace("set X 1;", "")),
+            ],
+        );
+        assert_eq!(
+            result.unwrap().output,
+            "|RAM[0| PC |\n|   2 |  2 |\n|   2 |  2 |\n|   2 |  3 |\n|  -5 |  1 |\n"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        struct Test {
+            name: &'static str,
+            tst: &'static str,
+            expected: &'static str,
+        }
+        let tests = vec![
+            Test {
+                name: "mismatch",
+                tst: "load Xor.hdl, output-file Test.out, compare-to Test.cmp,
+output-list a%B3.1.3 b%B3.1.3 out%B3.1.3;
+set a 0, set b 0, eval, output;
+set a 0, set b 1, output;",
+                expected: "line 4: comparison failure at output line 3
+  expected: |   0   |   1   |   1   |
+  found:    |   0   |   1   |   0   |",
+            },
+            Test {
+                name: "pin",
+                tst: "load Xor.hdl,\nset c 1;",
+                expected: "line 2: Xor has no pin c",
+            },
+            Test {
+                name: "unloaded",
+                tst: "\neval;",
+                expected: "line 2: no chip loaded",
+            },
+            Test {
+                name: "tick",
+                tst: "load Bit.hdl, tick, tick;",
+                expected: "line 1: tick while the clock is high",
+            },
+            Test {
+                name: "assembler",
+                tst: "load Bad.asm;",
+                expected: "line 1: cannot assemble, line 2: invalid computation D*A",
+            },
+            Test {
+                name: "computer",
+                tst: "load Loop.asm,\nset RAM[30000] 1;",
+                expected: "line 2: address out of bounds: RAM Error on load",
+            },
+        ];
+
+        for test in tests {
+            let files = [
+                ("Test.tst", test.tst),
+                ("Test.cmp", XOR_CMP),
+                ("Bad.asm", "@0\nD=D*A\n"),
+                ("Loop.asm", "(LOOP)\n@LOOP\n0;JMP\n"),
+            ];
+            let (result, out) = run(test.name, &files);
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                test.expected,
+                "{}",
+                test.name
+            );
+            if test.name == "mismatch" {
+                assert_eq!(out.unwrap().lines().count(), 3);
+            }
+        }
+    }
+}
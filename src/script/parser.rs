@@ -0,0 +1,443 @@
+use super::column::Column;
+use super::ScriptError;
+
+/// A command with the line it starts on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub line: usize,
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Load(String),
+    OutputFile(String),
+    CompareTo(String),
+    OutputList(Vec<Column>),
+    Set(String, u16),
+    Eval,
+    Tick,
+    Tock,
+    TickTock,
+    Output,
+    Echo(String),
+    ClearEcho,
+    Repeat(usize, Vec<Step>),
+    While(Condition, Vec<Step>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Name(String),
+    Value(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessOrEqual,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub left: Operand,
+    pub comparison: Comparison,
+    pub right: Operand,
+}
+
+impl Comparison {
+    /// Compares the operands as signed 16-bit numbers.
+    pub fn holds(&self, left: u16, right: u16) -> bool {
+        let (left, right) = (left as i16, right as i16);
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::Greater => left > right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    /// One of `, ; { }`.
+    Symbol(char),
+    End,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(word) => format!("`{word}`"),
+            Token::Text(text) => format!("\"{text}\""),
+            Token::Symbol(symbol) => format!("`{symbol}`"),
+            Token::End => "end of script".to_string(),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ScriptError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    let mut line = 1;
+
+    while let Some((idx, c)) = chars.next() {
+        let rest = &source[idx..];
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '/' if rest.starts_with("//") => while chars.next_if(|(_, c)| *c != '\n').is_some() {},
+            '/' if rest.starts_with("/*") => {
+                let start = line;
+                let len = rest[2..].find("*/").ok_or(ScriptError::Syntax {
+                    line: start,
+                    expected: "`*/`",
+                    found: "end of script".to_string(),
+                })?;
+                line += rest[..len + 4].matches('\n').count();
+                while chars.next_if(|(next, _)| *next < idx + len + 4).is_some() {}
+            }
+            ',' | ';' | '{' | '}' => tokens.push((Token::Symbol(c), line)),
+            '"' => {
+                let len = rest[1..].find('"').ok_or(ScriptError::Syntax {
+                    line,
+                    expected: "`\"`",
+                    found: "end of script".to_string(),
+                })?;
+                tokens.push((Token::Text(rest[1..len + 1].to_string()), line));
+                line += rest[..len + 2].matches('\n').count();
+                while chars.next_if(|(next, _)| *next < idx + len + 2).is_some() {}
+            }
+            _ => {
+                let len = rest
+                    .find(|c: char| c.is_whitespace() || ",;{}\"".contains(c))
+                    .unwrap_or(rest.len());
+                tokens.push((Token::Word(rest[..len].to_string()), line));
+                while chars.next_if(|(next, _)| *next < idx + len).is_some() {}
+            }
+        }
+    }
+
+    tokens.push((Token::End, line));
+    Ok(tokens)
+}
+
+/// Parses a number as scripts write them: `5`, `-1`, `%B0101`, `%XFF` or
+/// `%D12`, as a 16-bit word.
+pub fn value(line: usize, text: &str) -> Result<u16, ScriptError> {
+    let (digits, radix) = match text.get(..2) {
+        Some("%B") => (&text[2..], 2),
+        Some("%X") => (&text[2..], 16),
+        Some("%D") => (&text[2..], 10),
+        _ => (text, 10),
+    };
+    match i32::from_str_radix(digits, radix) {
+        Ok(value) if (-32768..=65535).contains(&value) => Ok(value as u16),
+        _ => Err(ScriptError::InvalidValue {
+            line,
+            value: text.to_string(),
+        }),
+    }
+}
+
+/// Parses a test script into its commands. Commands end with `,` or `;`;
+/// `repeat n { ... }` and `while a <> b { ... }` group commands. The
+/// endless `repeat { ... }` of the CPU emulator is not supported, since a
+/// script run from the command line could never finish.
+pub fn parse(source: &str) -> Result<Vec<Step>, ScriptError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+    };
+    let steps = parser.steps()?;
+    match parser.peek() {
+        Token::End => Ok(steps),
+        _ => Err(parser.error("a command")),
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn line(&self) -> usize {
+        self.tokens[self.pos].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].0.clone();
+        if token != Token::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, expected: &'static str) -> ScriptError {
+        ScriptError::Syntax {
+            line: self.line(),
+            expected,
+            found: self.peek().describe(),
+        }
+    }
+
+    fn word(&mut self, expected: &'static str) -> Result<String, ScriptError> {
+        match self.peek() {
+            Token::Word(word) => {
+                let word = word.clone();
+                self.next();
+                Ok(word)
+            }
+            _ => Err(self.error(expected)),
+        }
+    }
+
+    fn symbol(&mut self, symbol: char, expected: &'static str) -> Result<(), ScriptError> {
+        match self.peek() {
+            Token::Symbol(found) if *found == symbol => {
+                self.next();
+                Ok(())
+            }
+            _ => Err(self.error(expected)),
+        }
+    }
+
+    /// Commands up to the end of the script or a closing `}`.
+    fn steps(&mut self) -> Result<Vec<Step>, ScriptError> {
+        let mut steps = Vec::new();
+        while let Token::Word(_) = self.peek() {
+            steps.push(self.step()?);
+        }
+        Ok(steps)
+    }
+
+    fn block(&mut self) -> Result<Vec<Step>, ScriptError> {
+        self.symbol('{', "`{`")?;
+        let steps = self.steps()?;
+        self.symbol('}', "a command or `}`")?;
+        Ok(steps)
+    }
+
+    fn step(&mut self) -> Result<Step, ScriptError> {
+        let line = self.line();
+        let command = match self.word("a command")?.as_str() {
+            "repeat" => {
+                if let Token::Symbol('{') = self.peek() {
+                    return Err(
+                        self.error("a repeat count (endless `repeat { ... }` is not supported)")
+                    );
+                }
+                let count = self.word("a repeat count")?;
+                let count = count.parse().map_err(|_| ScriptError::InvalidValue {
+                    line: self.line(),
+                    value: count,
+                })?;
+                return Ok(Step {
+                    line,
+                    command: Command::Repeat(count, self.block()?),
+                });
+            }
+            "while" => {
+                let left = self.operand()?;
+                let comparison = match self.word("a comparison")?.as_str() {
+                    "=" => Comparison::Equal,
+                    "<>" => Comparison::NotEqual,
+                    "<" => Comparison::Less,
+                    ">" => Comparison::Greater,
+                    "<=" => Comparison::LessOrEqual,
+                    ">=" => Comparison::GreaterOrEqual,
+                    _ => {
+                        self.pos -= 1;
+                        return Err(self.error("one of `= <> < > <= >=`"));
+                    }
+                };
+                let condition = Condition {
+                    left,
+                    comparison,
+                    right: self.operand()?,
+                };
+                return Ok(Step {
+                    line,
+                    command: Command::While(condition, self.block()?),
+                });
+            }
+            "load" => Command::Load(self.word("a file name")?),
+            "output-file" => Command::OutputFile(self.word("a file name")?),
+            "compare-to" => Command::CompareTo(self.word("a file name")?),
+            "output-list" => {
+                let mut columns = Vec::new();
+                while let Token::Word(spec) = self.peek() {
+                    columns.push(Column::parse(self.line(), spec)?);
+                    self.next();
+                }
+                Command::OutputList(columns)
+            }
+            "set" => {
+                let name = self.word("a pin name")?;
+                let line = self.line();
+                Command::Set(name, value(line, &self.word("a value")?)?)
+            }
+            "eval" => Command::Eval,
+            "tick" => Command::Tick,
+            "tock" => Command::Tock,
+            "ticktock" => Command::TickTock,
+            "output" => Command::Output,
+            "echo" => match self.peek() {
+                Token::Text(text) | Token::Word(text) => {
+                    let text = text.clone();
+                    self.next();
+                    Command::Echo(text)
+                }
+                _ => return Err(self.error("a message")),
+            },
+            "clear-echo" => Command::ClearEcho,
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("a command"));
+            }
+        };
+
+        match self.peek() {
+            Token::Symbol(',' | ';') => {
+                self.next();
+                Ok(Step { line, command })
+            }
+            _ => Err(self.error("`,` or `;`")),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, ScriptError> {
+        let line = self.line();
+        let word = self.word("a pin name or value")?;
+        match word.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '%') {
+            true => Ok(Operand::Value(value(line, &word)?)),
+            false => Ok(Operand::Name(word)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let steps = parse(
+            "/* Bit.tst */
+load Bit.hdl,
+output-file Bit.out, // results
+compare-to Bit.cmp,
+output-list time%S1.4.1 in%B2.1.2 out%B2.1.2;
+
+set in %B1, set load -1,
+repeat 2 {
+    tick, output;
+    tock, output;
+}
+while out <> %X10 { eval; }
+echo \"done, really\";",
+        )
+        .unwrap();
+
+        let lines: Vec<usize> = steps.iter().map(|step| step.line).collect();
+        assert_eq!(lines, [2, 3, 4, 5, 7, 7, 8, 12, 13]);
+        assert_eq!(steps[0].command, Command::Load("Bit.hdl".to_string()));
+        assert!(matches!(&steps[3].command, Command::OutputList(columns) if columns.len() == 3));
+        assert_eq!(steps[5].command, Command::Set("load".to_string(), 0xFFFF));
+        let Command::Repeat(2, body) = &steps[6].command else {
+            panic!("expected repeat");
+        };
+        let body: Vec<&Command> = body.iter().map(|step| &step.command).collect();
+        assert_eq!(
+            body,
+            [
+                &Command::Tick,
+                &Command::Output,
+                &Command::Tock,
+                &Command::Output
+            ]
+        );
+        assert_eq!(
+            steps[7].command,
+            Command::While(
+                Condition {
+                    left: Operand::Name("out".to_string()),
+                    comparison: Comparison::NotEqual,
+                    right: Operand::Value(16),
+                },
+                vec![Step {
+                    line: 12,
+                    command: Command::Eval
+                }]
+            )
+        );
+        assert_eq!(steps[8].command, Command::Echo("done, really".to_string()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        struct Test {
+            source: &'static str,
+            expected: &'static str,
+        }
+        let tests = vec![
+            Test {
+                source: "load Not.hdl\neval;",
+                expected: "line 2: expected `,` or `;`, found `eval`",
+            },
+            Test {
+                source: "set in %B12;",
+                expected: "line 1: invalid value %B12",
+            },
+            Test {
+                source: "set in 70000;",
+                expected: "line 1: invalid value 70000",
+            },
+            Test {
+                source: "tick;\necho",
+                expected: "line 2: expected a message, found end of script",
+            },
+            Test {
+                source: "echo ;",
+                expected: "line 1: expected a message, found `;`",
+            },
+            Test {
+                source: "repeat { ticktock; }",
+                expected: "line 1: expected a repeat count (endless `repeat { ... }` is not supported), found `{`",
+            },
+            Test {
+                source: "repeat 3 { tick;",
+                expected: "line 1: expected a command or `}`, found end of script",
+            },
+            Test {
+                source: "\n\nfrobnicate;",
+                expected: "line 3: expected a command, found `frobnicate`",
+            },
+            Test {
+                source: "while a ! 3 { eval; }",
+                expected: "line 1: expected one of `= <> < > <= >=`, found `!`",
+            },
+            Test {
+                source: "output-list a%B3.1;",
+                expected: "line 1: invalid output column a%B3.1",
+            },
+        ];
+
+        for Test { source, expected } in tests {
+            assert_eq!(parse(source).unwrap_err().to_string(), expected, "{source}");
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::path::Path;
+
+use super::ScriptError;
+use crate::assembler;
+use crate::computer::chip::clock::Clock;
+use crate::computer::{self, Computer};
+use crate::hdl::{Chip, Library};
+
+/// What a script drives: a chip from the hardware simulator, or the whole
+/// computer as in the CPU emulator.
+pub enum Target {
+    Chip(Chip, Clock),
+    /// Reads and sets `A`, `D`, `PC` and `RAM[n]`.
+    Computer(Box<Computer>),
+}
+
+impl Target {
+    /// Loads `file` from `dir`: `.hdl` builds a chip, `.hack` and `.asm`
+    /// load a program into a fresh computer.
+    pub fn load(line: usize, dir: &Path, file: &str) -> Result<Target, ScriptError> {
+        let path = Path::new(file);
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(file);
+        let computer_error = |error| ScriptError::Computer { line, error };
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("hack") => {
+                let mut computer = Box::new(Computer::new());
+                computer.load_hack(dir.join(file)).map_err(computer_error)?;
+                Ok(Target::Computer(computer))
+            }
+            Some("asm") => {
+                let path = dir.join(file);
+                let source = std::fs::read_to_string(&path)
+                    .map_err(|error| ScriptError::Io { path, error })?;
+                let words = assembler::assemble(&source)
+                    .map_err(|error| ScriptError::Assembler { line, error })?;
+                let mut computer = Box::new(Computer::new());
+                computer.load_program(words).map_err(computer_error)?;
+                Ok(Target::Computer(computer))
+            }
+            _ => {
+                let chip = Library::open(dir)
+                    .build(name)
+                    .map_err(|error| ScriptError::Chip { line, error })?;
+                Ok(Target::Chip(chip, Clock::new()))
+            }
+        }
+    }
+
+    pub fn clock(&self) -> Clock {
+        match self {
+            Target::Chip(_, clock) => *clock,
+            Target::Computer(computer) => computer.clock(),
+        }
+    }
+
+    pub fn get(&self, line: usize, name: &str) -> Result<u16, ScriptError> {
+        match self {
+            Target::Chip(chip, _) => chip
+                .get(name)
+                .map_err(|error| ScriptError::Chip { line, error }),
+            Target::Computer(computer) => match name {
+                "A" => Ok(computer.cpu().a_register),
+                "D" => Ok(computer.cpu().d_register),
+                "PC" => Ok(computer.pc()),
+                _ => computer
+                    .ram()
+                    .read(ram_address(line, name)?)
+                    .map_err(|error| ScriptError::Computer {
+                        line,
+                        error: error.into(),
+                    }),
+            },
+        }
+    }
+
+    pub fn set(&mut self, line: usize, name: &str, value: u16) -> Result<(), ScriptError> {
+        match self {
+            Target::Chip(chip, _) => chip
+                .set(name, value)
+                .map_err(|error| ScriptError::Chip { line, error }),
+            Target::Computer(computer) => {
+                match name {
+                    "A" => computer.cpu_mut().a_register = value,
+                    "D" => computer.cpu_mut().d_register = value,
+                    "PC" => computer.set_pc(value),
+                    _ => {
+                        let address = ram_address(line, name)?;
+                        computer.ram_mut().load(value, address).map_err(|error| {
+                            ScriptError::Computer {
+                                line,
+                                error: error.into(),
+                            }
+                        })?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn eval(&mut self) {
+        if let Target::Chip(chip, _) = self {
+            chip.eval();
+        }
+    }
+
+    pub fn tick(&mut self, line: usize) -> Result<(), ScriptError> {
+        match self {
+            Target::Chip(chip, clock) => {
+                clock
+                    .tick()
+                    .map_err(|error| ScriptError::Clock { line, error })?;
+                chip.tick();
+            }
+            Target::Computer(computer) => {
+                computer.tick().map_err(|error| error_at(line, error))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn tock(&mut self, line: usize) -> Result<(), ScriptError> {
+        match self {
+            Target::Chip(chip, clock) => {
+                clock
+                    .tock()
+                    .map_err(|error| ScriptError::Clock { line, error })?;
+                chip.tock();
+            }
+            Target::Computer(computer) => {
+                computer.tock().map_err(|error| error_at(line, error))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn error_at(line: usize, error: computer::Error) -> ScriptError {
+    match error {
+        computer::Error::Clock(error) => ScriptError::Clock { line, error },
+        error => ScriptError::Computer { line, error },
+    }
+}
+
+/// The address in `RAM[n]`.
+fn ram_address(line: usize, name: &str) -> Result<u16, ScriptError> {
+    name.strip_prefix("RAM[")
+        .and_then(|rest| rest.strip_suffix(']'))
+        .and_then(|address| address.parse().ok())
+        .ok_or_else(|| ScriptError::UnknownVariable {
+            line,
+            name: name.to_string(),
+        })
+}
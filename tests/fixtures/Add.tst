@@ -0,0 +1,17 @@
+// Runs Add.hack, which computes RAM[0] = 2 + 3, twice: the second run
+// starts over from PC 0 as a reset would.
+
+load Add.hack,
+compare-to Add.cmp,
+output-list time%S1.4.1 PC%D0.4.0 A D RAM[0]%D2.6.2;
+
+repeat 6 {
+    ticktock, output;
+}
+
+set PC 0,
+set RAM[0] 0,
+
+repeat 6 {
+    ticktock, output;
+}
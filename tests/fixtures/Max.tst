@@ -0,0 +1,20 @@
+// Runs Max.hack, which computes RAM[2] = max(RAM[0], RAM[1]), tracing the
+// first run and checking only the result of the second.
+
+load Max.hack,
+compare-to Max.cmp,
+output-list time%S1.4.1 PC%D0.4.0 A D RAM[0]%D2.6.2 RAM[1]%D2.6.2 RAM[2]%D2.6.2;
+
+set RAM[0] 3,
+set RAM[1] 5;
+repeat 12 {
+    ticktock, output;
+}
+
+set PC 0,
+set RAM[0] 23456,
+set RAM[1] 12345;
+repeat 14 {
+    ticktock;
+}
+output;